@@ -0,0 +1,137 @@
+//! SHA-256 digests of archives and entries that are trusted as-is, such as
+//! popular libraries and mods. Unlike the package-prefix library check,
+//! a digest only vouches for the exact bytes that were built into the list.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::ScanError;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Allowlist {
+    /// Whole-archive digests, to the label of the trusted source.
+    #[serde(default)]
+    pub archives: BTreeMap<String, String>,
+    /// Digests of individual entries, to the label of the archive they came from.
+    #[serde(default)]
+    pub entries: BTreeMap<String, String>,
+}
+
+impl Allowlist {
+    /// Merges every given list; the first label seen for a digest wins.
+    pub fn load(paths: &[PathBuf]) -> Result<Self, ScanError> {
+        let mut allowlist = Allowlist::default();
+        for path in paths {
+            let loaded: Allowlist = serde_json::from_slice(&std::fs::read(path)?)?;
+            allowlist.merge(loaded);
+        }
+        Ok(allowlist)
+    }
+
+    fn merge(&mut self, other: Allowlist) {
+        for (digest, label) in other.archives {
+            self.archives.entry(digest).or_insert(label);
+        }
+        for (digest, label) in other.entries {
+            self.entries.entry(digest).or_insert(label);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.archives.is_empty() && self.entries.is_empty()
+    }
+
+    /// The trusted source of these bytes, whether they were listed as an
+    /// archive or as an entry of one.
+    pub fn source(&self, sha256: &str) -> Option<&str> {
+        self.archives
+            .get(sha256)
+            .or_else(|| self.entries.get(sha256))
+            .map(String::as_str)
+    }
+
+    /// Adds a trusted archive and every file entry in it. Returns the number
+    /// of entries read.
+    #[cfg(all(feature = "cli", not(feature = "gui")))]
+    pub fn add_archive(&mut self, path: &std::path::Path, label: &str) -> Result<usize, ScanError> {
+        use std::io::Read;
+
+        use crate::utils::sha256_hex;
+
+        let data = std::fs::read(path)?;
+        self.archives
+            .entry(sha256_hex(&data))
+            .or_insert_with(|| label.to_string());
+
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(&data))?;
+        let mut added = 0;
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i)?;
+            if entry.is_dir() {
+                continue;
+            }
+            let mut buffer = Vec::with_capacity(entry.size() as usize);
+            entry.read_to_end(&mut buffer)?;
+            self.entries
+                .entry(sha256_hex(&buffer))
+                .or_insert_with(|| label.to_string());
+            added += 1;
+        }
+        Ok(added)
+    }
+
+    #[cfg(all(feature = "cli", not(feature = "gui")))]
+    pub fn save(&self, path: &std::path::Path) -> Result<(), ScanError> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::jar;
+    use crate::utils::sha256_hex;
+
+    #[test]
+    fn vouches_for_the_archive_and_its_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = jar(&[
+            ("p/A.class", b"first"),
+            ("p/", b""),
+            ("data.txt", b"second"),
+        ]);
+        let archive_path = dir.path().join("lib.jar");
+        std::fs::write(&archive_path, &archive).unwrap();
+
+        let mut allowlist = Allowlist::default();
+        assert_eq!(allowlist.add_archive(&archive_path, "lib 1.0").unwrap(), 2);
+        let list_path = dir.path().join("allowlist.json");
+        allowlist.save(&list_path).unwrap();
+
+        let loaded = Allowlist::load(&[list_path]).unwrap();
+        assert_eq!(loaded.source(&sha256_hex(&archive)), Some("lib 1.0"));
+        assert_eq!(loaded.source(&sha256_hex(b"second")), Some("lib 1.0"));
+        assert_eq!(loaded.source(&sha256_hex(b"third")), None);
+    }
+
+    #[test]
+    fn first_label_wins_when_lists_overlap() {
+        let dir = tempfile::tempdir().unwrap();
+        let digest = sha256_hex(b"shared");
+        let paths: Vec<PathBuf> = ["first", "second"]
+            .iter()
+            .map(|label| {
+                let path = dir.path().join(format!("{}.json", label));
+                let list = format!(r#"{{"entries": {{"{}": "{}"}}}}"#, digest, label);
+                std::fs::write(&path, list).unwrap();
+                path
+            })
+            .collect();
+
+        let allowlist = Allowlist::load(&paths).unwrap();
+        assert_eq!(allowlist.source(&digest), Some("first"));
+    }
+}
@@ -0,0 +1,117 @@
+use wildmatch::WildMatch;
+
+use crate::types::{Annotation, ClassDetails, ElementValue};
+
+/// A user rule such as `org.spongepowered.asm.mixin.Mixin`,
+/// `*.EventHandler` or `Inject:method=*getSession*`. The type may be
+/// dotted, slashed or a bare simple name and may use `*`/`?` wildcards;
+/// the optional element value is matched the same way against each value
+/// of that element.
+#[derive(Debug, Clone)]
+pub struct AnnotationRule {
+    pub source: String,
+    type_pattern: WildMatch,
+    simple_name: bool,
+    element: Option<(String, WildMatch)>,
+}
+
+impl AnnotationRule {
+    #[cfg(all(feature = "cli", not(feature = "gui")))]
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let (type_part, element) = match rule.split_once(':') {
+            Some((type_part, condition)) => {
+                let (name, value) = condition
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected ELEMENT=VALUE after ':' in '{}'", rule))?;
+                if name.trim().is_empty() {
+                    return Err(format!("empty element name in '{}'", rule));
+                }
+                (
+                    type_part,
+                    Some((name.trim().to_string(), WildMatch::new(value.trim()))),
+                )
+            }
+            None => (rule, None),
+        };
+        let type_part = type_part.trim().trim_start_matches('@');
+        if type_part.is_empty() {
+            return Err(format!("empty annotation type in '{}'", rule));
+        }
+        let type_name = type_part
+            .strip_prefix('L')
+            .and_then(|t| t.strip_suffix(';'))
+            .unwrap_or(type_part)
+            .replace('.', "/");
+        Ok(AnnotationRule {
+            source: rule.to_string(),
+            simple_name: !type_name.contains('/'),
+            type_pattern: WildMatch::new(&type_name),
+            element,
+        })
+    }
+
+    pub fn matches(&self, annotation: &Annotation) -> bool {
+        let type_name = annotation.type_name();
+        let candidate = if self.simple_name {
+            type_name.rsplit(['/', '$']).next().unwrap_or(type_name)
+        } else {
+            type_name
+        };
+        if !self.type_pattern.matches(candidate) {
+            return false;
+        }
+        let Some((name, pattern)) = &self.element else {
+            return true;
+        };
+        annotation
+            .element(name)
+            .is_some_and(|value| value_matches(value, pattern))
+    }
+}
+
+fn value_matches(value: &ElementValue, pattern: &WildMatch) -> bool {
+    match value {
+        ElementValue::Array(values) => values.iter().any(|v| value_matches(v, pattern)),
+        ElementValue::Annotation(annotation) => pattern.matches(&annotation.to_string()),
+        other => other.text().is_some_and(|text| {
+            pattern.matches(&text) || pattern.matches(&text.replace('/', "."))
+        }),
+    }
+}
+
+impl ClassDetails {
+    /// Every annotation in the class with where it sits, e.g. `class`,
+    /// `field token` or `parameter 0 of method login(Ljava/lang/String;)V`.
+    pub fn annotation_sites(&self) -> Vec<(String, &Annotation)> {
+        let mut sites: Vec<(String, &Annotation)> = self
+            .annotations
+            .iter()
+            .map(|a| ("class".to_string(), a))
+            .collect();
+        for field in &self.fields {
+            sites.extend(
+                field
+                    .annotations
+                    .iter()
+                    .map(|a| (format!("field {}", field.name), a)),
+            );
+        }
+        for method in &self.methods {
+            let signature = format!("{}{}", method.name, method.descriptor);
+            sites.extend(
+                method
+                    .annotations
+                    .iter()
+                    .map(|a| (format!("method {}", signature), a)),
+            );
+            for (index, parameter) in method.parameter_annotations.iter().enumerate() {
+                sites.extend(
+                    parameter
+                        .iter()
+                        .map(|a| (format!("parameter {} of method {}", index, signature), a)),
+                );
+            }
+        }
+        sites
+    }
+}
@@ -0,0 +1,463 @@
+use crate::types::{ClassDetails, ConstantPoolEntry};
+
+pub const ACONST_NULL: u8 = 0x01;
+pub const ICONST_M1: u8 = 0x02;
+pub const ICONST_5: u8 = 0x08;
+pub const LCONST_0: u8 = 0x09;
+pub const LCONST_1: u8 = 0x0a;
+pub const BIPUSH: u8 = 0x10;
+pub const SIPUSH: u8 = 0x11;
+pub const LDC: u8 = 0x12;
+pub const LDC_W: u8 = 0x13;
+pub const LDC2_W: u8 = 0x14;
+pub const ILOAD: u8 = 0x15;
+pub const ALOAD: u8 = 0x19;
+pub const ILOAD_0: u8 = 0x1a;
+pub const ALOAD_3: u8 = 0x2d;
+pub const IALOAD: u8 = 0x2e;
+pub const SALOAD: u8 = 0x35;
+pub const ISTORE: u8 = 0x36;
+pub const ASTORE: u8 = 0x3a;
+pub const ISTORE_0: u8 = 0x3b;
+pub const ASTORE_3: u8 = 0x4e;
+pub const IASTORE: u8 = 0x4f;
+pub const BASTORE: u8 = 0x54;
+pub const CASTORE: u8 = 0x55;
+pub const SASTORE: u8 = 0x56;
+pub const POP: u8 = 0x57;
+pub const POP2: u8 = 0x58;
+pub const DUP: u8 = 0x59;
+pub const SWAP: u8 = 0x5f;
+pub const IINC: u8 = 0x84;
+pub const IFEQ: u8 = 0x99;
+pub const GOTO: u8 = 0xa7;
+pub const JSR: u8 = 0xa8;
+pub const RET: u8 = 0xa9;
+pub const TABLESWITCH: u8 = 0xaa;
+pub const LOOKUPSWITCH: u8 = 0xab;
+pub const IRETURN: u8 = 0xac;
+pub const RETURN: u8 = 0xb1;
+pub const GETSTATIC: u8 = 0xb2;
+pub const PUTSTATIC: u8 = 0xb3;
+pub const GETFIELD: u8 = 0xb4;
+pub const PUTFIELD: u8 = 0xb5;
+pub const INVOKEVIRTUAL: u8 = 0xb6;
+pub const INVOKESTATIC: u8 = 0xb8;
+pub const INVOKEINTERFACE: u8 = 0xb9;
+pub const INVOKEDYNAMIC: u8 = 0xba;
+pub const NEW: u8 = 0xbb;
+pub const NEWARRAY: u8 = 0xbc;
+pub const ANEWARRAY: u8 = 0xbd;
+pub const ARRAYLENGTH: u8 = 0xbe;
+pub const ATHROW: u8 = 0xbf;
+pub const CHECKCAST: u8 = 0xc0;
+pub const INSTANCEOF: u8 = 0xc1;
+pub const WIDE: u8 = 0xc4;
+pub const MULTIANEWARRAY: u8 = 0xc5;
+pub const IFNULL: u8 = 0xc6;
+pub const IFNONNULL: u8 = 0xc7;
+pub const GOTO_W: u8 = 0xc8;
+pub const JSR_W: u8 = 0xc9;
+
+pub const T_CHAR: u8 = 5;
+pub const T_BYTE: u8 = 8;
+pub const T_SHORT: u8 = 9;
+pub const T_INT: u8 = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    None,
+    Int(i32),
+    Local(u16),
+    Constant(u16),
+    Iinc(u16, i16),
+    Branch(i32),
+    TableSwitch {
+        default: i32,
+        low: i32,
+        targets: Vec<i32>,
+    },
+    LookupSwitch {
+        default: i32,
+        pairs: Vec<(i32, i32)>,
+    },
+    NewArray(u8),
+    MultiANewArray(u16, u8),
+}
+
+#[derive(Debug, Clone)]
+pub struct Instruction {
+    pub offset: usize,
+    pub opcode: u8,
+    pub operand: Operand,
+}
+
+impl Instruction {
+    pub fn is_branch(&self) -> bool {
+        matches!(self.opcode, IFEQ..=JSR | IFNULL..=JSR_W)
+    }
+
+    pub fn ends_block(&self) -> bool {
+        self.is_branch()
+            || matches!(
+                self.opcode,
+                RET | TABLESWITCH | LOOKUPSWITCH | IRETURN..=RETURN | ATHROW
+            )
+    }
+
+    pub fn falls_through(&self) -> bool {
+        !matches!(
+            self.opcode,
+            GOTO | GOTO_W | RET | TABLESWITCH | LOOKUPSWITCH | IRETURN..=RETURN | ATHROW
+        )
+    }
+
+    pub fn branch_targets(&self) -> Vec<i32> {
+        match &self.operand {
+            Operand::Branch(target) => vec![*target],
+            Operand::TableSwitch {
+                default, targets, ..
+            } => std::iter::once(*default)
+                .chain(targets.iter().copied())
+                .collect(),
+            Operand::LookupSwitch { default, pairs } => std::iter::once(*default)
+                .chain(pairs.iter().map(|&(_, target)| target))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+struct CodeReader<'a> {
+    code: &'a [u8],
+    pos: usize,
+}
+
+impl CodeReader<'_> {
+    fn u8(&mut self) -> Option<u8> {
+        let b = *self.code.get(self.pos)?;
+        self.pos += 1;
+        Some(b)
+    }
+
+    fn i8(&mut self) -> Option<i8> {
+        self.u8().map(|b| b as i8)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_be_bytes([self.u8()?, self.u8()?]))
+    }
+
+    fn i16(&mut self) -> Option<i16> {
+        self.u16().map(|v| v as i16)
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        Some(i32::from_be_bytes([
+            self.u8()?,
+            self.u8()?,
+            self.u8()?,
+            self.u8()?,
+        ]))
+    }
+}
+
+/// Decodes a method body into instructions. Decoding stops at the first
+/// truncated or invalid instruction, so malformed code yields a prefix.
+pub fn decode(code: &[u8]) -> Vec<Instruction> {
+    let mut reader = CodeReader { code, pos: 0 };
+    let mut instructions = Vec::new();
+
+    while reader.pos < code.len() {
+        let offset = reader.pos;
+        match decode_one(&mut reader, offset) {
+            Some((opcode, operand)) => instructions.push(Instruction {
+                offset,
+                opcode,
+                operand,
+            }),
+            None => break,
+        }
+    }
+
+    instructions
+}
+
+fn decode_one(reader: &mut CodeReader, offset: usize) -> Option<(u8, Operand)> {
+    let opcode = reader.u8()?;
+    let base = offset as i32;
+
+    let operand = match opcode {
+        0x00..=0x0f => Operand::None,
+        BIPUSH => Operand::Int(reader.i8()? as i32),
+        SIPUSH => Operand::Int(reader.i16()? as i32),
+        LDC => Operand::Constant(reader.u8()? as u16),
+        LDC_W | LDC2_W => Operand::Constant(reader.u16()?),
+        ILOAD..=ALOAD | ISTORE..=ASTORE | RET => Operand::Local(reader.u8()? as u16),
+        ILOAD_0..=SALOAD | ISTORE_0..=0x83 | 0x85..=0x98 => Operand::None,
+        IINC => Operand::Iinc(reader.u8()? as u16, reader.i8()? as i16),
+        IFEQ..=JSR | IFNULL | IFNONNULL => Operand::Branch(base + reader.i16()? as i32),
+        GOTO_W | JSR_W => Operand::Branch(base + reader.i32()?),
+        TABLESWITCH => {
+            reader.pos = (offset + 4) & !3;
+            let default = base + reader.i32()?;
+            let low = reader.i32()?;
+            let high = reader.i32()?;
+            if high < low || (high as i64 - low as i64) > 0xffff {
+                return None;
+            }
+            let mut targets = Vec::with_capacity((high - low + 1) as usize);
+            for _ in low..=high {
+                targets.push(base + reader.i32()?);
+            }
+            Operand::TableSwitch {
+                default,
+                low,
+                targets,
+            }
+        }
+        LOOKUPSWITCH => {
+            reader.pos = (offset + 4) & !3;
+            let default = base + reader.i32()?;
+            let npairs = reader.i32()?;
+            if !(0..=0xffff).contains(&npairs) {
+                return None;
+            }
+            let mut pairs = Vec::with_capacity(npairs as usize);
+            for _ in 0..npairs {
+                pairs.push((reader.i32()?, base + reader.i32()?));
+            }
+            Operand::LookupSwitch { default, pairs }
+        }
+        IRETURN..=RETURN | ARRAYLENGTH | ATHROW | 0xc2 | 0xc3 => Operand::None,
+        GETSTATIC..=INVOKESTATIC | NEW | ANEWARRAY | CHECKCAST | INSTANCEOF => {
+            Operand::Constant(reader.u16()?)
+        }
+        INVOKEINTERFACE | INVOKEDYNAMIC => {
+            let index = reader.u16()?;
+            reader.u16()?;
+            Operand::Constant(index)
+        }
+        NEWARRAY => Operand::NewArray(reader.u8()?),
+        WIDE => {
+            let modified = reader.u8()?;
+            let index = reader.u16()?;
+            match modified {
+                IINC => {
+                    let delta = reader.i16()?;
+                    return Some((IINC, Operand::Iinc(index, delta)));
+                }
+                ILOAD..=ALOAD | ISTORE..=ASTORE | RET => {
+                    return Some((modified, Operand::Local(index)));
+                }
+                _ => return None,
+            }
+        }
+        MULTIANEWARRAY => Operand::MultiANewArray(reader.u16()?, reader.u8()?),
+        _ => return None,
+    };
+
+    Some((opcode, operand))
+}
+
+/// Local variable slot addressed by a load/store, including the `_0.._3` forms.
+pub fn local_slot(instruction: &Instruction) -> Option<u16> {
+    match (instruction.opcode, &instruction.operand) {
+        (_, Operand::Local(index)) => Some(*index),
+        (ILOAD_0..=ALOAD_3, _) => Some(((instruction.opcode - ILOAD_0) % 4) as u16),
+        (ISTORE_0..=ASTORE_3, _) => Some(((instruction.opcode - ISTORE_0) % 4) as u16),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstantValue<'a> {
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(&'a str),
+    Class(&'a str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemberRef<'a> {
+    pub owner: &'a str,
+    pub name: &'a str,
+    pub descriptor: &'a str,
+}
+
+impl ClassDetails {
+    pub fn constant(&self, index: u16) -> Option<&ConstantPoolEntry> {
+        self.constant_pool.get((index as usize).checked_sub(1)?)
+    }
+
+    pub fn utf8_constant(&self, index: u16) -> Option<&str> {
+        match self.constant(index)? {
+            ConstantPoolEntry::Utf8(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn class_constant(&self, index: u16) -> Option<&str> {
+        match self.constant(index)? {
+            ConstantPoolEntry::Class(name_index) => self.utf8_constant(*name_index),
+            _ => None,
+        }
+    }
+
+    /// Classes named by `CONSTANT_Class` entries, with array types unwrapped.
+    pub fn referenced_classes(&self) -> impl Iterator<Item = &str> {
+        self.constant_pool.iter().filter_map(|entry| match entry {
+            ConstantPoolEntry::Class(name_index) => {
+                let name = self.utf8_constant(*name_index)?.trim_start_matches('[');
+                match name.strip_prefix('L') {
+                    Some(object) if name.ends_with(';') => object.strip_suffix(';'),
+                    _ if name.len() == 1 => None,
+                    _ => Some(name),
+                }
+            }
+            _ => None,
+        })
+    }
+
+    /// Literals loadable with `ldc`, as opposed to every Utf8 entry in `strings`.
+    pub fn string_constants(&self) -> impl Iterator<Item = &str> {
+        self.constant_pool.iter().filter_map(|entry| match entry {
+            ConstantPoolEntry::String(utf8_index) => self.utf8_constant(*utf8_index),
+            _ => None,
+        })
+    }
+
+    /// Every resolvable Methodref and InterfaceMethodref entry.
+    #[cfg(all(feature = "cli", not(feature = "gui")))]
+    pub fn method_refs(&self) -> impl Iterator<Item = MemberRef<'_>> {
+        (1..=self.constant_pool.len() as u16)
+            .filter(|&index| {
+                matches!(
+                    self.constant(index),
+                    Some(ConstantPoolEntry::Methodref(..) | ConstantPoolEntry::InterfaceMethodref(..))
+                )
+            })
+            .filter_map(|index| self.member_ref(index))
+    }
+
+    pub fn name_and_type(&self, index: u16) -> Option<(&str, &str)> {
+        match self.constant(index)? {
+            ConstantPoolEntry::NameAndType(name_index, descriptor_index) => Some((
+                self.utf8_constant(*name_index)?,
+                self.utf8_constant(*descriptor_index)?,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Resolves a Fieldref, Methodref or InterfaceMethodref entry.
+    pub fn member_ref(&self, index: u16) -> Option<MemberRef<'_>> {
+        match self.constant(index)? {
+            ConstantPoolEntry::Fieldref(class_index, nat_index)
+            | ConstantPoolEntry::Methodref(class_index, nat_index)
+            | ConstantPoolEntry::InterfaceMethodref(class_index, nat_index) => {
+                let (name, descriptor) = self.name_and_type(*nat_index)?;
+                Some(MemberRef {
+                    owner: self.class_constant(*class_index)?,
+                    name,
+                    descriptor,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Resolves an entry that `ldc`/`ldc_w`/`ldc2_w` or a bootstrap argument can load.
+    pub fn loadable_constant(&self, index: u16) -> Option<ConstantValue<'_>> {
+        match self.constant(index)? {
+            ConstantPoolEntry::Integer(v) => Some(ConstantValue::Int(*v)),
+            ConstantPoolEntry::Long(v) => Some(ConstantValue::Long(*v)),
+            ConstantPoolEntry::Float(v) => Some(ConstantValue::Float(*v)),
+            ConstantPoolEntry::Double(v) => Some(ConstantValue::Double(*v)),
+            ConstantPoolEntry::String(utf8_index) => {
+                self.utf8_constant(*utf8_index).map(ConstantValue::String)
+            }
+            ConstantPoolEntry::Class(name_index) => {
+                self.utf8_constant(*name_index).map(ConstantValue::Class)
+            }
+            _ => None,
+        }
+    }
+
+    /// Resolves an `invokedynamic` site to its bootstrap method reference,
+    /// static arguments, and call site name and descriptor.
+    pub fn invoke_dynamic(&self, index: u16) -> Option<(MemberRef<'_>, &[u16], &str, &str)> {
+        let ConstantPoolEntry::InvokeDynamic(bootstrap_index, nat_index) = self.constant(index)?
+        else {
+            return None;
+        };
+        let (name, descriptor) = self.name_and_type(*nat_index)?;
+        let bootstrap = self.bootstrap_methods.get(*bootstrap_index as usize)?;
+        let ConstantPoolEntry::MethodHandle(_, reference_index) =
+            self.constant(bootstrap.method_ref)?
+        else {
+            return None;
+        };
+        Some((
+            self.member_ref(*reference_index)?,
+            &bootstrap.arguments,
+            name,
+            descriptor,
+        ))
+    }
+}
+
+/// Splits a method descriptor into parameter types and the return type.
+pub fn parse_method_descriptor(descriptor: &str) -> Option<(Vec<&str>, &str)> {
+    let rest = descriptor.strip_prefix('(')?;
+    let close = rest.find(')')?;
+    let (params_str, return_type) = (&rest[..close], &rest[close + 1..]);
+
+    let mut params = Vec::new();
+    let bytes = params_str.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        while bytes[i] == b'[' {
+            i += 1;
+            if i >= bytes.len() {
+                return None;
+            }
+        }
+        match bytes[i] {
+            b'L' => i += bytes[i..].iter().position(|&b| b == b';')?,
+            b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' => {}
+            _ => return None,
+        }
+        i += 1;
+        params.push(&params_str[start..i]);
+    }
+
+    Some((params, return_type))
+}
+
+pub fn qualified_method_name(class_name: &str, name: &str, descriptor: &str) -> String {
+    format!("{}.{}{}", class_name, name, descriptor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_method_descriptors() {
+        let (params, return_type) =
+            parse_method_descriptor("(I[[JLjava/lang/String;[Lé/Ü;Z)V").unwrap();
+        assert_eq!(params, ["I", "[[J", "Ljava/lang/String;", "[Lé/Ü;", "Z"]);
+        assert_eq!(return_type, "V");
+    }
+
+    #[test]
+    fn rejects_malformed_descriptors() {
+        for descriptor in ["(é)V", "(I[)V", "(Ljava/lang/String)V", "(Q)V", "I)V", "(I"] {
+            assert_eq!(parse_method_descriptor(descriptor), None, "{descriptor}");
+        }
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::bytecode::{self, Instruction};
+use crate::types::CodeAttribute;
+
+const MIN_DISPATCHER_CASES: usize = 4;
+const MIN_DISPATCHER_BACK_EDGES: usize = 4;
+
+#[allow(dead_code)]
+pub struct BasicBlock {
+    /// Instruction index range `[start, end)` into the decoded method.
+    pub start: usize,
+    pub end: usize,
+    pub successors: Vec<usize>,
+    pub handlers: Vec<usize>,
+}
+
+pub struct ControlFlowGraph {
+    pub blocks: Vec<BasicBlock>,
+}
+
+impl ControlFlowGraph {
+    /// Splits a decoded method into basic blocks. Branch targets, handler
+    /// ranges and the instruction after every block terminator start a block;
+    /// targets that do not land on an instruction boundary are ignored.
+    pub fn build(instructions: &[Instruction], code: &CodeAttribute) -> Self {
+        let index_of: HashMap<usize, usize> = instructions
+            .iter()
+            .enumerate()
+            .map(|(i, ins)| (ins.offset, i))
+            .collect();
+        let resolve = |offset: i64| {
+            usize::try_from(offset)
+                .ok()
+                .and_then(|o| index_of.get(&o).copied())
+        };
+
+        let mut leaders = BTreeSet::new();
+        if !instructions.is_empty() {
+            leaders.insert(0);
+        }
+        for (i, ins) in instructions.iter().enumerate() {
+            leaders.extend(ins.branch_targets().into_iter().filter_map(|t| resolve(t as i64)));
+            if ins.ends_block() && i + 1 < instructions.len() {
+                leaders.insert(i + 1);
+            }
+        }
+        for entry in &code.exception_table {
+            leaders.extend(
+                [entry.start_pc, entry.end_pc, entry.handler_pc]
+                    .into_iter()
+                    .filter_map(|pc| resolve(pc as i64)),
+            );
+        }
+
+        let starts: Vec<usize> = leaders.into_iter().collect();
+        let block_of: HashMap<usize, usize> =
+            starts.iter().enumerate().map(|(b, &i)| (i, b)).collect();
+
+        let blocks = starts
+            .iter()
+            .enumerate()
+            .map(|(b, &start)| {
+                let end = starts.get(b + 1).copied().unwrap_or(instructions.len());
+                let last = &instructions[end - 1];
+
+                let mut successors: Vec<usize> = last
+                    .branch_targets()
+                    .into_iter()
+                    .filter_map(|t| resolve(t as i64))
+                    .filter_map(|i| block_of.get(&i).copied())
+                    .collect();
+                if last.falls_through() && end < instructions.len() {
+                    successors.push(b + 1);
+                }
+                successors.sort_unstable();
+                successors.dedup();
+
+                let start_offset = instructions[start].offset;
+                let handlers = code
+                    .exception_table
+                    .iter()
+                    .filter(|e| {
+                        (e.start_pc as usize..e.end_pc as usize).contains(&start_offset)
+                    })
+                    .filter_map(|e| resolve(e.handler_pc as i64))
+                    .filter_map(|i| block_of.get(&i).copied())
+                    .collect();
+
+                BasicBlock {
+                    start,
+                    end,
+                    successors,
+                    handlers,
+                }
+            })
+            .collect();
+
+        Self { blocks }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct FlowMetrics {
+    pub blocks: usize,
+    pub edges: usize,
+    pub cyclomatic_complexity: usize,
+    pub back_edges: usize,
+    pub unreachable_blocks: usize,
+    /// Case count of the largest switch that most loop back edges return to.
+    pub dispatcher_cases: usize,
+    /// Jumps to an earlier block outside any loop around the jump, which
+    /// only happens once blocks have been shuffled.
+    pub backward_jumps: usize,
+    pub constant_conditions: usize,
+    pub self_covering_handlers: usize,
+}
+
+impl FlowMetrics {
+    pub fn unreachable_ratio(&self) -> f32 {
+        if self.blocks == 0 {
+            0.0
+        } else {
+            self.unreachable_blocks as f32 / self.blocks as f32
+        }
+    }
+
+    /// Human-readable reasons this method looks flattened or junk-padded.
+    /// javac output only rarely has any of these shapes, so one is enough.
+    pub fn obfuscation_reasons(&self) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if self.dispatcher_cases > 0 {
+            reasons.push(format!(
+                "switch dispatcher with {} cases",
+                self.dispatcher_cases
+            ));
+        }
+        if self.unreachable_blocks >= 3 && self.unreachable_ratio() >= 0.15 {
+            reasons.push(format!(
+                "{} unreachable blocks ({:.0}%)",
+                self.unreachable_blocks,
+                self.unreachable_ratio() * 100.0
+            ));
+        }
+        if self.constant_conditions >= 2 {
+            reasons.push(format!("{} constant branch conditions", self.constant_conditions));
+        }
+        if self.backward_jumps >= 2 {
+            reasons.push(format!("{} out-of-order jumps", self.backward_jumps));
+        }
+        if self.self_covering_handlers > 0 {
+            reasons.push(format!(
+                "{} exception handler(s) inside their own range",
+                self.self_covering_handlers
+            ));
+        }
+        reasons
+    }
+}
+
+pub fn analyze_method(code: &CodeAttribute) -> FlowMetrics {
+    let instructions = bytecode::decode(&code.code);
+    if instructions.is_empty() {
+        return FlowMetrics::default();
+    }
+    let cfg = ControlFlowGraph::build(&instructions, code);
+
+    let mut metrics = FlowMetrics {
+        blocks: cfg.blocks.len(),
+        ..FlowMetrics::default()
+    };
+
+    // Iterative DFS; an edge into a block still on the stack is a back edge.
+    const UNSEEN: u8 = 0;
+    const ACTIVE: u8 = 1;
+    const DONE: u8 = 2;
+    let mut state = vec![UNSEEN; cfg.blocks.len()];
+    let mut back_edges_into = vec![0usize; cfg.blocks.len()];
+    let mut stack = vec![(0usize, 0usize)];
+    let mut finished = Vec::with_capacity(cfg.blocks.len());
+    let mut backward = Vec::new();
+    state[0] = ACTIVE;
+
+    while let Some((block, next_edge)) = stack.pop() {
+        let node = &cfg.blocks[block];
+        let target = node.successors.iter().chain(&node.handlers).nth(next_edge);
+
+        if let Some(&target) = target {
+            stack.push((block, next_edge + 1));
+            metrics.edges += 1;
+            if state[target] == ACTIVE {
+                metrics.back_edges += 1;
+                back_edges_into[target] += 1;
+                continue;
+            }
+            if next_edge < node.successors.len() && target <= block {
+                backward.push((block, target));
+            }
+            if state[target] == UNSEEN {
+                state[target] = ACTIVE;
+                stack.push((target, 0));
+            }
+        } else {
+            state[block] = DONE;
+            finished.push(block);
+        }
+    }
+
+    // A bottom-tested loop enters at its condition and jumps back to the
+    // body without the body being on the DFS stack yet, so a backward jump
+    // only counts when it leaves the loop it sits in.
+    let component = strongly_connected_components(&cfg, &finished);
+    metrics.backward_jumps = backward
+        .iter()
+        .filter(|&&(block, target)| component[block] != component[target])
+        .count();
+
+    let reachable = state.iter().filter(|&&s| s != UNSEEN).count();
+    metrics.unreachable_blocks = cfg.blocks.len() - reachable;
+    metrics.cyclomatic_complexity = (metrics.edges + 2).saturating_sub(reachable);
+
+    for (b, block) in cfg.blocks.iter().enumerate() {
+        let last = &instructions[block.end - 1];
+        if !matches!(last.opcode, bytecode::TABLESWITCH | bytecode::LOOKUPSWITCH) {
+            continue;
+        }
+        let cases = block.successors.len();
+        if cases >= MIN_DISPATCHER_CASES
+            && back_edges_into[b] >= MIN_DISPATCHER_BACK_EDGES
+            && back_edges_into[b] * 2 >= cases
+        {
+            metrics.dispatcher_cases = metrics.dispatcher_cases.max(cases);
+        }
+    }
+
+    let jump_targets: BTreeSet<usize> = instructions
+        .iter()
+        .flat_map(|ins| ins.branch_targets())
+        .filter_map(|t| usize::try_from(t).ok())
+        .collect();
+    for (i, ins) in instructions.iter().enumerate() {
+        let operands = match ins.opcode {
+            bytecode::IFEQ..=0x9e | bytecode::IFNULL | bytecode::IFNONNULL => 1,
+            0x9f..=0xa4 => 2,
+            _ => continue,
+        };
+        // The pushes must share the branch's block, otherwise this is just
+        // javac materialising a boolean on both arms of a condition.
+        if i >= operands
+            && !instructions[i + 1 - operands..=i]
+                .iter()
+                .any(|ins| jump_targets.contains(&ins.offset))
+            && instructions[i - operands..i].iter().all(|prev| {
+                matches!(
+                    prev.opcode,
+                    bytecode::ACONST_NULL..=bytecode::ICONST_5
+                        | bytecode::BIPUSH
+                        | bytecode::SIPUSH
+                )
+            })
+        {
+            metrics.constant_conditions += 1;
+        }
+    }
+
+    metrics.self_covering_handlers = code
+        .exception_table
+        .iter()
+        .filter(|e| e.catch_type != 0 && (e.start_pc..e.end_pc).contains(&e.handler_pc))
+        .count();
+
+    metrics
+}
+
+/// Kosaraju's second pass: walks the reversed graph in reverse finishing
+/// order, labelling each block with its component. Unreached blocks keep
+/// `usize::MAX`.
+fn strongly_connected_components(cfg: &ControlFlowGraph, finished: &[usize]) -> Vec<usize> {
+    let mut predecessors = vec![Vec::new(); cfg.blocks.len()];
+    for (b, block) in cfg.blocks.iter().enumerate() {
+        for &target in block.successors.iter().chain(&block.handlers) {
+            predecessors[target].push(b);
+        }
+    }
+
+    let mut reached = vec![false; cfg.blocks.len()];
+    for &block in finished {
+        reached[block] = true;
+    }
+
+    let mut component = vec![usize::MAX; cfg.blocks.len()];
+    for (id, &root) in finished.iter().rev().enumerate() {
+        if component[root] != usize::MAX {
+            continue;
+        }
+        component[root] = id;
+        let mut stack = vec![root];
+        while let Some(block) = stack.pop() {
+            for &pred in &predecessors[block] {
+                if reached[pred] && component[pred] == usize::MAX {
+                    component[pred] = id;
+                    stack.push(pred);
+                }
+            }
+        }
+    }
+    component
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(bytes: &[u8]) -> CodeAttribute {
+        CodeAttribute {
+            max_stack: 4,
+            max_locals: 4,
+            code: bytes.to_vec(),
+            exception_table: Vec::new(),
+            has_line_numbers: false,
+            has_local_variables: false,
+        }
+    }
+
+    #[test]
+    fn javac_for_loop_is_not_obfuscated() {
+        // for (int i = 0; i < a.length; i++) s += a[i];
+        let metrics = analyze_method(&code(&[
+            0x03, 0x3c, 0x03, 0x3d, // s = 0; i = 0
+            0x1c, 0x2a, 0xbe, 0xa2, 0x00, 0x0f, // i < a.length, else -> 22
+            0x1b, 0x2a, 0x1c, 0x2e, 0x60, 0x3c, // s += a[i]
+            0x84, 0x02, 0x01, 0xa7, 0xff, 0xf1, // i++; goto 4
+            0x1b, 0xac,
+        ]));
+        assert_eq!(metrics.back_edges, 1);
+        assert_eq!(metrics.backward_jumps, 0);
+        assert!(metrics.obfuscation_reasons().is_empty());
+    }
+
+    #[test]
+    fn bottom_tested_loops_are_not_out_of_order() {
+        // Two loops laid out as `goto cond; body: ...; cond: if (..) goto body`.
+        let metrics = analyze_method(&code(&[
+            0x03, 0x3c, 0xa7, 0x00, 0x06, // i = 0; goto 8
+            0x84, 0x01, 0x01, // 5: i++
+            0x1b, 0x10, 0x0a, 0xa1, 0xff, 0xfa, // 8: if (i < 10) goto 5
+            0xa7, 0x00, 0x06, // goto 20
+            0x84, 0x01, 0xff, // 17: i--
+            0x1b, 0x9d, 0xff, 0xfc, // 20: if (i > 0) goto 17
+            0xb1,
+        ]));
+        assert_eq!(metrics.backward_jumps, 0);
+        assert!(metrics.obfuscation_reasons().is_empty());
+    }
+
+    #[test]
+    fn shuffled_blocks_count_as_backward_jumps() {
+        let metrics = analyze_method(&code(&[
+            0xa7, 0x00, 0x09, // 0: goto 9
+            0xa7, 0x00, 0x09, // 3: goto 12
+            0xa7, 0xff, 0xfd, // 6: goto 3
+            0xa7, 0xff, 0xfd, // 9: goto 6
+            0xb1, // 12: return
+        ]));
+        assert_eq!(metrics.backward_jumps, 2);
+        assert!(!metrics.obfuscation_reasons().is_empty());
+    }
+}
@@ -0,0 +1,586 @@
+//! Offline ClamAV databases: `.hdb`/`.hsb` hash signatures and `.ndb`
+//! body signatures. Body signatures are compiled to byte regexes, and only
+//! run on entries holding the longest literal byte run they require; the
+//! parts of the format that need ClamAV's file-type engine (PE entry point
+//! and section offsets, normalised text, anchored word boundaries) are
+//! skipped and counted.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use aho_corasick::AhoCorasick;
+use regex::bytes::{Regex, RegexBuilder};
+use sha1::Sha1;
+use sha2::Digest;
+
+use crate::errors::ScanError;
+use crate::native::{NativeFormat, sniff_native_format};
+use crate::utils::{md5_hex, sha256_hex};
+
+const DATABASE_EXTENSIONS: &[&str] = &["hdb", "hsb", "ndb"];
+/// Large gaps compile to large automata; ClamAV rules rarely need more.
+const REGEX_SIZE_LIMIT: usize = 8 * 1024 * 1024;
+/// Shorter literals occur in nearly every entry and filter out nothing.
+const MIN_LITERAL_LEN: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum HashKind {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl HashKind {
+    fn from_hex_len(len: usize) -> Option<Self> {
+        match len {
+            32 => Some(HashKind::Md5),
+            40 => Some(HashKind::Sha1),
+            64 => Some(HashKind::Sha256),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            HashKind::Md5 => "MD5",
+            HashKind::Sha1 => "SHA-1",
+            HashKind::Sha256 => "SHA-256",
+        }
+    }
+
+    fn digest(self, data: &[u8]) -> String {
+        match self {
+            HashKind::Md5 => md5_hex(data),
+            HashKind::Sha1 => hex::encode(Sha1::digest(data)),
+            HashKind::Sha256 => sha256_hex(data),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct HashSignature {
+    name: String,
+    /// `None` for ClamAV's `*` size wildcard.
+    size: Option<u64>,
+}
+
+/// Where an `.ndb` body signature may start.
+#[derive(Debug, Clone, Copy)]
+enum Offset {
+    Any,
+    FromStart { offset: usize, shift: usize },
+    FromEnd { offset: usize, shift: usize },
+}
+
+/// `.ndb` target types, restricted to those a JAR entry can be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Any,
+    Native(NativeFormat),
+    JavaClass,
+}
+
+impl Target {
+    fn parse(text: &str) -> Option<Self> {
+        Some(match text {
+            "0" => Target::Any,
+            "1" => Target::Native(NativeFormat::Pe),
+            "6" => Target::Native(NativeFormat::Elf),
+            "9" => Target::Native(NativeFormat::MachO),
+            "12" => Target::JavaClass,
+            _ => return None,
+        })
+    }
+
+    fn applies_to(self, data: &[u8]) -> bool {
+        match self {
+            Target::Any => true,
+            Target::Native(format) => sniff_native_format(data) == Some(format),
+            Target::JavaClass => {
+                data.starts_with(&[0xCA, 0xFE, 0xBA, 0xBE]) && sniff_native_format(data).is_none()
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct BodySignature {
+    name: String,
+    target: Target,
+    offset: Offset,
+    /// Group 1 is the signature itself, after any offset shift.
+    pattern: Regex,
+    /// Bytes every match contains, if the signature has a long enough run.
+    literal: Option<Vec<u8>>,
+}
+
+/// One pass over an entry for the literals of all body signatures, so only
+/// those whose literal occurs run their regex.
+#[derive(Debug, Default)]
+struct Prefilter {
+    automaton: Option<AhoCorasick>,
+    /// Signatures sharing each automaton pattern.
+    owners: Vec<Vec<usize>>,
+    /// Signatures without a literal, which always run.
+    unfiltered: Vec<usize>,
+}
+
+impl Prefilter {
+    fn build(signatures: &[BodySignature]) -> Self {
+        let mut literals: HashMap<&[u8], Vec<usize>> = HashMap::new();
+        let mut unfiltered = Vec::new();
+        for (index, signature) in signatures.iter().enumerate() {
+            match &signature.literal {
+                Some(literal) => literals.entry(literal).or_default().push(index),
+                None => unfiltered.push(index),
+            }
+        }
+        let (patterns, owners): (Vec<&[u8]>, Vec<Vec<usize>>) = literals.into_iter().unzip();
+        // Without an automaton every signature runs, as before filtering.
+        let automaton = AhoCorasick::new(&patterns).ok();
+        if automaton.is_none() {
+            unfiltered = (0..signatures.len()).collect();
+        }
+        Prefilter {
+            automaton,
+            owners,
+            unfiltered,
+        }
+    }
+
+    /// Which signatures may match `data`, by index.
+    fn candidates(&self, data: &[u8], count: usize) -> Vec<bool> {
+        let mut candidates = vec![false; count];
+        for &index in &self.unfiltered {
+            candidates[index] = true;
+        }
+        if let Some(automaton) = &self.automaton {
+            for found in automaton.find_overlapping_iter(data) {
+                for &index in &self.owners[found.pattern().as_usize()] {
+                    candidates[index] = true;
+                }
+            }
+        }
+        candidates
+    }
+}
+
+/// A signature that matched, and how.
+pub struct ClamAvHit<'a> {
+    pub name: &'a str,
+    pub evidence: String,
+}
+
+#[derive(Debug, Default)]
+pub struct ClamAvDatabase {
+    hashes: HashMap<(HashKind, String), Vec<HashSignature>>,
+    hash_kinds: HashSet<HashKind>,
+    hash_sizes: HashSet<u64>,
+    any_size_hashes: bool,
+    body_signatures: Vec<BodySignature>,
+    prefilter: Prefilter,
+    /// Lines in a supported file that use unsupported features.
+    pub skipped: usize,
+}
+
+impl ClamAvDatabase {
+    /// Loads each file, or every `.hdb`/`.hsb`/`.ndb` in each directory.
+    pub fn load(paths: &[PathBuf]) -> Result<Self, ScanError> {
+        let mut database = ClamAvDatabase::default();
+        for path in paths {
+            if path.is_dir() {
+                let mut files: Vec<PathBuf> = std::fs::read_dir(path)?
+                    .filter_map(|entry| entry.ok().map(|e| e.path()))
+                    .filter(|file| database_extension(file).is_some())
+                    .collect();
+                files.sort();
+                for file in files {
+                    database.load_file(&file)?;
+                }
+            } else {
+                database.load_file(path)?;
+            }
+        }
+        database.prefilter = Prefilter::build(&database.body_signatures);
+        Ok(database)
+    }
+
+    fn load_file(&mut self, path: &Path) -> Result<(), ScanError> {
+        let extension = database_extension(path).ok_or_else(|| {
+            ScanError::ClamAvError(format!(
+                "{} is not a .hdb, .hsb or .ndb database",
+                path.display()
+            ))
+        })?;
+        let contents = std::fs::read(path)?;
+        for line in String::from_utf8_lossy(&contents).lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let added = if extension == "ndb" {
+                self.add_body_signature(line)
+            } else {
+                self.add_hash_signature(line)
+            };
+            if added.is_none() {
+                self.skipped += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// `HASH:SIZE:NAME[:FLEVEL]`, the layout of both `.hdb` and `.hsb`.
+    fn add_hash_signature(&mut self, line: &str) -> Option<()> {
+        let mut parts = line.split(':');
+        let (digest, size, name) = (parts.next()?, parts.next()?, parts.next()?);
+        let kind = HashKind::from_hex_len(digest.len())
+            .filter(|_| digest.bytes().all(|b| b.is_ascii_hexdigit()))?;
+        let size = match size {
+            "*" => None,
+            size => Some(size.parse::<u64>().ok()?),
+        };
+        match size {
+            Some(size) => {
+                self.hash_sizes.insert(size);
+            }
+            None => self.any_size_hashes = true,
+        }
+        self.hash_kinds.insert(kind);
+        self.hashes
+            .entry((kind, digest.to_ascii_lowercase()))
+            .or_default()
+            .push(HashSignature {
+                name: name.to_string(),
+                size,
+            });
+        Some(())
+    }
+
+    /// `NAME:TARGET:OFFSET:HEXSIG[:MINFL[:MAXFL]]`.
+    fn add_body_signature(&mut self, line: &str) -> Option<()> {
+        let mut parts = line.split(':');
+        let (name, target, offset, signature) =
+            (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        let target = Target::parse(target)?;
+        let offset = parse_offset(offset)?;
+        let body = hex_signature_regex(signature)?;
+        let prefix = match offset {
+            Offset::Any => String::new(),
+            Offset::FromStart { shift, .. } | Offset::FromEnd { shift, .. } => {
+                format!(r"\A.{{0,{}}}?", shift)
+            }
+        };
+        let pattern = RegexBuilder::new(&format!("(?s){}({})", prefix, body))
+            .unicode(false)
+            .size_limit(REGEX_SIZE_LIMIT)
+            .build()
+            .ok()?;
+        self.body_signatures.push(BodySignature {
+            name: name.to_string(),
+            target,
+            offset,
+            pattern,
+            literal: required_literal(signature),
+        });
+        Some(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty() && self.body_signatures.is_empty()
+    }
+
+    pub fn hash_signature_count(&self) -> usize {
+        self.hashes.values().map(Vec::len).sum()
+    }
+
+    pub fn body_signature_count(&self) -> usize {
+        self.body_signatures.len()
+    }
+
+    pub fn scan(&self, data: &[u8]) -> Vec<ClamAvHit<'_>> {
+        let mut hits = Vec::new();
+        let size = data.len() as u64;
+        if self.any_size_hashes || self.hash_sizes.contains(&size) {
+            for kind in [HashKind::Md5, HashKind::Sha1, HashKind::Sha256] {
+                if !self.hash_kinds.contains(&kind) {
+                    continue;
+                }
+                let digest = kind.digest(data);
+                let Some(signatures) = self.hashes.get(&(kind, digest)) else {
+                    continue;
+                };
+                hits.extend(
+                    signatures
+                        .iter()
+                        .filter(|s| s.size.is_none_or(|s| s == size))
+                        .map(|s| ClamAvHit {
+                            name: &s.name,
+                            evidence: format!("{} hash", kind.label()),
+                        }),
+                );
+            }
+        }
+
+        let candidates = self.prefilter.candidates(data, self.body_signatures.len());
+        for (signature, _) in self
+            .body_signatures
+            .iter()
+            .zip(candidates)
+            .filter(|(_, candidate)| *candidate)
+        {
+            if !signature.target.applies_to(data) {
+                continue;
+            }
+            let start = match signature.offset {
+                Offset::Any => 0,
+                Offset::FromStart { offset, .. } => offset,
+                Offset::FromEnd { offset, .. } => match data.len().checked_sub(offset) {
+                    Some(start) => start,
+                    None => continue,
+                },
+            };
+            let Some(body) = data
+                .get(start..)
+                .and_then(|slice| signature.pattern.captures(slice))
+                .and_then(|captures| captures.get(1))
+            else {
+                continue;
+            };
+            hits.push(ClamAvHit {
+                name: &signature.name,
+                evidence: format!("body signature at offset {}", start + body.start()),
+            });
+        }
+        hits
+    }
+}
+
+fn database_extension(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    DATABASE_EXTENSIONS
+        .iter()
+        .find(|known| **known == extension)
+        .copied()
+}
+
+/// `*`, `n`, `n,shift`, `EOF-n` and `EOF-n,shift`.
+fn parse_offset(text: &str) -> Option<Offset> {
+    if text == "*" {
+        return Some(Offset::Any);
+    }
+    let (base, shift) = match text.split_once(',') {
+        Some((base, shift)) => (base, shift.parse().ok()?),
+        None => (text, 0),
+    };
+    match base.strip_prefix("EOF-") {
+        Some(offset) => Some(Offset::FromEnd {
+            offset: offset.parse().ok()?,
+            shift,
+        }),
+        None => Some(Offset::FromStart {
+            offset: base.parse().ok()?,
+            shift,
+        }),
+    }
+}
+
+/// Translates a ClamAV hex signature into regex syntax: hex bytes, `??`,
+/// nibble wildcards, `*`, `{n}`/`{n-}`/`{-n}`/`{n-m}`, `[n-m]` and
+/// `(aa|bb)` alternatives, negated when single bytes (`!(aa|bb)`).
+fn hex_signature_regex(signature: &str) -> Option<String> {
+    let chars: Vec<char> = signature.chars().collect();
+    let mut regex = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '*' => {
+                regex.push_str(".*?");
+                i += 1;
+            }
+            '{' | '[' => {
+                let close = if chars[i] == '{' { '}' } else { ']' };
+                let end = i + chars[i..].iter().position(|&c| c == close)?;
+                let range: String = chars[i + 1..end].iter().collect();
+                regex.push_str(&gap_regex(&range)?);
+                i = end + 1;
+            }
+            '!' | '(' => {
+                let negated = chars[i] == '!';
+                let open = if negated { i + 1 } else { i };
+                if chars.get(open) != Some(&'(') {
+                    return None;
+                }
+                let end = open + chars[open..].iter().position(|&c| c == ')')?;
+                let alternatives: Vec<String> = chars[open + 1..end]
+                    .iter()
+                    .collect::<String>()
+                    .split('|')
+                    .map(hex_bytes_regex)
+                    .collect::<Option<_>>()?;
+                if negated {
+                    // Only single literal bytes negate cleanly into a class.
+                    if alternatives.iter().any(|a| !a.starts_with(r"\x") || a.len() != 4) {
+                        return None;
+                    }
+                    regex.push_str(&format!("[^{}]", alternatives.concat()));
+                } else {
+                    regex.push_str(&format!("(?:{})", alternatives.join("|")));
+                }
+                i = end + 1;
+            }
+            _ => {
+                let pair: String = chars.get(i..i + 2)?.iter().collect();
+                regex.push_str(&hex_bytes_regex(&pair)?);
+                i += 2;
+            }
+        }
+    }
+    (!regex.is_empty()).then_some(regex)
+}
+
+/// The longest run of plain hex bytes outside any wildcard, gap or
+/// alternative; a match of the whole signature always contains it.
+fn required_literal(signature: &str) -> Option<Vec<u8>> {
+    let chars: Vec<char> = signature.chars().collect();
+    let mut longest: Vec<u8> = Vec::new();
+    let mut run: Vec<u8> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let pair = chars
+            .get(i..i + 2)
+            .and_then(|pair| Some((pair[0].to_digit(16)?, pair[1].to_digit(16)?)));
+        if let Some((high, low)) = pair {
+            run.push((high << 4 | low) as u8);
+            i += 2;
+            continue;
+        }
+        if run.len() > longest.len() {
+            longest = run.clone();
+        }
+        run.clear();
+        i = match chars[i] {
+            '{' | '[' | '(' => {
+                let close = match chars[i] {
+                    '{' => '}',
+                    '[' => ']',
+                    _ => ')',
+                };
+                i + chars[i..].iter().position(|&c| c == close)? + 1
+            }
+            '*' | '!' => i + 1,
+            _ => i + 2,
+        };
+    }
+    if run.len() > longest.len() {
+        longest = run;
+    }
+    (longest.len() >= MIN_LITERAL_LEN).then_some(longest)
+}
+
+/// A run of byte tokens: hex pairs, `??` and nibble wildcards.
+fn hex_bytes_regex(text: &str) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.is_empty() || !chars.len().is_multiple_of(2) {
+        return None;
+    }
+    let mut regex = String::new();
+    for pair in chars.chunks_exact(2) {
+        let nibble = |c: char| c.to_digit(16);
+        regex.push_str(&match (pair[0], pair[1]) {
+            ('?', '?') => ".".to_string(),
+            ('?', low) => {
+                let low = nibble(low)?;
+                let class: String = (0..16).map(|high| format!(r"\x{:02x}", high << 4 | low)).collect();
+                format!("[{}]", class)
+            }
+            (high, '?') => {
+                let high = nibble(high)?;
+                format!(r"[\x{:02x}-\x{:02x}]", high << 4, high << 4 | 0xf)
+            }
+            (high, low) => format!(r"\x{:02x}", nibble(high)? << 4 | nibble(low)?),
+        });
+    }
+    Some(regex)
+}
+
+/// The inside of `{..}` or `[..]`: `n`, `n-`, `-n` or `n-m` bytes.
+fn gap_regex(range: &str) -> Option<String> {
+    let number = |text: &str| text.parse::<usize>().ok();
+    Some(match range.split_once('-') {
+        None => format!(".{{{}}}", number(range)?),
+        Some(("", max)) => format!(".{{0,{}}}", number(max)?),
+        Some((min, "")) => format!(".{{{},}}", number(min)?),
+        Some((min, max)) => {
+            let (min, max) = (number(min)?, number(max)?);
+            if min > max {
+                return None;
+            }
+            format!(".{{{},{}}}", min, max)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database(files: &[(&str, &str)]) -> ClamAvDatabase {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, contents) in files {
+            std::fs::write(dir.path().join(name), contents).unwrap();
+        }
+        ClamAvDatabase::load(&[dir.path().to_path_buf()]).unwrap()
+    }
+
+    fn hit_names<'a>(database: &'a ClamAvDatabase, data: &[u8]) -> Vec<&'a str> {
+        database
+            .scan(data)
+            .into_iter()
+            .map(|hit| hit.name)
+            .collect()
+    }
+
+    #[test]
+    fn matches_md5_hash_signatures() {
+        let database = database(&[(
+            "test.hdb",
+            "5d41402abc4b2a76b9719d911017c592:5:Test.Hello\n",
+        )]);
+        assert_eq!(hit_names(&database, b"hello"), ["Test.Hello"]);
+        assert!(hit_names(&database, b"hellO").is_empty());
+    }
+
+    #[test]
+    fn body_signatures_run_only_when_their_literal_occurs() {
+        let database = database(&[(
+            "test.ndb",
+            "Test.Literal:0:*:6576696c{2-4}626f6479\nTest.Wildcard:0:20:??????\n",
+        )]);
+        assert_eq!(
+            database.body_signatures[0].literal.as_deref(),
+            Some(&b"evil"[..])
+        );
+        assert_eq!(database.body_signatures[1].literal, None);
+
+        assert_eq!(hit_names(&database, b"an evil--body"), ["Test.Literal"]);
+        assert_eq!(
+            hit_names(&database, b"nothing to see here at all"),
+            ["Test.Wildcard"]
+        );
+        assert!(hit_names(&database, b"evil").is_empty());
+    }
+
+    #[test]
+    fn literals_skip_alternatives_and_nibbles() {
+        assert_eq!(
+            required_literal("aa(bbcc|dd)eeff00").as_deref(),
+            Some(&[0xee, 0xff, 0x00][..])
+        );
+        assert_eq!(
+            required_literal("a?bbccdd*1122").as_deref(),
+            Some(&[0xbb, 0xcc, 0xdd][..])
+        );
+        assert_eq!(required_literal("aabb{4}ccdd"), None);
+    }
+}
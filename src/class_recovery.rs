@@ -0,0 +1,467 @@
+use std::io::Read;
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+
+use crate::bytecode::{self, ConstantValue, Operand};
+use crate::parser::parse_class_structure;
+use crate::reconstruct::reconstruct_strings;
+use crate::types::{ClassDetails, ConstantPoolEntry};
+
+const CLASS_MAGIC: [u8; 4] = [0xCA, 0xFE, 0xBA, 0xBE];
+const MIN_MAJOR_VERSION: u8 = 45;
+const MAX_MAJOR_VERSION: u8 = 80;
+/// Longest repeating XOR key that can be read off the 8-byte class header.
+const MAX_HEADER_KEY_LENGTH: usize = 8;
+const MAX_LAYERS: usize = 3;
+/// Bytes all XOR candidates for one entry may decode between them. Every
+/// 8-byte key reproduces the class header, so even junk yields dozens.
+const MAX_XOR_BYTES: usize = 16 * 1024 * 1024;
+const MAX_DECOMPRESSED_SIZE: usize = 64 * 1024 * 1024;
+const MAX_LOADER_KEY_LENGTH: usize = 64;
+const MAX_KEYS_PER_LOADER: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClassTransform {
+    Xor {
+        key: Vec<u8>,
+        /// The class the key was taken from, when it came from a loader.
+        source: Option<String>,
+    },
+    Gzip,
+    Zlib,
+    Lz4Frame,
+    Lz4Block,
+}
+
+impl std::fmt::Display for ClassTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClassTransform::Xor { key, source } => {
+                write!(f, "XOR with key 0x{}", hex::encode(key))?;
+                if let Some(source) = source {
+                    write!(f, " from {}", source)?;
+                }
+                Ok(())
+            }
+            ClassTransform::Gzip => write!(f, "gzip"),
+            ClassTransform::Zlib => write!(f, "zlib"),
+            ClassTransform::Lz4Frame => write!(f, "LZ4 frame"),
+            ClassTransform::Lz4Block => write!(f, "LZ4 block stream"),
+        }
+    }
+}
+
+/// A key found in a class that defines classes from bytes.
+#[derive(Debug, Clone)]
+pub struct KeyCandidate {
+    pub key: Vec<u8>,
+    pub source: String,
+}
+
+/// Collects plausible XOR keys from a class loader: its string constants,
+/// strings it builds from arrays, and byte-sized integer constants.
+pub fn loader_key_candidates(loader: &ClassDetails) -> Vec<KeyCandidate> {
+    let mut keys: Vec<Vec<u8>> = Vec::new();
+    let mut add = |key: Vec<u8>| {
+        if !key.is_empty() && key.len() <= MAX_LOADER_KEY_LENGTH && !keys.contains(&key) {
+            keys.push(key);
+        }
+    };
+
+    for entry in &loader.constant_pool {
+        if let ConstantPoolEntry::String(index) = entry
+            && let Some(value) = loader.utf8_constant(*index)
+        {
+            add(value.as_bytes().to_vec());
+        }
+    }
+    for recovered in reconstruct_strings(loader) {
+        add(recovered.value.into_bytes());
+    }
+    for method in &loader.methods {
+        let Some(code) = &method.code else {
+            continue;
+        };
+        for instruction in bytecode::decode(&code.code) {
+            let value = match (instruction.opcode, instruction.operand) {
+                (bytecode::BIPUSH | bytecode::SIPUSH, Operand::Int(value)) => value,
+                (bytecode::LDC | bytecode::LDC_W, Operand::Constant(index)) => {
+                    match loader.loadable_constant(index) {
+                        Some(ConstantValue::Int(value)) => value,
+                        _ => continue,
+                    }
+                }
+                _ => continue,
+            };
+            if let Ok(byte) = u8::try_from(value)
+                && byte > 1
+            {
+                add(vec![byte]);
+            }
+        }
+    }
+
+    keys.into_iter()
+        .take(MAX_KEYS_PER_LOADER)
+        .map(|key| KeyCandidate {
+            key,
+            source: loader.class_name.clone(),
+        })
+        .collect()
+}
+
+pub struct RecoveredClass {
+    pub data: Vec<u8>,
+    /// Transforms in the order they were undone.
+    pub transforms: Vec<ClassTransform>,
+}
+
+impl RecoveredClass {
+    pub fn describe(&self) -> String {
+        self.transforms
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", then ")
+    }
+}
+
+/// Tries to turn an entry that is not a class file into one by peeling off
+/// compression wrappers and XOR layers. A candidate only counts once it
+/// parses as a class, so a wrong key is never reported as a recovery.
+pub fn recover_class(data: &[u8], keys: &[KeyCandidate]) -> Option<RecoveredClass> {
+    let mut xor_budget = MAX_XOR_BYTES;
+    peel(data, keys, 0, true, &mut xor_budget).map(|(data, mut transforms)| {
+        transforms.reverse();
+        RecoveredClass { data, transforms }
+    })
+}
+
+/// Undoes one layer and recurses. XOR output is only ever decompressed or
+/// parsed: a second XOR pass would just compose with the first.
+fn peel(
+    data: &[u8],
+    keys: &[KeyCandidate],
+    depth: usize,
+    try_xor: bool,
+    xor_budget: &mut usize,
+) -> Option<(Vec<u8>, Vec<ClassTransform>)> {
+    if is_class_file(data) {
+        return Some((data.to_vec(), Vec::new()));
+    }
+    if depth == MAX_LAYERS {
+        return None;
+    }
+
+    let xor_keys = if try_xor {
+        distinct_xor_keys(data, keys)
+    } else {
+        Vec::new()
+    };
+    let mut transforms = decompress_kind(data).into_iter().chain(xor_keys);
+
+    transforms.find_map(|transform| {
+        let output = match &transform {
+            ClassTransform::Xor { key, .. } => {
+                // Only worth following if the key exposes something we know.
+                let head = &data[..data.len().min(8)];
+                if !has_known_magic(&xor(head, key)) || *xor_budget < data.len() {
+                    return None;
+                }
+                *xor_budget -= data.len();
+                xor(data, key)
+            }
+            kind => decompress(data, kind)?,
+        };
+        let after_xor = matches!(transform, ClassTransform::Xor { .. });
+        let (data, mut transforms) =
+            peel(&output, keys, depth + 1, try_xor && !after_xor, xor_budget)?;
+        transforms.push(transform);
+        Some((data, transforms))
+    })
+}
+
+/// Loader keys, then keys read off the header, each cut down to its
+/// repeating unit so two spellings of one keystream are only tried once.
+fn distinct_xor_keys(data: &[u8], keys: &[KeyCandidate]) -> Vec<ClassTransform> {
+    let candidates = keys
+        .iter()
+        .map(|candidate| (candidate.key.as_slice(), Some(&candidate.source)));
+    let header_keys = header_xor_keys(data);
+
+    let mut seen: Vec<&[u8]> = Vec::new();
+    let mut transforms = Vec::new();
+    for (key, source) in candidates.chain(header_keys.iter().map(|key| (key.as_slice(), None))) {
+        let key = repeating_unit(key);
+        if seen.contains(&key) {
+            continue;
+        }
+        seen.push(key);
+        transforms.push(ClassTransform::Xor {
+            key: key.to_vec(),
+            source: source.cloned(),
+        });
+    }
+    transforms
+}
+
+/// Shortest prefix that `key` is a repetition of.
+fn repeating_unit(key: &[u8]) -> &[u8] {
+    (1..key.len())
+        .filter(|&length| key.len().is_multiple_of(length))
+        .find(|&length| key.chunks(length).all(|chunk| chunk == &key[..length]))
+        .map_or(key, |length| &key[..length])
+}
+
+fn is_class_file(data: &[u8]) -> bool {
+    data.len() > 10
+        && data[..4] == CLASS_MAGIC
+        && data[6] == 0
+        && (MIN_MAJOR_VERSION..=MAX_MAJOR_VERSION).contains(&data[7])
+        && parse_class_structure(data, "", false).is_ok()
+}
+
+fn has_known_magic(data: &[u8]) -> bool {
+    data.starts_with(&CLASS_MAGIC) || decompress_kind(data).is_some()
+}
+
+/// Repeating XOR keys that map the start of `data` onto a class header:
+/// `CAFEBABE`, a zero minor version and a plausible major version. Single-byte
+/// keys that expose a compression header instead are included as well.
+fn header_xor_keys(data: &[u8]) -> Vec<Vec<u8>> {
+    let Some(header) = data.get(..8) else {
+        return Vec::new();
+    };
+
+    let mut keys = Vec::new();
+    for length in 1..=MAX_HEADER_KEY_LENGTH {
+        for major in MIN_MAJOR_VERSION..=MAX_MAJOR_VERSION {
+            let plain = [0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, major];
+            let mut key = vec![None; length];
+            let consistent = header.iter().zip(plain).enumerate().all(|(i, (&c, p))| {
+                let byte = c ^ p;
+                *key[i % length].get_or_insert(byte) == byte
+            });
+            if !consistent {
+                continue;
+            }
+            let key: Vec<u8> = key.into_iter().map(|b| b.unwrap_or(0)).collect();
+            if key.iter().any(|&b| b != 0) && !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+    keys.extend(
+        (1..=u8::MAX)
+            .filter(|&k| decompress_kind(&xor(header, &[k])).is_some())
+            .map(|k| vec![k]),
+    );
+    keys
+}
+
+fn xor(data: &[u8], key: &[u8]) -> Vec<u8> {
+    if key.is_empty() {
+        return data.to_vec();
+    }
+    data.iter()
+        .zip(key.iter().cycle())
+        .map(|(b, k)| b ^ k)
+        .collect()
+}
+
+fn decompress_kind(data: &[u8]) -> Option<ClassTransform> {
+    match data {
+        [0x1f, 0x8b, 0x08, ..] => Some(ClassTransform::Gzip),
+        [0x78, 0x01 | 0x5e | 0x9c | 0xda, ..] => Some(ClassTransform::Zlib),
+        [0x04, 0x22, 0x4d, 0x18, ..] => Some(ClassTransform::Lz4Frame),
+        _ if data.starts_with(b"LZ4Block") => Some(ClassTransform::Lz4Block),
+        _ => None,
+    }
+}
+
+fn decompress(data: &[u8], kind: &ClassTransform) -> Option<Vec<u8>> {
+    match kind {
+        ClassTransform::Gzip => read_limited(GzDecoder::new(data)),
+        ClassTransform::Zlib => read_limited(ZlibDecoder::new(data)),
+        ClassTransform::Lz4Frame => decode_lz4_frame(data),
+        ClassTransform::Lz4Block => decode_lz4_block_stream(data),
+        ClassTransform::Xor { .. } => None,
+    }
+}
+
+fn read_limited(reader: impl Read) -> Option<Vec<u8>> {
+    let mut output = Vec::new();
+    reader
+        .take(MAX_DECOMPRESSED_SIZE as u64)
+        .read_to_end(&mut output)
+        .ok()?;
+    Some(output)
+}
+
+fn u32_le(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Decodes the standard LZ4 frame format (`04 22 4D 18`). Checksums are
+/// skipped rather than verified; the class parse that follows is the check.
+fn decode_lz4_frame(data: &[u8]) -> Option<Vec<u8>> {
+    let flags = *data.get(4)?;
+    if flags >> 6 != 0b01 {
+        return None;
+    }
+    let block_checksums = flags & 0x10 != 0;
+    let mut pos = 6;
+    if flags & 0x08 != 0 {
+        pos += 8;
+    }
+    if flags & 0x01 != 0 {
+        pos += 4;
+    }
+    pos += 1;
+
+    let mut output = Vec::new();
+    loop {
+        let size = u32_le(data, pos)?;
+        pos += 4;
+        if size == 0 {
+            break;
+        }
+        let length = (size & 0x7fff_ffff) as usize;
+        let block = data.get(pos..pos.checked_add(length)?)?;
+        if size & 0x8000_0000 != 0 {
+            output.extend_from_slice(block);
+        } else {
+            decode_lz4_block(block, &mut output)?;
+        }
+        if output.len() > MAX_DECOMPRESSED_SIZE {
+            return None;
+        }
+        pos += length + if block_checksums { 4 } else { 0 };
+    }
+    Some(output)
+}
+
+/// Decodes the framing written by lz4-java's `LZ4BlockOutputStream`.
+fn decode_lz4_block_stream(data: &[u8]) -> Option<Vec<u8>> {
+    const HEADER_LENGTH: usize = 21;
+    const METHOD_RAW: u8 = 0x10;
+    const METHOD_LZ4: u8 = 0x20;
+
+    let mut output = Vec::new();
+    let mut pos = 0;
+    while data.get(pos..pos + 8) == Some(b"LZ4Block".as_slice()) {
+        let method = *data.get(pos + 8)? & 0xf0;
+        let compressed = u32_le(data, pos + 9)? as usize;
+        let decompressed = u32_le(data, pos + 13)? as usize;
+        if decompressed == 0 {
+            break;
+        }
+        if output.len() + decompressed > MAX_DECOMPRESSED_SIZE {
+            return None;
+        }
+        pos += HEADER_LENGTH;
+        let block = data.get(pos..pos.checked_add(compressed)?)?;
+        match method {
+            METHOD_RAW => output.extend_from_slice(block),
+            METHOD_LZ4 => {
+                let mut chunk = Vec::with_capacity(decompressed);
+                decode_lz4_block(block, &mut chunk)?;
+                output.extend(chunk);
+            }
+            _ => return None,
+        }
+        pos += compressed;
+    }
+    (!output.is_empty()).then_some(output)
+}
+
+/// Decodes one raw LZ4 block, appending to `output` so linked blocks can
+/// reference data produced by earlier ones.
+fn decode_lz4_block(block: &[u8], output: &mut Vec<u8>) -> Option<()> {
+    let read_length = |pos: &mut usize, mut length: usize| -> Option<usize> {
+        if length == 15 {
+            loop {
+                let byte = *block.get(*pos)?;
+                *pos += 1;
+                length += byte as usize;
+                if byte != 255 {
+                    break;
+                }
+            }
+        }
+        Some(length)
+    };
+
+    let mut pos = 0;
+    while pos < block.len() {
+        let token = block[pos];
+        pos += 1;
+
+        let literals = read_length(&mut pos, (token >> 4) as usize)?;
+        output.extend_from_slice(block.get(pos..pos.checked_add(literals)?)?);
+        pos += literals;
+        if pos == block.len() {
+            break;
+        }
+
+        let offset = u16::from_le_bytes([*block.get(pos)?, *block.get(pos + 1)?]) as usize;
+        pos += 2;
+        let length = read_length(&mut pos, (token & 0x0f) as usize)? + 4;
+        if offset == 0 || offset > output.len() || output.len() + length > MAX_DECOMPRESSED_SIZE {
+            return None;
+        }
+        let start = output.len() - offset;
+        for i in 0..length {
+            output.push(output[start + i]);
+        }
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::time::{Duration, Instant};
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    use super::*;
+    use crate::test_support::{junk, ClassBuilder};
+
+    #[test]
+    fn recovers_xor_inside_gzip() {
+        let class = ClassBuilder::new("p/Hidden").build();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&xor(&class, b"k3y")).unwrap();
+        let wrapped = encoder.finish().unwrap();
+
+        let recovered = recover_class(&wrapped, &[]).expect("class should be recovered");
+        assert_eq!(recovered.data, class);
+        assert_eq!(recovered.describe(), "gzip, then XOR with key 0x6b3379");
+    }
+
+    #[test]
+    fn junk_entry_stays_cheap() {
+        let started = Instant::now();
+        assert!(recover_class(&junk(200 * 1024), &[]).is_none());
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn equivalent_keys_are_tried_once() {
+        assert_eq!(repeating_unit(b"abab"), b"ab");
+        assert_eq!(repeating_unit(b"aba"), b"aba");
+        let keys = [KeyCandidate {
+            key: b"xyxy".to_vec(),
+            source: "p/Loader".to_string(),
+        }];
+        let transforms = distinct_xor_keys(&xor(&[0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 52], b"xy"), &keys);
+        let xy = transforms
+            .iter()
+            .filter(|t| matches!(t, ClassTransform::Xor { key, .. } if key == b"xy"))
+            .count();
+        assert_eq!(xy, 1);
+    }
+}
@@ -5,6 +5,10 @@ use sysinfo::System;
 const DEFAULT_RESULT_CACHE_SIZE: usize = 4096;
 const DEFAULT_BUFFER_SIZE: usize = 512 * 1024;
 const DEFAULT_SAFE_STRING_CACHE_CAPACITY: usize = 4000;
+const DEFAULT_MAX_COMPRESSION_RATIO: u64 = 200;
+const DEFAULT_MAX_TOTAL_UNCOMPRESSED: u64 = 512 * 1024 * 1024;
+const DEFAULT_MAX_ARCHIVE_ENTRIES: usize = 100_000;
+const DEFAULT_MAX_NESTING_DEPTH: usize = 3;
 
 const LOW_MEMORY_THRESHOLD: u64 = 4 * 1024 * 1024 * 1024;
 const MEDIUM_MEMORY_THRESHOLD: u64 = 8 * 1024 * 1024 * 1024;
@@ -20,6 +24,37 @@ pub struct SystemConfig {
     pub safe_string_cache_capacity: usize,
     pub parallel_scanning: bool,
     pub available_memory: u64,
+    pub limits: ScanLimits,
+}
+
+/// Caps on what a single archive scan may inflate, enforced on the bytes
+/// actually read rather than on sizes the archive declares.
+pub struct ScanLimits {
+    /// Uncompressed-to-compressed ratio above which an entry is abandoned.
+    pub max_compression_ratio: u64,
+    /// Uncompressed bytes read across an archive, nested archives included.
+    pub max_total_uncompressed: u64,
+    pub max_archive_entries: usize,
+    pub max_nesting_depth: usize,
+}
+
+impl ScanLimits {
+    fn from_env() -> Self {
+        let parse = |name: &str| env::var(name).ok().and_then(|v| v.parse::<u64>().ok());
+        ScanLimits {
+            max_compression_ratio: parse("COLLAPSEFINDOBF_MAX_COMPRESSION_RATIO")
+                .unwrap_or(DEFAULT_MAX_COMPRESSION_RATIO),
+            max_total_uncompressed: parse("COLLAPSEFINDOBF_MAX_TOTAL_UNCOMPRESSED_MB")
+                .map(|mb| mb.saturating_mul(1024 * 1024))
+                .unwrap_or(DEFAULT_MAX_TOTAL_UNCOMPRESSED),
+            max_archive_entries: parse("COLLAPSEFINDOBF_MAX_ARCHIVE_ENTRIES")
+                .map(|n| n as usize)
+                .unwrap_or(DEFAULT_MAX_ARCHIVE_ENTRIES),
+            max_nesting_depth: parse("COLLAPSEFINDOBF_MAX_NESTING_DEPTH")
+                .map(|n| n as usize)
+                .unwrap_or(DEFAULT_MAX_NESTING_DEPTH),
+        }
+    }
 }
 
 impl SystemConfig {
@@ -92,6 +127,7 @@ impl SystemConfig {
             safe_string_cache_capacity,
             parallel_scanning,
             available_memory,
+            limits: ScanLimits::from_env(),
         }
     }
 
@@ -110,6 +146,13 @@ impl SystemConfig {
             "   💾 String Cache Capacity: {} entries",
             self.safe_string_cache_capacity
         );
+        println!(
+            "   🧱 Archive Limits: {}:1 ratio, {} MB total, {} entries, depth {}",
+            self.limits.max_compression_ratio,
+            self.limits.max_total_uncompressed / (1024 * 1024),
+            self.limits.max_archive_entries,
+            self.limits.max_nesting_depth
+        );
         println!(
             "   🔍 Parallel Scanning: {}",
             if self.parallel_scanning {
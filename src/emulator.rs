@@ -0,0 +1,1510 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::bytecode::{self, ConstantValue, Instruction, Operand};
+use crate::types::{ClassDetails, MethodInfo, RecoveredString, StringSource};
+use crate::utils::decode_base64;
+
+const MAX_STEPS_PER_CALL: usize = 200_000;
+const MAX_STEPS_PER_CLASS: usize = 2_000_000;
+const MAX_CALL_DEPTH: usize = 16;
+const MAX_HEAP_OBJECTS: usize = 16_384;
+const MAX_HEAP_CELLS: usize = 1 << 20;
+const MAX_CALL_SITES_PER_CLASS: usize = 512;
+
+const ACC_STATIC: u16 = 0x0008;
+const ACC_NATIVE: u16 = 0x0100;
+
+/// Looks up a class by internal name within the current class or archive.
+pub type ClassLookup<'a> = dyn Fn(&str) -> Option<&'a ClassDetails> + 'a;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value {
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Ref(usize),
+    Null,
+    Top,
+}
+
+impl Value {
+    fn is_wide(&self) -> bool {
+        matches!(self, Value::Long(_) | Value::Double(_))
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Object {
+    Str(Vec<u16>),
+    Builder(Vec<u16>),
+    Array(u8, Vec<Value>),
+    Uninit(&'static str),
+    Charset(bool),
+    Base64Decoder,
+}
+
+/// Reasons an emulation is abandoned. None of them are errors for the scan:
+/// the call site simply stays opaque.
+#[derive(Debug)]
+enum Halt {
+    Unsupported,
+    Budget,
+    Exception,
+}
+
+type Exec<T> = Result<T, Halt>;
+
+struct DecodedMethod {
+    instructions: Vec<Instruction>,
+    index_of: HashMap<usize, usize>,
+}
+
+struct Emulator<'a> {
+    lookup: &'a ClassLookup<'a>,
+    heap: Vec<Object>,
+    heap_cells: usize,
+    statics: HashMap<(String, String), Value>,
+    initialized: HashSet<String>,
+    decoded: HashMap<String, Rc<DecodedMethod>>,
+    steps_left: usize,
+    class_steps_left: usize,
+    depth: usize,
+}
+
+/// Evaluates calls to static string-returning helpers whose arguments are all
+/// constants (the usual `ldc` ciphertext plus int key shape) by interpreting
+/// the helper, and harvests strings a pure `<clinit>` leaves in static fields.
+///
+/// Only arithmetic, arrays, static fields of archive classes and a whitelist
+/// of `String`/`StringBuilder`/`Base64` operations are executed; anything else
+/// (I/O, reflection, object fields, exceptions) abandons the evaluation.
+pub fn recover_decrypted_strings<'a>(
+    details: &'a ClassDetails,
+    lookup: &'a ClassLookup<'a>,
+) -> Vec<RecoveredString> {
+    let mut emulator = Emulator {
+        lookup,
+        heap: Vec::new(),
+        heap_cells: 0,
+        statics: HashMap::new(),
+        initialized: HashSet::new(),
+        decoded: HashMap::new(),
+        steps_left: 0,
+        class_steps_left: MAX_STEPS_PER_CLASS,
+        depth: 0,
+    };
+
+    let pool_strings: HashSet<&str> = details.strings.iter().map(String::as_str).collect();
+    let mut seen = HashSet::new();
+    let mut recovered = Vec::new();
+    let mut push = |candidate: RecoveredString| {
+        if !candidate.value.trim().is_empty()
+            && !pool_strings.contains(candidate.value.as_str())
+            && seen.insert((candidate.value.clone(), candidate.method.clone()))
+        {
+            recovered.push(candidate);
+        }
+    };
+
+    let mut evaluated = 0;
+    for method in &details.methods {
+        let Some(code) = &method.code else {
+            continue;
+        };
+        let caller = bytecode::qualified_method_name(
+            &details.class_name,
+            &method.name,
+            &method.descriptor,
+        );
+        let instructions = bytecode::decode(&code.code);
+
+        for (i, instruction) in instructions.iter().enumerate() {
+            if evaluated >= MAX_CALL_SITES_PER_CLASS || emulator.class_steps_left == 0 {
+                break;
+            }
+            let Some((target_class, target, args)) =
+                decrypt_call_site(details, lookup, &instructions, i, instruction)
+            else {
+                continue;
+            };
+            evaluated += 1;
+
+            let Ok(arg_values) = args
+                .iter()
+                .map(|arg| emulator.constant_value(details, arg))
+                .collect::<Exec<Vec<_>>>()
+            else {
+                continue;
+            };
+
+            emulator.steps_left = MAX_STEPS_PER_CALL;
+            if let Ok(Some(Value::Ref(r))) = emulator.execute(target_class, target, arg_values)
+                && let Some(value) = emulator.string_of(r)
+            {
+                push(RecoveredString {
+                    value,
+                    method: caller.clone(),
+                    source: StringSource::Emulated,
+                    helper: Some(bytecode::qualified_method_name(
+                        &target_class.class_name,
+                        &target.name,
+                        &target.descriptor,
+                    )),
+                });
+            }
+        }
+    }
+
+    let has_string_statics = details.fields.iter().any(|f| {
+        f.access_flags & ACC_STATIC != 0
+            && matches!(
+                f.descriptor.as_str(),
+                "Ljava/lang/String;" | "[Ljava/lang/String;"
+            )
+    });
+    let clinit = details.methods.iter().find(|m| m.name == "<clinit>");
+    if let (true, Some(clinit)) = (has_string_statics, clinit) {
+        emulator.steps_left = MAX_STEPS_PER_CALL;
+        if emulator.ensure_initialized(&details.class_name).is_ok() {
+            let initializer = bytecode::qualified_method_name(
+                &details.class_name,
+                &clinit.name,
+                &clinit.descriptor,
+            );
+            for field in &details.fields {
+                let key = (details.class_name.clone(), field.name.clone());
+                let Some(Value::Ref(r)) = emulator.statics.get(&key).copied() else {
+                    continue;
+                };
+                for value in emulator.strings_reachable_from(r) {
+                    push(RecoveredString {
+                        value,
+                        method: initializer.clone(),
+                        source: StringSource::Emulated,
+                        helper: None,
+                    });
+                }
+            }
+        }
+    }
+
+    recovered
+}
+
+/// Matches `invokestatic` of an archive method returning `String` whose
+/// arguments are all pushed by the immediately preceding constant loads.
+fn decrypt_call_site<'a, 'i>(
+    details: &ClassDetails,
+    lookup: &ClassLookup<'a>,
+    instructions: &'i [Instruction],
+    index: usize,
+    instruction: &Instruction,
+) -> Option<(&'a ClassDetails, &'a MethodInfo, &'i [Instruction])> {
+    if instruction.opcode != bytecode::INVOKESTATIC {
+        return None;
+    }
+    let Operand::Constant(cp_index) = instruction.operand else {
+        return None;
+    };
+    let member = details.member_ref(cp_index)?;
+    let (params, return_type) = bytecode::parse_method_descriptor(member.descriptor)?;
+    if return_type != "Ljava/lang/String;"
+        || params.is_empty()
+        || !params
+            .iter()
+            .all(|p| matches!(*p, "I" | "J" | "C" | "S" | "B" | "Z" | "Ljava/lang/String;"))
+        || index < params.len()
+    {
+        return None;
+    }
+
+    let args = &instructions[index - params.len()..index];
+    if !args.iter().all(is_constant_push) {
+        return None;
+    }
+
+    let target_class = lookup(member.owner)?;
+    let target = target_class.methods.iter().find(|m| {
+        m.name == member.name
+            && m.descriptor == member.descriptor
+            && m.access_flags & ACC_STATIC != 0
+            && m.access_flags & ACC_NATIVE == 0
+            && m.code.is_some()
+    })?;
+
+    Some((target_class, target, args))
+}
+
+fn is_constant_push(instruction: &Instruction) -> bool {
+    matches!(
+        instruction.opcode,
+        bytecode::ICONST_M1..=bytecode::LCONST_1
+            | bytecode::BIPUSH
+            | bytecode::SIPUSH
+            | bytecode::LDC..=bytecode::LDC2_W
+    )
+}
+
+fn java_string(s: &str) -> Vec<u16> {
+    s.encode_utf16().collect()
+}
+
+impl<'a> Emulator<'a> {
+    fn tick(&mut self) -> Exec<()> {
+        if self.steps_left == 0 || self.class_steps_left == 0 {
+            return Err(Halt::Budget);
+        }
+        self.steps_left -= 1;
+        self.class_steps_left -= 1;
+        Ok(())
+    }
+
+    fn alloc(&mut self, object: Object) -> Exec<Value> {
+        let cells = match &object {
+            Object::Str(units) | Object::Builder(units) => units.len(),
+            Object::Array(_, elements) => elements.len(),
+            _ => 1,
+        };
+        if self.heap.len() >= MAX_HEAP_OBJECTS {
+            return Err(Halt::Budget);
+        }
+        self.charge(cells)?;
+        self.heap.push(object);
+        Ok(Value::Ref(self.heap.len() - 1))
+    }
+
+    /// Counts cells an object takes on, whether new or grown in place.
+    fn charge(&mut self, cells: usize) -> Exec<()> {
+        if self.heap_cells + cells > MAX_HEAP_CELLS {
+            return Err(Halt::Budget);
+        }
+        self.heap_cells += cells;
+        Ok(())
+    }
+
+    fn alloc_string(&mut self, units: Vec<u16>) -> Exec<Value> {
+        self.alloc(Object::Str(units))
+    }
+
+    fn object(&self, value: Value) -> Exec<&Object> {
+        match value {
+            Value::Ref(r) => self.heap.get(r).ok_or(Halt::Unsupported),
+            Value::Null => Err(Halt::Exception),
+            _ => Err(Halt::Unsupported),
+        }
+    }
+
+    fn object_mut(&mut self, value: Value) -> Exec<&mut Object> {
+        match value {
+            Value::Ref(r) => self.heap.get_mut(r).ok_or(Halt::Unsupported),
+            Value::Null => Err(Halt::Exception),
+            _ => Err(Halt::Unsupported),
+        }
+    }
+
+    fn units(&self, value: Value) -> Exec<&[u16]> {
+        match self.object(value)? {
+            Object::Str(units) | Object::Builder(units) => Ok(units),
+            _ => Err(Halt::Unsupported),
+        }
+    }
+
+    fn string_of(&self, r: usize) -> Option<String> {
+        match self.heap.get(r)? {
+            Object::Str(units) => String::from_utf16(units).ok(),
+            _ => None,
+        }
+    }
+
+    fn strings_reachable_from(&self, r: usize) -> Vec<String> {
+        match self.heap.get(r) {
+            Some(Object::Str(_)) => self.string_of(r).into_iter().collect(),
+            Some(Object::Array(b'L', elements)) => elements
+                .iter()
+                .filter_map(|v| match v {
+                    Value::Ref(e) => self.string_of(*e),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn constant_value(&mut self, class: &ClassDetails, instruction: &Instruction) -> Exec<Value> {
+        match (instruction.opcode, &instruction.operand) {
+            (bytecode::ICONST_M1..=bytecode::ICONST_5, _) => {
+                Ok(Value::Int(instruction.opcode as i32 - 3))
+            }
+            (bytecode::LCONST_0 | bytecode::LCONST_1, _) => {
+                Ok(Value::Long((instruction.opcode - bytecode::LCONST_0) as i64))
+            }
+            (bytecode::BIPUSH | bytecode::SIPUSH, Operand::Int(v)) => Ok(Value::Int(*v)),
+            (bytecode::LDC..=bytecode::LDC2_W, Operand::Constant(index)) => {
+                self.load_constant(class, *index)
+            }
+            _ => Err(Halt::Unsupported),
+        }
+    }
+
+    fn load_constant(&mut self, class: &ClassDetails, index: u16) -> Exec<Value> {
+        match class.loadable_constant(index) {
+            Some(ConstantValue::Int(v)) => Ok(Value::Int(v)),
+            Some(ConstantValue::Long(v)) => Ok(Value::Long(v)),
+            Some(ConstantValue::Float(v)) => Ok(Value::Float(v)),
+            Some(ConstantValue::Double(v)) => Ok(Value::Double(v)),
+            Some(ConstantValue::String(s)) => self.alloc_string(java_string(s)),
+            _ => Err(Halt::Unsupported),
+        }
+    }
+
+    fn decoded(&mut self, class: &ClassDetails, method: &MethodInfo) -> Exec<Rc<DecodedMethod>> {
+        let key = bytecode::qualified_method_name(&class.class_name, &method.name, &method.descriptor);
+        if let Some(decoded) = self.decoded.get(&key) {
+            return Ok(decoded.clone());
+        }
+        let code = method.code.as_ref().ok_or(Halt::Unsupported)?;
+        let instructions = bytecode::decode(&code.code);
+        let index_of = instructions
+            .iter()
+            .enumerate()
+            .map(|(i, ins)| (ins.offset, i))
+            .collect();
+        let decoded = Rc::new(DecodedMethod {
+            instructions,
+            index_of,
+        });
+        self.decoded.insert(key, decoded.clone());
+        Ok(decoded)
+    }
+
+    fn ensure_initialized(&mut self, class_name: &str) -> Exec<()> {
+        if !self.initialized.insert(class_name.to_string()) {
+            return Ok(());
+        }
+        let class = (self.lookup)(class_name).ok_or(Halt::Unsupported)?;
+        if let Some(clinit) = class.methods.iter().find(|m| m.name == "<clinit>") {
+            self.execute(class, clinit, Vec::new())?;
+        }
+        Ok(())
+    }
+
+    fn execute(
+        &mut self,
+        class: &'a ClassDetails,
+        method: &'a MethodInfo,
+        args: Vec<Value>,
+    ) -> Exec<Option<Value>> {
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(Halt::Budget);
+        }
+        let code = method.code.as_ref().ok_or(Halt::Unsupported)?;
+        let decoded = self.decoded(class, method)?;
+
+        let mut locals = vec![Value::Top; code.max_locals as usize];
+        let mut slot = 0;
+        for arg in args {
+            *locals.get_mut(slot).ok_or(Halt::Unsupported)? = arg;
+            slot += if arg.is_wide() { 2 } else { 1 };
+        }
+
+        self.depth += 1;
+        let result = self.run(class, &decoded, &mut locals, code.max_stack as usize);
+        self.depth -= 1;
+        result
+    }
+
+    fn run(
+        &mut self,
+        class: &'a ClassDetails,
+        method: &DecodedMethod,
+        locals: &mut [Value],
+        max_stack: usize,
+    ) -> Exec<Option<Value>> {
+        use bytecode::*;
+
+        let mut stack: Vec<Value> = Vec::with_capacity(max_stack);
+        let mut pc = 0;
+
+        macro_rules! pop {
+            () => {
+                stack.pop().ok_or(Halt::Unsupported)?
+            };
+        }
+        macro_rules! pop_int {
+            () => {
+                match pop!() {
+                    Value::Int(v) => v,
+                    _ => return Err(Halt::Unsupported),
+                }
+            };
+        }
+        macro_rules! pop_long {
+            () => {
+                match pop!() {
+                    Value::Long(v) => v,
+                    _ => return Err(Halt::Unsupported),
+                }
+            };
+        }
+        macro_rules! pop_float {
+            () => {
+                match pop!() {
+                    Value::Float(v) => v,
+                    _ => return Err(Halt::Unsupported),
+                }
+            };
+        }
+        macro_rules! pop_double {
+            () => {
+                match pop!() {
+                    Value::Double(v) => v,
+                    _ => return Err(Halt::Unsupported),
+                }
+            };
+        }
+
+        loop {
+            self.tick()?;
+            let instruction = method.instructions.get(pc).ok_or(Halt::Unsupported)?;
+            let mut jump: Option<i32> = None;
+
+            match instruction.opcode {
+                0x00 => {}
+                ACONST_NULL => stack.push(Value::Null),
+                ICONST_M1..=ICONST_5 => stack.push(Value::Int(instruction.opcode as i32 - 3)),
+                LCONST_0 | LCONST_1 => {
+                    stack.push(Value::Long((instruction.opcode - LCONST_0) as i64))
+                }
+                0x0b..=0x0d => stack.push(Value::Float((instruction.opcode - 0x0b) as f32)),
+                0x0e | 0x0f => stack.push(Value::Double((instruction.opcode - 0x0e) as f64)),
+                BIPUSH | SIPUSH => match instruction.operand {
+                    Operand::Int(v) => stack.push(Value::Int(v)),
+                    _ => return Err(Halt::Unsupported),
+                },
+                LDC | LDC_W | LDC2_W => match instruction.operand {
+                    Operand::Constant(index) => {
+                        let value = self.load_constant(class, index)?;
+                        stack.push(value);
+                    }
+                    _ => return Err(Halt::Unsupported),
+                },
+                ILOAD..=ALOAD | ILOAD_0..=ALOAD_3 => {
+                    let slot = local_slot(instruction).ok_or(Halt::Unsupported)? as usize;
+                    match locals.get(slot) {
+                        Some(Value::Top) | None => return Err(Halt::Unsupported),
+                        Some(value) => stack.push(*value),
+                    }
+                }
+                IALOAD..=SALOAD => {
+                    let index = pop_int!();
+                    let array = pop!();
+                    match self.object(array)? {
+                        Object::Array(_, elements) => {
+                            let element = usize::try_from(index)
+                                .ok()
+                                .and_then(|i| elements.get(i))
+                                .ok_or(Halt::Exception)?;
+                            stack.push(*element);
+                        }
+                        _ => return Err(Halt::Unsupported),
+                    }
+                }
+                ISTORE..=ASTORE | ISTORE_0..=ASTORE_3 => {
+                    let slot = local_slot(instruction).ok_or(Halt::Unsupported)? as usize;
+                    let value = pop!();
+                    if slot + usize::from(value.is_wide()) >= locals.len() {
+                        return Err(Halt::Unsupported);
+                    }
+                    locals[slot] = value;
+                    if value.is_wide() {
+                        locals[slot + 1] = Value::Top;
+                    }
+                }
+                IASTORE..=SASTORE => {
+                    let value = pop!();
+                    let index = pop_int!();
+                    let array = pop!();
+                    let opcode = instruction.opcode;
+                    match self.object_mut(array)? {
+                        Object::Array(element_type, elements) => {
+                            let slot = usize::try_from(index)
+                                .ok()
+                                .and_then(|i| elements.get_mut(i))
+                                .ok_or(Halt::Exception)?;
+                            *slot = match (opcode, value) {
+                                (BASTORE, Value::Int(v)) if *element_type == b'Z' => {
+                                    Value::Int(v & 1)
+                                }
+                                (BASTORE, Value::Int(v)) => Value::Int(v as i8 as i32),
+                                (CASTORE, Value::Int(v)) => Value::Int(v as u16 as i32),
+                                (SASTORE, Value::Int(v)) => Value::Int(v as i16 as i32),
+                                (IASTORE, Value::Int(_))
+                                | (0x50, Value::Long(_))
+                                | (0x51, Value::Float(_))
+                                | (0x52, Value::Double(_))
+                                | (0x53, Value::Ref(_) | Value::Null) => value,
+                                _ => return Err(Halt::Unsupported),
+                            };
+                        }
+                        _ => return Err(Halt::Unsupported),
+                    }
+                }
+                POP => {
+                    pop!();
+                }
+                POP2 => {
+                    if !pop!().is_wide() {
+                        pop!();
+                    }
+                }
+                DUP => {
+                    let top = *stack.last().ok_or(Halt::Unsupported)?;
+                    stack.push(top);
+                }
+                0x5a => {
+                    let v1 = pop!();
+                    let v2 = pop!();
+                    stack.extend([v1, v2, v1]);
+                }
+                0x5b => {
+                    let v1 = pop!();
+                    let v2 = pop!();
+                    if v2.is_wide() {
+                        stack.extend([v1, v2, v1]);
+                    } else {
+                        let v3 = pop!();
+                        stack.extend([v1, v3, v2, v1]);
+                    }
+                }
+                0x5c => {
+                    let v1 = pop!();
+                    if v1.is_wide() {
+                        stack.extend([v1, v1]);
+                    } else {
+                        let v2 = pop!();
+                        stack.extend([v2, v1, v2, v1]);
+                    }
+                }
+                0x5d => {
+                    let v1 = pop!();
+                    if v1.is_wide() {
+                        let v2 = pop!();
+                        stack.extend([v1, v2, v1]);
+                    } else {
+                        let v2 = pop!();
+                        let v3 = pop!();
+                        stack.extend([v2, v1, v3, v2, v1]);
+                    }
+                }
+                SWAP => {
+                    let v1 = pop!();
+                    let v2 = pop!();
+                    stack.extend([v1, v2]);
+                }
+                0x60 | 0x64 | 0x68 | 0x6c | 0x70 | 0x78 | 0x7a | 0x7c | 0x7e | 0x80 | 0x82 => {
+                    let b = pop_int!();
+                    let a = pop_int!();
+                    let result = match instruction.opcode {
+                        0x60 => a.wrapping_add(b),
+                        0x64 => a.wrapping_sub(b),
+                        0x68 => a.wrapping_mul(b),
+                        0x6c | 0x70 if b == 0 => return Err(Halt::Exception),
+                        0x6c => a.wrapping_div(b),
+                        0x70 => a.wrapping_rem(b),
+                        0x78 => a.wrapping_shl(b as u32 & 0x1f),
+                        0x7a => a.wrapping_shr(b as u32 & 0x1f),
+                        0x7c => ((a as u32) >> (b as u32 & 0x1f)) as i32,
+                        0x7e => a & b,
+                        0x80 => a | b,
+                        _ => a ^ b,
+                    };
+                    stack.push(Value::Int(result));
+                }
+                0x61 | 0x65 | 0x69 | 0x6d | 0x71 | 0x7f | 0x81 | 0x83 => {
+                    let b = pop_long!();
+                    let a = pop_long!();
+                    let result = match instruction.opcode {
+                        0x61 => a.wrapping_add(b),
+                        0x65 => a.wrapping_sub(b),
+                        0x69 => a.wrapping_mul(b),
+                        0x6d | 0x71 if b == 0 => return Err(Halt::Exception),
+                        0x6d => a.wrapping_div(b),
+                        0x71 => a.wrapping_rem(b),
+                        0x7f => a & b,
+                        0x81 => a | b,
+                        _ => a ^ b,
+                    };
+                    stack.push(Value::Long(result));
+                }
+                0x79 | 0x7b | 0x7d => {
+                    let b = pop_int!() as u32 & 0x3f;
+                    let a = pop_long!();
+                    stack.push(Value::Long(match instruction.opcode {
+                        0x79 => a.wrapping_shl(b),
+                        0x7b => a.wrapping_shr(b),
+                        _ => ((a as u64) >> b) as i64,
+                    }));
+                }
+                0x62 | 0x66 | 0x6a | 0x6e | 0x72 => {
+                    let b = pop_float!();
+                    let a = pop_float!();
+                    stack.push(Value::Float(match instruction.opcode {
+                        0x62 => a + b,
+                        0x66 => a - b,
+                        0x6a => a * b,
+                        0x6e => a / b,
+                        _ => a % b,
+                    }));
+                }
+                0x63 | 0x67 | 0x6b | 0x6f | 0x73 => {
+                    let b = pop_double!();
+                    let a = pop_double!();
+                    stack.push(Value::Double(match instruction.opcode {
+                        0x63 => a + b,
+                        0x67 => a - b,
+                        0x6b => a * b,
+                        0x6f => a / b,
+                        _ => a % b,
+                    }));
+                }
+                0x74 => {
+                    let a = pop_int!();
+                    stack.push(Value::Int(a.wrapping_neg()));
+                }
+                0x75 => {
+                    let a = pop_long!();
+                    stack.push(Value::Long(a.wrapping_neg()));
+                }
+                0x76 => {
+                    let a = pop_float!();
+                    stack.push(Value::Float(-a));
+                }
+                0x77 => {
+                    let a = pop_double!();
+                    stack.push(Value::Double(-a));
+                }
+                IINC => match instruction.operand {
+                    Operand::Iinc(slot, delta) => match locals.get_mut(slot as usize) {
+                        Some(Value::Int(v)) => *v = v.wrapping_add(delta as i32),
+                        _ => return Err(Halt::Unsupported),
+                    },
+                    _ => return Err(Halt::Unsupported),
+                },
+                0x85..=0x87 => {
+                    let a = pop_int!();
+                    stack.push(match instruction.opcode {
+                        0x85 => Value::Long(a as i64),
+                        0x86 => Value::Float(a as f32),
+                        _ => Value::Double(a as f64),
+                    });
+                }
+                0x88..=0x8a => {
+                    let a = pop_long!();
+                    stack.push(match instruction.opcode {
+                        0x88 => Value::Int(a as i32),
+                        0x89 => Value::Float(a as f32),
+                        _ => Value::Double(a as f64),
+                    });
+                }
+                0x8b..=0x8d => {
+                    let a = pop_float!();
+                    stack.push(match instruction.opcode {
+                        0x8b => Value::Int(a as i32),
+                        0x8c => Value::Long(a as i64),
+                        _ => Value::Double(a as f64),
+                    });
+                }
+                0x8e..=0x90 => {
+                    let a = pop_double!();
+                    stack.push(match instruction.opcode {
+                        0x8e => Value::Int(a as i32),
+                        0x8f => Value::Long(a as i64),
+                        _ => Value::Float(a as f32),
+                    });
+                }
+                0x91..=0x93 => {
+                    let a = pop_int!();
+                    stack.push(Value::Int(match instruction.opcode {
+                        0x91 => a as i8 as i32,
+                        0x92 => a as u16 as i32,
+                        _ => a as i16 as i32,
+                    }));
+                }
+                0x94 => {
+                    let b = pop_long!();
+                    let a = pop_long!();
+                    stack.push(Value::Int(a.cmp(&b) as i32));
+                }
+                0x95..=0x98 => {
+                    let nan_result = if instruction.opcode % 2 == 1 { -1 } else { 1 };
+                    let (a, b) = if instruction.opcode <= 0x96 {
+                        let b = pop_float!();
+                        (pop_float!() as f64, b as f64)
+                    } else {
+                        let b = pop_double!();
+                        (pop_double!(), b)
+                    };
+                    stack.push(Value::Int(
+                        a.partial_cmp(&b).map_or(nan_result, |ordering| ordering as i32),
+                    ));
+                }
+                IFEQ..=0x9e => {
+                    let a = pop_int!();
+                    if compare(instruction.opcode - IFEQ, a, 0) {
+                        jump = instruction.branch_targets().first().copied();
+                    }
+                }
+                0x9f..=0xa4 => {
+                    let b = pop_int!();
+                    let a = pop_int!();
+                    if compare(instruction.opcode - 0x9f, a, b) {
+                        jump = instruction.branch_targets().first().copied();
+                    }
+                }
+                0xa5 | 0xa6 => {
+                    let b = pop!();
+                    let a = pop!();
+                    if (a == b) == (instruction.opcode == 0xa5) {
+                        jump = instruction.branch_targets().first().copied();
+                    }
+                }
+                IFNULL | IFNONNULL => {
+                    let a = pop!();
+                    if (a == Value::Null) == (instruction.opcode == IFNULL) {
+                        jump = instruction.branch_targets().first().copied();
+                    }
+                }
+                GOTO | GOTO_W => jump = instruction.branch_targets().first().copied(),
+                TABLESWITCH => {
+                    let key = pop_int!();
+                    if let Operand::TableSwitch {
+                        default,
+                        low,
+                        targets,
+                    } = &instruction.operand
+                    {
+                        let target = usize::try_from(key as i64 - *low as i64)
+                            .ok()
+                            .and_then(|i| targets.get(i))
+                            .unwrap_or(default);
+                        jump = Some(*target);
+                    }
+                }
+                LOOKUPSWITCH => {
+                    let key = pop_int!();
+                    if let Operand::LookupSwitch { default, pairs } = &instruction.operand {
+                        let target = pairs
+                            .iter()
+                            .find(|(k, _)| *k == key)
+                            .map_or(*default, |&(_, t)| t);
+                        jump = Some(target);
+                    }
+                }
+                IRETURN..=0xb0 => return Ok(Some(pop!())),
+                RETURN => return Ok(None),
+                GETSTATIC => {
+                    let Operand::Constant(index) = instruction.operand else {
+                        return Err(Halt::Unsupported);
+                    };
+                    let field = class.member_ref(index).ok_or(Halt::Unsupported)?;
+                    let value = self.get_static(field.owner, field.name, field.descriptor)?;
+                    stack.push(value);
+                }
+                PUTSTATIC => {
+                    let Operand::Constant(index) = instruction.operand else {
+                        return Err(Halt::Unsupported);
+                    };
+                    let field = class.member_ref(index).ok_or(Halt::Unsupported)?;
+                    (self.lookup)(field.owner).ok_or(Halt::Unsupported)?;
+                    self.ensure_initialized(field.owner)?;
+                    let value = pop!();
+                    self.statics
+                        .insert((field.owner.to_string(), field.name.to_string()), value);
+                }
+                INVOKEVIRTUAL..=INVOKEINTERFACE => {
+                    let Operand::Constant(index) = instruction.operand else {
+                        return Err(Halt::Unsupported);
+                    };
+                    let member = class.member_ref(index).ok_or(Halt::Unsupported)?;
+                    let (params, return_type) =
+                        parse_method_descriptor(member.descriptor).ok_or(Halt::Unsupported)?;
+                    if stack.len() < params.len() {
+                        return Err(Halt::Unsupported);
+                    }
+                    let args = stack.split_off(stack.len() - params.len());
+
+                    let result = if instruction.opcode == INVOKESTATIC {
+                        self.invoke_static(member.owner, member.name, member.descriptor, args)?
+                    } else {
+                        let receiver = pop!();
+                        self.invoke_builtin(receiver, member.owner, member.name, &params, args)?
+                    };
+
+                    match (result, return_type) {
+                        (None, "V") => {}
+                        (Some(value), r) if r != "V" => stack.push(value),
+                        _ => return Err(Halt::Unsupported),
+                    }
+                }
+                INVOKEDYNAMIC => {
+                    let Operand::Constant(index) = instruction.operand else {
+                        return Err(Halt::Unsupported);
+                    };
+                    let value = self.invoke_concat(class, index, &mut stack)?;
+                    stack.push(value);
+                }
+                NEW => {
+                    let Operand::Constant(index) = instruction.operand else {
+                        return Err(Halt::Unsupported);
+                    };
+                    let kind = match class.class_constant(index) {
+                        Some("java/lang/String") => "java/lang/String",
+                        Some("java/lang/StringBuilder") => "java/lang/StringBuilder",
+                        Some("java/lang/StringBuffer") => "java/lang/StringBuffer",
+                        _ => return Err(Halt::Unsupported),
+                    };
+                    let value = self.alloc(Object::Uninit(kind))?;
+                    stack.push(value);
+                }
+                NEWARRAY | ANEWARRAY => {
+                    let length = pop_int!();
+                    let element_type = match instruction.operand {
+                        Operand::NewArray(T_CHAR) => b'C',
+                        Operand::NewArray(T_BYTE) => b'B',
+                        Operand::NewArray(T_SHORT) => b'S',
+                        Operand::NewArray(T_INT) => b'I',
+                        Operand::NewArray(4) => b'Z',
+                        Operand::NewArray(6) => b'F',
+                        Operand::NewArray(7) => b'D',
+                        Operand::NewArray(11) => b'J',
+                        Operand::Constant(_) => b'L',
+                        _ => return Err(Halt::Unsupported),
+                    };
+                    let length = usize::try_from(length).map_err(|_| Halt::Exception)?;
+                    if length > MAX_HEAP_CELLS {
+                        return Err(Halt::Budget);
+                    }
+                    let zero = match element_type {
+                        b'J' => Value::Long(0),
+                        b'F' => Value::Float(0.0),
+                        b'D' => Value::Double(0.0),
+                        b'L' => Value::Null,
+                        _ => Value::Int(0),
+                    };
+                    let value = self.alloc(Object::Array(element_type, vec![zero; length]))?;
+                    stack.push(value);
+                }
+                ARRAYLENGTH => {
+                    let array = pop!();
+                    match self.object(array)? {
+                        Object::Array(_, elements) => stack.push(Value::Int(elements.len() as i32)),
+                        _ => return Err(Halt::Unsupported),
+                    }
+                }
+                CHECKCAST => {}
+                0xc2 | 0xc3 => {
+                    pop!();
+                }
+                _ => return Err(Halt::Unsupported),
+            }
+
+            pc = match jump {
+                Some(target) => *usize::try_from(target)
+                    .ok()
+                    .and_then(|t| method.index_of.get(&t))
+                    .ok_or(Halt::Unsupported)?,
+                None => pc + 1,
+            };
+        }
+    }
+
+    fn get_static(&mut self, owner: &str, name: &str, descriptor: &str) -> Exec<Value> {
+        if owner == "java/nio/charset/StandardCharsets" {
+            return match name {
+                "UTF_8" => self.alloc(Object::Charset(false)),
+                "ISO_8859_1" | "US_ASCII" => self.alloc(Object::Charset(true)),
+                _ => Err(Halt::Unsupported),
+            };
+        }
+
+        (self.lookup)(owner).ok_or(Halt::Unsupported)?;
+        self.ensure_initialized(owner)?;
+        if let Some(value) = self.statics.get(&(owner.to_string(), name.to_string())) {
+            return Ok(*value);
+        }
+        Ok(match descriptor {
+            "J" => Value::Long(0),
+            "F" => Value::Float(0.0),
+            "D" => Value::Double(0.0),
+            d if d.starts_with('L') || d.starts_with('[') => Value::Null,
+            _ => Value::Int(0),
+        })
+    }
+
+    fn invoke_static(
+        &mut self,
+        owner: &str,
+        name: &str,
+        descriptor: &str,
+        args: Vec<Value>,
+    ) -> Exec<Option<Value>> {
+        if let Some(class) = (self.lookup)(owner) {
+            let method = class
+                .methods
+                .iter()
+                .find(|m| m.name == name && m.descriptor == descriptor)
+                .ok_or(Halt::Unsupported)?;
+            if method.access_flags & ACC_NATIVE != 0 {
+                return Err(Halt::Unsupported);
+            }
+            self.ensure_initialized(owner)?;
+            return self.execute(class, method, args);
+        }
+
+        let result = match (owner, name, descriptor, args.as_slice()) {
+            ("java/lang/String", "valueOf", "(C)Ljava/lang/String;", [Value::Int(c)]) => {
+                self.alloc_string(vec![*c as u16])?
+            }
+            ("java/lang/String", "valueOf", "(I)Ljava/lang/String;", [Value::Int(v)]) => {
+                self.alloc_string(java_string(&v.to_string()))?
+            }
+            ("java/lang/String", "valueOf" | "copyValueOf", "([C)Ljava/lang/String;", [array]) => {
+                let units = self.char_array(*array)?;
+                self.alloc_string(units)?
+            }
+            ("java/lang/Integer", "parseInt", "(Ljava/lang/String;)I", [s]) => {
+                let text = String::from_utf16(self.units(*s)?).map_err(|_| Halt::Exception)?;
+                Value::Int(text.parse().map_err(|_| Halt::Exception)?)
+            }
+            ("java/lang/Math", "abs", "(I)I", [Value::Int(v)]) => Value::Int(v.wrapping_abs()),
+            ("java/lang/Math", "max", "(II)I", [Value::Int(a), Value::Int(b)]) => {
+                Value::Int(*a.max(b))
+            }
+            ("java/lang/Math", "min", "(II)I", [Value::Int(a), Value::Int(b)]) => {
+                Value::Int(*a.min(b))
+            }
+            ("java/util/Base64", "getDecoder" | "getMimeDecoder", _, []) => {
+                self.alloc(Object::Base64Decoder)?
+            }
+            _ => return Err(Halt::Unsupported),
+        };
+        Ok(Some(result))
+    }
+
+    fn char_array(&self, value: Value) -> Exec<Vec<u16>> {
+        match self.object(value)? {
+            Object::Array(b'C', elements) => elements
+                .iter()
+                .map(|e| match e {
+                    Value::Int(c) => Ok(*c as u16),
+                    _ => Err(Halt::Unsupported),
+                })
+                .collect(),
+            _ => Err(Halt::Unsupported),
+        }
+    }
+
+    fn byte_array(&self, value: Value) -> Exec<Vec<u8>> {
+        match self.object(value)? {
+            Object::Array(b'B', elements) => elements
+                .iter()
+                .map(|e| match e {
+                    Value::Int(b) => Ok(*b as u8),
+                    _ => Err(Halt::Unsupported),
+                })
+                .collect(),
+            _ => Err(Halt::Unsupported),
+        }
+    }
+
+    fn new_byte_array(&mut self, bytes: &[u8]) -> Exec<Value> {
+        let elements = bytes.iter().map(|&b| Value::Int(b as i8 as i32)).collect();
+        self.alloc(Object::Array(b'B', elements))
+    }
+
+    fn decode_bytes(&self, bytes: &[u8], charset: Option<Value>) -> Exec<Vec<u16>> {
+        let latin1 = match charset {
+            None => false,
+            Some(value) => match self.object(value)? {
+                Object::Charset(latin1) => *latin1,
+                Object::Str(name) => {
+                    let name = String::from_utf16_lossy(name).to_ascii_uppercase();
+                    match name.as_str() {
+                        "UTF-8" | "UTF8" => false,
+                        "ISO-8859-1" | "US-ASCII" | "ASCII" => true,
+                        _ => return Err(Halt::Unsupported),
+                    }
+                }
+                _ => return Err(Halt::Unsupported),
+            },
+        };
+        Ok(if latin1 {
+            bytes.iter().map(|&b| b as u16).collect()
+        } else {
+            java_string(&String::from_utf8_lossy(bytes))
+        })
+    }
+
+    fn render(&self, value: Value, param_type: &str) -> Exec<Vec<u16>> {
+        Ok(match (param_type, value) {
+            ("C", Value::Int(c)) => vec![c as u16],
+            ("Z", Value::Int(v)) => java_string(if v != 0 { "true" } else { "false" }),
+            (_, Value::Int(v)) => java_string(&v.to_string()),
+            (_, Value::Long(v)) => java_string(&v.to_string()),
+            (_, Value::Null) => java_string("null"),
+            (_, Value::Ref(_)) => self.units(value)?.to_vec(),
+            _ => return Err(Halt::Unsupported),
+        })
+    }
+
+    fn invoke_builtin(
+        &mut self,
+        receiver: Value,
+        owner: &str,
+        name: &str,
+        params: &[&str],
+        args: Vec<Value>,
+    ) -> Exec<Option<Value>> {
+        if name == "<init>" {
+            if owner == "java/lang/Object" {
+                return Ok(None);
+            }
+            let kind = match self.object(receiver)? {
+                Object::Uninit(kind) if *kind == owner => *kind,
+                _ => return Err(Halt::Unsupported),
+            };
+            let initialized = match (kind, params, args.as_slice()) {
+                ("java/lang/String", [], []) => Object::Str(Vec::new()),
+                ("java/lang/String", ["[C"], [array]) => Object::Str(self.char_array(*array)?),
+                ("java/lang/String", ["[C", "I", "I"], [array, Value::Int(offset), Value::Int(count)]) => {
+                    let units = self.char_array(*array)?;
+                    let start = usize::try_from(*offset).map_err(|_| Halt::Exception)?;
+                    let end = start
+                        .checked_add(usize::try_from(*count).map_err(|_| Halt::Exception)?)
+                        .ok_or(Halt::Exception)?;
+                    Object::Str(units.get(start..end).ok_or(Halt::Exception)?.to_vec())
+                }
+                ("java/lang/String", ["[B"], [array]) => {
+                    let bytes = self.byte_array(*array)?;
+                    Object::Str(self.decode_bytes(&bytes, None)?)
+                }
+                ("java/lang/String", ["[B", _], [array, charset]) => {
+                    let bytes = self.byte_array(*array)?;
+                    Object::Str(self.decode_bytes(&bytes, Some(*charset))?)
+                }
+                ("java/lang/String", ["Ljava/lang/String;"], [s]) => {
+                    Object::Str(self.units(*s)?.to_vec())
+                }
+                (_, [], []) | (_, ["I"], [_]) => Object::Builder(Vec::new()),
+                (_, ["Ljava/lang/String;" | "Ljava/lang/CharSequence;"], [s]) => {
+                    Object::Builder(self.units(*s)?.to_vec())
+                }
+                _ => return Err(Halt::Unsupported),
+            };
+            *self.object_mut(receiver)? = initialized;
+            return Ok(None);
+        }
+
+        if let Object::Base64Decoder = self.object(receiver)? {
+            let encoded = match (name, args.as_slice()) {
+                ("decode", [arg]) => match self.object(*arg)? {
+                    Object::Str(units) => String::from_utf16_lossy(units).into_bytes(),
+                    Object::Array(b'B', _) => self.byte_array(*arg)?,
+                    _ => return Err(Halt::Unsupported),
+                },
+                _ => return Err(Halt::Unsupported),
+            };
+            let decoded = decode_base64(&encoded).ok_or(Halt::Exception)?;
+            return self.new_byte_array(&decoded).map(Some);
+        }
+
+        if let Object::Builder(_) = self.object(receiver)? {
+            return self.invoke_builder(receiver, name, params, args);
+        }
+
+        let units = match self.object(receiver)? {
+            Object::Str(units) => units.clone(),
+            _ => return Err(Halt::Unsupported),
+        };
+        let int_arg = |i: usize| match args.get(i) {
+            Some(Value::Int(v)) => Ok(*v),
+            _ => Err(Halt::Unsupported),
+        };
+        let index = |i: i32, len: usize| {
+            usize::try_from(i)
+                .ok()
+                .filter(|&i| i <= len)
+                .ok_or(Halt::Exception)
+        };
+
+        let result = match (name, params) {
+            ("length", []) => Value::Int(units.len() as i32),
+            ("isEmpty", []) => Value::Int(units.is_empty() as i32),
+            ("charAt", ["I"]) => {
+                let i = index(int_arg(0)?, units.len())?;
+                Value::Int(*units.get(i).ok_or(Halt::Exception)? as i32)
+            }
+            ("toCharArray", []) => {
+                let elements = units.iter().map(|&c| Value::Int(c as i32)).collect();
+                self.alloc(Object::Array(b'C', elements))?
+            }
+            ("getBytes", []) => {
+                let bytes = String::from_utf16_lossy(&units).into_bytes();
+                self.new_byte_array(&bytes)?
+            }
+            ("getBytes", [_]) => {
+                let latin1 = matches!(self.decode_bytes(b"\xff", Some(args[0]))?.as_slice(), [0xff]);
+                let bytes: Vec<u8> = if latin1 {
+                    units.iter().map(|&c| if c < 0x100 { c as u8 } else { b'?' }).collect()
+                } else {
+                    String::from_utf16_lossy(&units).into_bytes()
+                };
+                self.new_byte_array(&bytes)?
+            }
+            ("hashCode", []) => Value::Int(
+                units
+                    .iter()
+                    .fold(0i32, |h, &c| h.wrapping_mul(31).wrapping_add(c as i32)),
+            ),
+            ("intern" | "toString", []) => receiver,
+            ("substring", ["I"]) => {
+                let start = index(int_arg(0)?, units.len())?;
+                self.alloc_string(units[start..].to_vec())?
+            }
+            ("substring", ["I", "I"]) => {
+                let start = index(int_arg(0)?, units.len())?;
+                let end = index(int_arg(1)?, units.len())?;
+                if start > end {
+                    return Err(Halt::Exception);
+                }
+                self.alloc_string(units[start..end].to_vec())?
+            }
+            ("concat", ["Ljava/lang/String;"]) => {
+                let mut joined = units;
+                joined.extend_from_slice(self.units(args[0])?);
+                self.alloc_string(joined)?
+            }
+            ("equals", ["Ljava/lang/Object;"]) => {
+                let equal = match args[0] {
+                    Value::Ref(_) => matches!(self.object(args[0])?, Object::Str(other) if *other == units),
+                    _ => false,
+                };
+                Value::Int(equal as i32)
+            }
+            ("indexOf", ["I"]) => {
+                let c = int_arg(0)?;
+                Value::Int(
+                    units
+                        .iter()
+                        .position(|&u| u as i32 == c)
+                        .map_or(-1, |p| p as i32),
+                )
+            }
+            ("replace", ["C", "C"]) => {
+                let (from, to) = (int_arg(0)? as u16, int_arg(1)? as u16);
+                let replaced = units.iter().map(|&c| if c == from { to } else { c }).collect();
+                self.alloc_string(replaced)?
+            }
+            ("trim", []) => {
+                let start = units.iter().position(|&c| c > 0x20).unwrap_or(units.len());
+                let end = units.iter().rposition(|&c| c > 0x20).map_or(start, |e| e + 1);
+                self.alloc_string(units[start..end].to_vec())?
+            }
+            _ => return Err(Halt::Unsupported),
+        };
+        Ok(Some(result))
+    }
+
+    fn invoke_builder(
+        &mut self,
+        receiver: Value,
+        name: &str,
+        params: &[&str],
+        args: Vec<Value>,
+    ) -> Exec<Option<Value>> {
+        let appended = match (name, params) {
+            ("append", [param]) => Some(match *param {
+                "[C" => self.char_array(args[0])?,
+                other => self.render(args[0], other)?,
+            }),
+            _ => None,
+        };
+        let grows_by = match (name, params, &appended) {
+            (_, _, Some(appended)) => appended.len(),
+            ("insert", ["I", "C"], None) => 1,
+            ("setLength", ["I"], None) => match args.first() {
+                Some(Value::Int(len)) => {
+                    (*len.max(&0) as usize).saturating_sub(self.units(receiver)?.len())
+                }
+                _ => 0,
+            },
+            _ => 0,
+        };
+        self.charge(grows_by)?;
+
+        let Object::Builder(units) = self.object_mut(receiver)? else {
+            return Err(Halt::Unsupported);
+        };
+
+        if let Some(appended) = appended {
+            units.extend(appended);
+            return Ok(Some(receiver));
+        }
+
+        let int_arg = |i: usize| match args.get(i) {
+            Some(Value::Int(v)) => usize::try_from(*v).map_err(|_| Halt::Exception),
+            _ => Err(Halt::Unsupported),
+        };
+
+        match (name, params) {
+            ("length", []) => Ok(Some(Value::Int(units.len() as i32))),
+            ("charAt", ["I"]) => {
+                let c = *units.get(int_arg(0)?).ok_or(Halt::Exception)?;
+                Ok(Some(Value::Int(c as i32)))
+            }
+            ("setCharAt", ["I", "C"]) => {
+                let i = int_arg(0)?;
+                let c = match args[1] {
+                    Value::Int(c) => c as u16,
+                    _ => return Err(Halt::Unsupported),
+                };
+                *units.get_mut(i).ok_or(Halt::Exception)? = c;
+                Ok(None)
+            }
+            ("deleteCharAt", ["I"]) => {
+                let i = int_arg(0)?;
+                if i >= units.len() {
+                    return Err(Halt::Exception);
+                }
+                units.remove(i);
+                Ok(Some(receiver))
+            }
+            ("insert", ["I", "C"]) => {
+                let i = int_arg(0)?;
+                let c = match args[1] {
+                    Value::Int(c) => c as u16,
+                    _ => return Err(Halt::Unsupported),
+                };
+                if i > units.len() {
+                    return Err(Halt::Exception);
+                }
+                units.insert(i, c);
+                Ok(Some(receiver))
+            }
+            ("setLength", ["I"]) => {
+                units.resize(int_arg(0)?, 0);
+                Ok(None)
+            }
+            ("reverse", []) => {
+                units.reverse();
+                Ok(Some(receiver))
+            }
+            ("toString", []) => {
+                let copy = units.clone();
+                self.alloc_string(copy).map(Some)
+            }
+            _ => Err(Halt::Unsupported),
+        }
+    }
+
+    fn invoke_concat(
+        &mut self,
+        class: &ClassDetails,
+        index: u16,
+        stack: &mut Vec<Value>,
+    ) -> Exec<Value> {
+        let (bootstrap, bootstrap_args, name, descriptor) =
+            class.invoke_dynamic(index).ok_or(Halt::Unsupported)?;
+        if bootstrap.owner != "java/lang/invoke/StringConcatFactory" {
+            return Err(Halt::Unsupported);
+        }
+        let (params, _) = bytecode::parse_method_descriptor(descriptor).ok_or(Halt::Unsupported)?;
+        if stack.len() < params.len() {
+            return Err(Halt::Unsupported);
+        }
+        let args = stack.split_off(stack.len() - params.len());
+        let mut pieces = params.iter().zip(args);
+
+        let mut result = Vec::new();
+        if name == "makeConcat" {
+            for (param_type, value) in pieces {
+                result.extend(self.render(value, param_type)?);
+            }
+        } else {
+            let recipe = match class.loadable_constant(*bootstrap_args.first().ok_or(Halt::Unsupported)?) {
+                Some(ConstantValue::String(recipe)) => recipe,
+                _ => return Err(Halt::Unsupported),
+            };
+            let mut constants = bootstrap_args[1..].iter();
+            for c in recipe.encode_utf16() {
+                match c {
+                    1 => {
+                        let (param_type, value) = pieces.next().ok_or(Halt::Unsupported)?;
+                        result.extend(self.render(value, param_type)?);
+                    }
+                    2 => {
+                        let index = *constants.next().ok_or(Halt::Unsupported)?;
+                        match class.loadable_constant(index) {
+                            Some(ConstantValue::String(s)) => result.extend(s.encode_utf16()),
+                            Some(ConstantValue::Int(v)) => result.extend(java_string(&v.to_string())),
+                            _ => return Err(Halt::Unsupported),
+                        }
+                    }
+                    other => result.push(other),
+                }
+            }
+        }
+        self.alloc_string(result)
+    }
+}
+
+fn compare(condition: u8, a: i32, b: i32) -> bool {
+    match condition {
+        0 => a == b,
+        1 => a != b,
+        2 => a < b,
+        3 => a >= b,
+        4 => a > b,
+        _ => a <= b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_class_structure;
+    use crate::test_support::ClassBuilder;
+
+    const DECRYPT: &str = "(Ljava/lang/String;I)Ljava/lang/String;";
+
+    fn u16_bytes(index: u16) -> [u8; 2] {
+        index.to_be_bytes()
+    }
+
+    /// `Test.d(s, k)` XORs every char of `s` with `k`; `Test.main` calls it
+    /// with `ciphertext` and 7 after `spins` calls to a helper that never
+    /// returns.
+    fn xor_class(ciphertext: &str, spins: usize, helper_code: Option<&[u8]>) -> ClassDetails {
+        let mut class = ClassBuilder::new("Test");
+        let builder = class.class("java/lang/StringBuilder");
+        let builder_init = class.method_ref("java/lang/StringBuilder", "<init>", "()V");
+        let length = class.method_ref("java/lang/String", "length", "()I");
+        let char_at = class.method_ref("java/lang/String", "charAt", "(I)C");
+        let append = class.method_ref(
+            "java/lang/StringBuilder",
+            "append",
+            "(C)Ljava/lang/StringBuilder;",
+        );
+        let to_string = class.method_ref(
+            "java/lang/StringBuilder",
+            "toString",
+            "()Ljava/lang/String;",
+        );
+        let decrypt = class.method_ref("Test", "d", DECRYPT);
+        let spin = class.method_ref("Test", "spin", "(I)Ljava/lang/String;");
+        let ciphertext = class.string(ciphertext) as u8;
+
+        let [b0, b1] = u16_bytes(builder);
+        let [i0, i1] = u16_bytes(builder_init);
+        let [l0, l1] = u16_bytes(length);
+        let [c0, c1] = u16_bytes(char_at);
+        let [a0, a1] = u16_bytes(append);
+        let [t0, t1] = u16_bytes(to_string);
+        #[rustfmt::skip]
+        let decrypt_code = [
+            0xbb, b0, b1, 0x59, 0xb7, i0, i1, 0x4d, // new StringBuilder; dup; <init>; astore_2
+            0x03, 0x3e,                             // iconst_0; istore_3
+            0x1d, 0x2a, 0xb6, l0, l1,               // 10: iload_3; aload_0; length()
+            0xa2, 0x00, 0x16,                       // if_icmpge 37
+            0x2c, 0x2a, 0x1d, 0xb6, c0, c1,         // aload_2; aload_0; iload_3; charAt()
+            0x1b, 0x82, 0x92,                       // iload_1; ixor; i2c
+            0xb6, a0, a1, 0x57,                     // append(C); pop
+            0x84, 0x03, 0x01,                       // iinc 3 1
+            0xa7, 0xff, 0xe8,                       // goto 10
+            0x2c, 0xb6, t0, t1, 0xb0,               // 37: aload_2; toString(); areturn
+        ];
+        class.method(
+            0x0009,
+            "d",
+            DECRYPT,
+            helper_code.unwrap_or(&decrypt_code),
+            &[],
+        );
+        // goto 0
+        class.method(
+            0x0009,
+            "spin",
+            "(I)Ljava/lang/String;",
+            &[0xa7, 0x00, 0x00],
+            &[],
+        );
+
+        let mut main = Vec::new();
+        let [s0, s1] = u16_bytes(spin);
+        for _ in 0..spins {
+            main.extend([0x03, 0xb8, s0, s1, 0x57]); // iconst_0; invokestatic spin; pop
+        }
+        let [d0, d1] = u16_bytes(decrypt);
+        // ldc ciphertext; bipush 7; invokestatic d; pop
+        main.extend([0x12, ciphertext, 0x10, 0x07, 0xb8, d0, d1, 0x57]);
+        main.push(0xb1);
+        class.method(0x0009, "main", "()V", &main, &[]);
+
+        parse_class_structure(&class.build(), "Test.class", false).unwrap()
+    }
+
+    fn recover(details: &ClassDetails) -> Vec<String> {
+        let lookup = |name: &str| (name == details.class_name).then_some(details);
+        recover_decrypted_strings(details, &lookup)
+            .into_iter()
+            .map(|recovered| recovered.value)
+            .collect()
+    }
+
+    fn xor(plaintext: &str) -> String {
+        plaintext.chars().map(|c| (c as u8 ^ 7) as char).collect()
+    }
+
+    #[test]
+    fn decrypts_constant_call_sites() {
+        let details = xor_class(&xor("http://example.com/payload"), 0, None);
+        let lookup = |name: &str| (name == details.class_name).then_some(&details);
+        let recovered = recover_decrypted_strings(&details, &lookup);
+        assert_eq!(recovered.len(), 1);
+        assert_eq!(recovered[0].value, "http://example.com/payload");
+        assert_eq!(recovered[0].method, "Test.main()V");
+        assert_eq!(
+            recovered[0].helper.as_deref(),
+            Some(&*format!("Test.d{}", DECRYPT))
+        );
+    }
+
+    #[test]
+    fn exhausted_budgets_leave_call_sites_opaque() {
+        // A runaway helper only costs its own call ...
+        assert_eq!(recover(&xor_class(&xor("secret"), 1, None)), ["secret"]);
+        // ... until the whole class budget is spent.
+        let spins = MAX_STEPS_PER_CLASS / MAX_STEPS_PER_CALL;
+        assert!(recover(&xor_class(&xor("secret"), spins, None)).is_empty());
+    }
+
+    #[test]
+    fn malformed_helpers_are_abandoned() {
+        // aload_0 until the operand stack overflows
+        let overflow = [[0x2a].repeat(17), vec![0xb0]].concat();
+        let helpers: [&[u8]; 7] = [
+            &[],
+            &[0x10],             // bipush without its operand
+            &[0xa7, 0x00, 0x40], // goto past the end
+            &overflow,
+            &[0x57, 0xb0],       // pop from an empty stack
+            &[0x19, 0xff, 0xb0], // aload of a missing local
+            &[0xff],             // undefined opcode
+        ];
+        for helper in helpers {
+            assert!(recover(&xor_class(&xor("secret"), 0, Some(helper))).is_empty());
+        }
+    }
+}
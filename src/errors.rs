@@ -13,6 +13,12 @@ pub enum ScanError {
     UnsupportedFileType(Option<std::ffi::OsString>),
     #[error("JSON serialization/deserialization error: {0}")]
     JsonError(#[from] serde_json::Error),
+    #[error("Signature pack error: {0}")]
+    SignaturePackError(String),
+    #[error("ClamAV database error: {0}")]
+    ClamAvError(String),
+    #[error("YARA rule error: {0}")]
+    YaraError(String),
     #[error("Template error: {0}")]
     TemplateError(#[from] indicatif::style::TemplateError),
 }
@@ -12,6 +12,8 @@ lazy_static::lazy_static! {
 \s()<>]+|(\([^\s()<>]+\)))*\))+(?:\(([^
 \s()<>]+|(\([^\s()<>]+\)))*\)|[^\s`!()\[\]{};:'".,<>?«»""']))"#).unwrap();
 
+    pub static ref DISCORD_WEBHOOK_REGEX: Regex = Regex::new(r"(?i)https?://(?:(?:canary|ptb)\.)?discord(?:app)?\.com/api/webhooks/\d{5,}/[\w\-]{20,}").unwrap();
+
     pub static ref MALICIOUS_PATTERN_REGEX: Regex = Regex::new(r"(?i)\b(powershell|cmd\.exe|Runtime\.getRuntime\(\)\.exec|ProcessBuilder|loadLibrary|socket\(|bind\(|connect\(|URL\(|URLConnection|Class\.forName|defineClass|getMethod|ldap|rmi)\b").unwrap();
 
     pub static ref GOOD_LINKS: Vec<String> = vec![
@@ -82,10 +84,10 @@ lazy_static::lazy_static! {
     pub static ref GOOD_IP_ADDRS: HashSet<IpAddr> = {
         let mut out = HashSet::new();
         for s in GOOD_IPS.iter() {
-            if !s.contains('/') {
-                if let Ok(a) = s.parse::<IpAddr>() {
-                    out.insert(a);
-                }
+            if !s.contains('/')
+                && let Ok(a) = s.parse::<IpAddr>()
+            {
+                out.insert(a);
             }
         }
         out
@@ -94,10 +96,10 @@ lazy_static::lazy_static! {
     pub static ref GOOD_IP_NETWORKS: Vec<IpNet> = {
         let mut out = Vec::new();
         for s in GOOD_IPS.iter() {
-            if s.contains('/') {
-                if let Ok(n) = s.parse::<IpNet>() {
-                    out.push(n);
-                }
+            if s.contains('/')
+                && let Ok(n) = s.parse::<IpNet>()
+            {
+                out.push(n);
             }
         }
         out
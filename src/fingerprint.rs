@@ -0,0 +1,227 @@
+//! Similarity fingerprints for classes and archives. Class structure is
+//! normalised so that renaming, reordering members or repacking does not
+//! change it; archives are then compared by MinHash over their classes and
+//! string constants.
+
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+
+use crate::bytecode::decode;
+use crate::filters::{is_known_good_ip, DISCORD_WEBHOOK_REGEX, GOOD_LINKS, IP_REGEX, URL_REGEX};
+use crate::types::ClassDetails;
+
+const MINHASH_SIZE: usize = 64;
+
+/// Packages whose names survive obfuscation and so stay in the fingerprint.
+const PLATFORM_PREFIXES: &[&str] = &["java/", "javax/", "jdk/", "sun/", "com/sun/"];
+
+#[derive(Debug, Clone)]
+pub struct ClassFingerprint {
+    pub class_name: String,
+    /// Digest of the normalised structure: flags, platform types, member
+    /// shapes, opcode sequences and platform calls.
+    pub structure: u64,
+    /// Digest of the sorted string constants.
+    pub strings: u64,
+}
+
+impl ClassFingerprint {
+    pub fn new(details: &ClassDetails) -> Self {
+        let mut tokens = vec![format!(
+            "class {:04x} {}",
+            details.access_flags,
+            normalise_class(&details.superclass_name)
+        )];
+        tokens.extend(
+            details
+                .interfaces
+                .iter()
+                .map(|interface| format!("implements {}", normalise_class(interface))),
+        );
+        tokens.extend(details.fields.iter().map(|field| {
+            format!("field {:04x} {}", field.access_flags, normalise_descriptor(&field.descriptor))
+        }));
+        tokens.extend(details.methods.iter().map(|method| {
+            // Only the special methods keep their names; opcodes stand in for the body.
+            let name = if method.name.starts_with('<') { method.name.as_str() } else { "" };
+            let opcodes: Vec<u8> = method
+                .code
+                .as_ref()
+                .map(|code| decode(&code.code).iter().map(|i| i.opcode).collect())
+                .unwrap_or_default();
+            format!(
+                "method {:04x} {}{} {:016x}",
+                method.access_flags,
+                name,
+                normalise_descriptor(&method.descriptor),
+                fnv1a(&opcodes)
+            )
+        }));
+        tokens.extend(details.method_refs().filter(|m| is_platform(m.owner)).map(|m| {
+            format!("calls {}.{}{}", m.owner, m.name, normalise_descriptor(m.descriptor))
+        }));
+        tokens.sort_unstable();
+
+        let strings: BTreeSet<&str> = details.string_constants().collect();
+        ClassFingerprint {
+            class_name: details.class_name.clone(),
+            structure: fnv1a(tokens.join("\n").as_bytes()),
+            strings: fnv1a(strings.into_iter().collect::<Vec<_>>().join("\n").as_bytes()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MinHash(Vec<u64>);
+
+impl MinHash {
+    fn new<'a>(items: impl Iterator<Item = &'a [u8]>) -> Self {
+        let mut slots = vec![u64::MAX; MINHASH_SIZE];
+        for item in items {
+            let base = fnv1a(item);
+            for (seed, slot) in slots.iter_mut().enumerate() {
+                *slot = (*slot).min(mix(base ^ (seed as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)));
+            }
+        }
+        MinHash(slots)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.iter().all(|&slot| slot == u64::MAX)
+    }
+
+    /// Estimated Jaccard similarity of the underlying sets.
+    pub fn similarity(&self, other: &MinHash) -> Option<f64> {
+        if self.is_empty() || other.is_empty() {
+            return None;
+        }
+        let equal = self.0.iter().zip(&other.0).filter(|(a, b)| a == b).count();
+        Some(equal as f64 / MINHASH_SIZE as f64)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JarFingerprint {
+    pub path: String,
+    /// MinHash over the structure digests of every class.
+    pub structure: MinHash,
+    /// MinHash over every string constant in the archive.
+    pub strings: MinHash,
+    #[serde(skip)]
+    pub classes: Vec<ClassFingerprint>,
+    /// URLs, IP addresses and webhooks among the string constants.
+    pub iocs: BTreeSet<String>,
+}
+
+impl JarFingerprint {
+    pub fn new(path: String, classes: &[ClassDetails]) -> Self {
+        let fingerprints: Vec<ClassFingerprint> = classes.iter().map(ClassFingerprint::new).collect();
+        let structures: BTreeSet<[u8; 8]> =
+            fingerprints.iter().map(|c| c.structure.to_le_bytes()).collect();
+        let strings: BTreeSet<&str> = classes
+            .iter()
+            .flat_map(|details| details.string_constants())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        JarFingerprint {
+            path,
+            structure: MinHash::new(structures.iter().map(|digest| digest.as_slice())),
+            strings: MinHash::new(strings.iter().map(|s| s.as_bytes())),
+            iocs: strings.iter().flat_map(|s| extract_iocs(s)).collect(),
+            classes: fingerprints,
+        }
+    }
+
+    /// Mean of the structure and string similarities, ignoring a side that
+    /// either archive has nothing for.
+    pub fn similarity(&self, other: &JarFingerprint) -> f64 {
+        let scores: Vec<f64> = [
+            self.structure.similarity(&other.structure),
+            self.strings.similarity(&other.strings),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if scores.is_empty() {
+            0.0
+        } else {
+            scores.iter().sum::<f64>() / scores.len() as f64
+        }
+    }
+}
+
+fn extract_iocs(string: &str) -> Vec<String> {
+    let mut iocs: Vec<String> = DISCORD_WEBHOOK_REGEX
+        .find_iter(string)
+        .map(|m| m.as_str().to_string())
+        .collect();
+    if iocs.is_empty() {
+        iocs.extend(
+            URL_REGEX
+                .find_iter(string)
+                .map(|m| m.as_str())
+                .filter(|url| !GOOD_LINKS.iter().any(|good| url.contains(good.as_str())))
+                .map(str::to_string),
+        );
+    }
+    iocs.extend(
+        IP_REGEX
+            .find_iter(string)
+            .map(|m| m.as_str())
+            .filter(|ip| !is_known_good_ip(ip))
+            .map(str::to_string),
+    );
+    iocs
+}
+
+fn is_platform(class_name: &str) -> bool {
+    PLATFORM_PREFIXES.iter().any(|prefix| class_name.starts_with(prefix))
+}
+
+fn normalise_class(class_name: &str) -> &str {
+    if class_name.is_empty() || is_platform(class_name) {
+        class_name
+    } else {
+        "?"
+    }
+}
+
+/// Replaces every non-platform class in a descriptor with `L?;`.
+fn normalise_descriptor(descriptor: &str) -> String {
+    let mut normalised = String::with_capacity(descriptor.len());
+    let mut rest = descriptor;
+    while let Some(start) = rest.find('L') {
+        normalised.push_str(&rest[..=start]);
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        normalised.push_str(normalise_class(&rest[..end]));
+        rest = &rest[end..];
+    }
+    normalised.push_str(rest);
+    normalised
+}
+
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// SplitMix64 finaliser, deriving independent hash functions from one digest.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+impl Serialize for MinHash {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // The low 32 bits of each slot are plenty to compare fingerprints by eye.
+        let hex: String = self.0.iter().map(|slot| format!("{:08x}", *slot as u32)).collect();
+        serializer.serialize_str(&hex)
+    }
+}
@@ -981,6 +981,11 @@ impl CollapseFindOBFApp {
             ignore_keywords_file: None,
             exclude_patterns: settings.exclude_patterns,
             find_patterns: settings.find_patterns,
+            annotation_rules: Vec::new(),
+            clamav_databases: Vec::new(),
+            yara_rules: Vec::new(),
+            allowlists: Vec::new(),
+            inspect: false,
             progress: Some(progress.clone()),
             verbose: false,
         };
@@ -0,0 +1,723 @@
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::manifest::{parse_manifest, raw_manifest_sections, ManifestSection, MANIFEST_PATH};
+use crate::utils::decode_base64;
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_BMP_STRING: u8 = 0x1E;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_CONTEXT_0: u8 = 0xA0;
+
+const OID_SIGNED_DATA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x02];
+const OID_MESSAGE_DIGEST: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x04];
+const OID_RSA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x01];
+const OID_EC: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x02, 0x01];
+const OID_DSA: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x38, 0x04, 0x01];
+const OID_ED25519: &[u8] = &[0x2B, 0x65, 0x70];
+
+const NAME_ATTRIBUTES: &[(u8, &str)] = &[
+    (0x03, "CN"),
+    (0x0B, "OU"),
+    (0x0A, "O"),
+    (0x07, "L"),
+    (0x08, "ST"),
+    (0x06, "C"),
+];
+const SIGNATURE_BLOCK_EXTENSIONS: &[&str] = &["RSA", "DSA", "EC"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// The algorithm prefix of a manifest attribute such as `SHA-256-Digest`.
+    fn from_attribute_prefix(prefix: &str) -> Option<Self> {
+        match prefix.to_ascii_uppercase().as_str() {
+            "SHA1" | "SHA-1" => Some(Self::Sha1),
+            "SHA-256" => Some(Self::Sha256),
+            "SHA-384" => Some(Self::Sha384),
+            "SHA-512" => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+
+    fn from_oid(oid: &[u8]) -> Option<Self> {
+        match oid {
+            [0x2B, 0x0E, 0x03, 0x02, 0x1A] => Some(Self::Sha1),
+            [0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01] => Some(Self::Sha256),
+            [0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02] => Some(Self::Sha384),
+            [0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03] => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+
+    pub fn digest(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha1 => Sha1::digest(data).to_vec(),
+            Self::Sha256 => Sha256::digest(data).to_vec(),
+            Self::Sha384 => Sha384::digest(data).to_vec(),
+            Self::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+}
+
+/// A `META-INF/*.SF` file and the PKCS#7 block that signs it, if any.
+pub struct SignatureFile {
+    pub path: String,
+    pub data: Vec<u8>,
+    pub block: Option<(String, Vec<u8>)>,
+}
+
+#[derive(Debug, Default)]
+pub struct SignatureReport {
+    /// One line of certificate details per signer.
+    pub signers: Vec<String>,
+    /// Signature files, blocks or manifest sections that fail to verify.
+    pub invalid: Vec<String>,
+    /// Signed entries whose content no longer matches its digest.
+    pub modified: Vec<String>,
+    /// Signed entries that are no longer in the JAR.
+    pub missing: Vec<String>,
+    /// Entries added outside the signature.
+    pub unsigned: Vec<String>,
+}
+
+/// Signature files are `META-INF/<name>.SF` directly under `META-INF`.
+pub fn is_signature_file(path: &str) -> bool {
+    meta_inf_file_name(path).is_some_and(|name| name.to_ascii_uppercase().ends_with(".SF"))
+}
+
+/// The `.RSA`, `.DSA` or `.EC` block that belongs to a signature file.
+pub fn signature_block_candidates(sf_path: &str) -> Vec<String> {
+    let stem = &sf_path[..sf_path.len() - 3];
+    SIGNATURE_BLOCK_EXTENSIONS
+        .iter()
+        .map(|ext| format!("{}.{}", stem, ext))
+        .collect()
+}
+
+/// Entries the JDK leaves out of the signature: the manifest, signature
+/// files, signature blocks and `SIG-*` files.
+pub fn is_signature_related(path: &str) -> bool {
+    if path.eq_ignore_ascii_case(MANIFEST_PATH) {
+        return true;
+    }
+    let Some(name) = meta_inf_file_name(path) else {
+        return false;
+    };
+    let upper = name.to_ascii_uppercase();
+    upper.starts_with("SIG-")
+        || upper.ends_with(".SF")
+        || SIGNATURE_BLOCK_EXTENSIONS
+            .iter()
+            .any(|ext| upper.ends_with(&format!(".{}", ext)))
+}
+
+fn meta_inf_file_name(path: &str) -> Option<&str> {
+    let prefix = path.get(..9)?;
+    let name = &path[9..];
+    (prefix.eq_ignore_ascii_case("META-INF/") && !name.is_empty() && !name.contains('/'))
+        .then_some(name)
+}
+
+/// Checks the signature files against the manifest, and the manifest digests
+/// against the entries. `entry_digest` hashes an entry's content, or returns
+/// `None` when it cannot be read, which leaves that entry unverified.
+///
+/// The PKCS#7 block is checked structurally: it must parse, name a signer
+/// whose certificate it carries, and its signed message digest must match
+/// the signature file. The RSA/DSA/EC signature value itself is not checked.
+pub fn verify_jar_signatures(
+    manifest_data: &[u8],
+    signature_files: &[SignatureFile],
+    entry_names: &[String],
+    mut entry_digest: impl FnMut(&str, DigestAlgorithm) -> Option<Vec<u8>>,
+) -> SignatureReport {
+    let mut report = SignatureReport::default();
+
+    let sections: Vec<(Option<String>, &[u8])> = raw_manifest_sections(manifest_data)
+        .into_iter()
+        .enumerate()
+        .map(|(i, raw)| {
+            let name = (i > 0)
+                .then(|| {
+                    parse_manifest(&String::from_utf8_lossy(raw))
+                        .main
+                        .attribute("Name")
+                        .map(str::to_string)
+                })
+                .flatten();
+            (name, raw)
+        })
+        .collect();
+    let manifest = parse_manifest(&String::from_utf8_lossy(manifest_data));
+
+    // Names listed by any signature file, and those whose manifest section
+    // still matches it.
+    let mut listed: Vec<String> = Vec::new();
+    let mut signed: Vec<String> = Vec::new();
+    for sf in signature_files {
+        verify_signature_block(sf, &mut report);
+
+        let sf_manifest = parse_manifest(&String::from_utf8_lossy(&sf.data));
+        let whole_manifest_ok = digests_match(&sf_manifest.main, "-Digest-Manifest", manifest_data);
+
+        if whole_manifest_ok != Some(true) {
+            let main_raw = sections.first().map(|(_, raw)| *raw).unwrap_or_default();
+            if digests_match(&sf_manifest.main, "-Digest-Manifest-Main-Attributes", main_raw)
+                == Some(false)
+            {
+                report.invalid.push(format!(
+                    "Manifest main attributes were changed after {} was signed",
+                    sf.path
+                ));
+            }
+        }
+
+        for sf_section in &sf_manifest.entries {
+            let Some(name) = sf_section.name.as_deref() else {
+                continue;
+            };
+            if !listed.iter().any(|s| s == name) {
+                listed.push(name.to_string());
+            }
+            let Some((_, raw)) = sections.iter().find(|(n, _)| n.as_deref() == Some(name)) else {
+                report
+                    .invalid
+                    .push(format!("{} lists '{}', which has no manifest section", sf.path, name));
+                continue;
+            };
+            if whole_manifest_ok != Some(true)
+                && digests_match(sf_section, "-Digest", raw) == Some(false)
+            {
+                report.invalid.push(format!(
+                    "Manifest section for '{}' was changed after {} was signed",
+                    name, sf.path
+                ));
+                continue;
+            }
+            if !signed.iter().any(|s| s == name) {
+                signed.push(name.to_string());
+            }
+        }
+    }
+
+    for section in &manifest.entries {
+        let Some(name) = section.name.as_deref() else {
+            continue;
+        };
+        if !signed.iter().any(|s| s == name) {
+            continue;
+        }
+        let expected = section_digests(section, "-Digest");
+        let Some((algorithm, _)) = expected.first() else {
+            continue;
+        };
+        if !entry_names.iter().any(|n| n == name) {
+            report.missing.push(name.to_string());
+            continue;
+        }
+        // An entry that could not be hashed is unverified, not modified.
+        let Some(actual) = entry_digest(name, *algorithm) else {
+            continue;
+        };
+        if !expected.iter().any(|(a, value)| a == algorithm && *value == actual) {
+            report.modified.push(name.to_string());
+        }
+    }
+
+    report.unsigned = entry_names
+        .iter()
+        .filter(|name| !name.ends_with('/') && !is_signature_related(name))
+        .filter(|name| !listed.contains(name))
+        .cloned()
+        .collect();
+    report
+}
+
+/// Digest attributes ending in `suffix`, decoded, for supported algorithms.
+fn section_digests(section: &ManifestSection, suffix: &str) -> Vec<(DigestAlgorithm, Vec<u8>)> {
+    section
+        .attributes
+        .iter()
+        .filter_map(|(key, value)| {
+            let split = key.len().checked_sub(suffix.len())?;
+            if !key.is_char_boundary(split) || !key[split..].eq_ignore_ascii_case(suffix) {
+                return None;
+            }
+            let algorithm = DigestAlgorithm::from_attribute_prefix(&key[..split])?;
+            Some((algorithm, decode_base64(value.trim().as_bytes())?))
+        })
+        .collect()
+}
+
+/// `None` when the section has no usable digest for `suffix`.
+fn digests_match(section: &ManifestSection, suffix: &str, data: &[u8]) -> Option<bool> {
+    let digests = section_digests(section, suffix);
+    if digests.is_empty() {
+        return None;
+    }
+    Some(
+        digests
+            .iter()
+            .all(|(algorithm, expected)| algorithm.digest(data) == *expected),
+    )
+}
+
+fn verify_signature_block(sf: &SignatureFile, report: &mut SignatureReport) {
+    let Some((block_path, block)) = &sf.block else {
+        report
+            .invalid
+            .push(format!("{} has no RSA, DSA or EC signature block", sf.path));
+        return;
+    };
+    let signed_data = match parse_signed_data(block) {
+        Ok(signed_data) => signed_data,
+        Err(reason) => {
+            report.invalid.push(format!("{}: {}", block_path, reason));
+            return;
+        }
+    };
+
+    for signer in &signed_data.signers {
+        let Some(certificate) = signed_data
+            .certificates
+            .iter()
+            .find(|c| c.issuer_raw == signer.issuer_raw && c.serial == signer.serial)
+        else {
+            report.invalid.push(format!(
+                "{}: signer certificate (serial {}) is not in the block",
+                block_path,
+                format_serial(&signer.serial)
+            ));
+            continue;
+        };
+        report
+            .signers
+            .push(format!("{} via {}", certificate.describe(), block_path));
+
+        match (signer.digest_algorithm, &signer.message_digest) {
+            (None, _) => report.invalid.push(format!(
+                "{}: unsupported signer digest algorithm",
+                block_path
+            )),
+            (Some(algorithm), Some(expected)) if algorithm.digest(&sf.data) != *expected => {
+                report
+                    .invalid
+                    .push(format!("{} does not sign the current {}", block_path, sf.path));
+            }
+            _ => {}
+        }
+    }
+}
+
+struct Der<'a> {
+    tag: u8,
+    content: &'a [u8],
+    raw: &'a [u8],
+}
+
+/// Reads one definite-length DER element, returning it and the rest.
+fn read_der(data: &[u8]) -> Option<(Der<'_>, &[u8])> {
+    let (&tag, rest) = data.split_first()?;
+    if tag & 0x1F == 0x1F {
+        return None;
+    }
+    let (&first, mut rest) = rest.split_first()?;
+    let length = if first < 0x80 {
+        first as usize
+    } else {
+        let count = (first & 0x7F) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+        let length = rest[..count]
+            .iter()
+            .fold(0usize, |acc, &b| (acc << 8) | b as usize);
+        rest = &rest[count..];
+        length
+    };
+    if rest.len() < length {
+        return None;
+    }
+    let header = data.len() - rest.len();
+    let element = Der {
+        tag,
+        content: &rest[..length],
+        raw: &data[..header + length],
+    };
+    Some((element, &rest[length..]))
+}
+
+fn der_children(mut content: &[u8]) -> Option<Vec<Der<'_>>> {
+    let mut children = Vec::new();
+    while !content.is_empty() {
+        let (child, rest) = read_der(content)?;
+        children.push(child);
+        content = rest;
+    }
+    Some(children)
+}
+
+fn expect_tag<'a>(element: Option<&'a Der<'a>>, tag: u8) -> Result<&'a Der<'a>, &'static str> {
+    element
+        .filter(|e| e.tag == tag)
+        .ok_or("malformed PKCS#7 SignedData")
+}
+
+struct SignedData {
+    certificates: Vec<Certificate>,
+    signers: Vec<SignerInfo>,
+}
+
+struct SignerInfo {
+    issuer_raw: Vec<u8>,
+    serial: Vec<u8>,
+    digest_algorithm: Option<DigestAlgorithm>,
+    message_digest: Option<Vec<u8>>,
+}
+
+struct Certificate {
+    subject: String,
+    issuer: String,
+    issuer_raw: Vec<u8>,
+    subject_raw: Vec<u8>,
+    serial: Vec<u8>,
+    not_before: String,
+    not_after: String,
+    key: String,
+}
+
+impl Certificate {
+    fn describe(&self) -> String {
+        let mut notes = vec![
+            self.key.clone(),
+            format!("serial {}", format_serial(&self.serial)),
+            format!("valid {} to {}", self.not_before, self.not_after),
+        ];
+        if self.subject_raw == self.issuer_raw {
+            notes.push("self-signed".to_string());
+        } else {
+            notes.push(format!("issued by {}", self.issuer));
+        }
+        if !self.not_after.is_empty() && self.not_after < today() {
+            notes.push("expired".to_string());
+        }
+        format!("Signed by {} ({})", self.subject, notes.join(", "))
+    }
+}
+
+fn parse_signed_data(data: &[u8]) -> Result<SignedData, &'static str> {
+    let (content_info, _) = read_der(data).ok_or("not a DER-encoded PKCS#7 block")?;
+    let parts = der_children(content_info.content).ok_or("not a DER-encoded PKCS#7 block")?;
+    if expect_tag(parts.first(), TAG_OID)?.content != OID_SIGNED_DATA {
+        return Err("not a PKCS#7 SignedData block");
+    }
+    let explicit = expect_tag(parts.get(1), TAG_CONTEXT_0)?;
+    let (signed_data, _) = read_der(explicit.content).ok_or("malformed PKCS#7 SignedData")?;
+    let fields = der_children(signed_data.content).ok_or("malformed PKCS#7 SignedData")?;
+
+    let certificates = fields
+        .iter()
+        .find(|f| f.tag == TAG_CONTEXT_0)
+        .and_then(|certs| der_children(certs.content))
+        .unwrap_or_default()
+        .iter()
+        .map(parse_certificate)
+        .collect::<Option<Vec<_>>>()
+        .ok_or("malformed certificate")?;
+
+    let signer_infos = fields
+        .iter()
+        .skip(3)
+        .rfind(|f| f.tag == TAG_SET)
+        .and_then(|set| der_children(set.content))
+        .ok_or("malformed PKCS#7 SignedData")?;
+    let signers = signer_infos
+        .iter()
+        .map(parse_signer_info)
+        .collect::<Result<Vec<_>, _>>()?;
+    if signers.is_empty() {
+        return Err("block has no signer");
+    }
+    Ok(SignedData {
+        certificates,
+        signers,
+    })
+}
+
+fn parse_signer_info(info: &Der<'_>) -> Result<SignerInfo, &'static str> {
+    let fields = der_children(info.content).ok_or("malformed signer info")?;
+    let issuer_and_serial = expect_tag(fields.get(1), TAG_SEQUENCE)
+        .map_err(|_| "signer is not identified by issuer and serial number")?;
+    let id = der_children(issuer_and_serial.content).ok_or("malformed signer info")?;
+    let issuer = expect_tag(id.first(), TAG_SEQUENCE)?;
+    let serial = expect_tag(id.get(1), TAG_INTEGER)?;
+
+    let digest_algorithm = expect_tag(fields.get(2), TAG_SEQUENCE)
+        .ok()
+        .and_then(|alg| der_children(alg.content))
+        .and_then(|alg| alg.first().map(|oid| DigestAlgorithm::from_oid(oid.content)))
+        .flatten();
+
+    let message_digest = fields
+        .get(3)
+        .filter(|f| f.tag == TAG_CONTEXT_0)
+        .and_then(|attrs| der_children(attrs.content))
+        .and_then(|attrs| {
+            attrs.iter().find_map(|attr| {
+                let parts = der_children(attr.content)?;
+                if parts.first()?.content != OID_MESSAGE_DIGEST {
+                    return None;
+                }
+                let values = der_children(parts.get(1)?.content)?;
+                let value = values.first().filter(|v| v.tag == TAG_OCTET_STRING)?;
+                Some(value.content.to_vec())
+            })
+        });
+
+    Ok(SignerInfo {
+        issuer_raw: issuer.raw.to_vec(),
+        serial: serial.content.to_vec(),
+        digest_algorithm,
+        message_digest,
+    })
+}
+
+fn parse_certificate(cert: &Der<'_>) -> Option<Certificate> {
+    let parts = der_children(cert.content)?;
+    let tbs = der_children(parts.first().filter(|p| p.tag == TAG_SEQUENCE)?.content)?;
+    let offset = usize::from(tbs.first()?.tag == TAG_CONTEXT_0);
+    let field = |i: usize| tbs.get(offset + i);
+
+    let serial = field(0).filter(|f| f.tag == TAG_INTEGER)?;
+    let issuer = field(2).filter(|f| f.tag == TAG_SEQUENCE)?;
+    let validity = der_children(field(3)?.content)?;
+    let subject = field(4).filter(|f| f.tag == TAG_SEQUENCE)?;
+    let key = field(5).map(describe_public_key).unwrap_or_default();
+
+    Some(Certificate {
+        subject: format_name(subject),
+        issuer: format_name(issuer),
+        issuer_raw: issuer.raw.to_vec(),
+        subject_raw: subject.raw.to_vec(),
+        serial: serial.content.to_vec(),
+        not_before: validity.first().map(format_time).unwrap_or_default(),
+        not_after: validity.get(1).map(format_time).unwrap_or_default(),
+        key,
+    })
+}
+
+fn describe_public_key(spki: &Der<'_>) -> String {
+    let Some(parts) = der_children(spki.content) else {
+        return "unknown key".to_string();
+    };
+    let algorithm = parts
+        .first()
+        .and_then(|alg| der_children(alg.content))
+        .and_then(|alg| alg.first().map(|oid| oid.content));
+    match algorithm {
+        Some(OID_RSA) => {
+            let bits = parts
+                .get(1)
+                .filter(|p| p.tag == TAG_BIT_STRING && !p.content.is_empty())
+                .and_then(|p| read_der(&p.content[1..]))
+                .and_then(|(key, _)| der_children(key.content))
+                .and_then(|key| key.first().map(|n| integer_bits(n.content)));
+            match bits {
+                Some(bits) => format!("RSA {}-bit", bits),
+                None => "RSA".to_string(),
+            }
+        }
+        Some(OID_EC) => "EC".to_string(),
+        Some(OID_DSA) => "DSA".to_string(),
+        Some(OID_ED25519) => "Ed25519".to_string(),
+        _ => "unknown key".to_string(),
+    }
+}
+
+fn format_serial(serial: &[u8]) -> String {
+    let start = serial.iter().position(|&b| b != 0).unwrap_or(serial.len().saturating_sub(1));
+    hex::encode(&serial[start..])
+}
+
+fn integer_bits(value: &[u8]) -> usize {
+    let trimmed: &[u8] = match value.iter().position(|&b| b != 0) {
+        Some(start) => &value[start..],
+        None => return 0,
+    };
+    trimmed.len() * 8 - trimmed[0].leading_zeros() as usize
+}
+
+/// Formats the common attributes of an X.500 name, e.g. `CN=Foo, O=Bar`.
+fn format_name(name: &Der<'_>) -> String {
+    let mut parts = Vec::new();
+    for rdn in der_children(name.content).unwrap_or_default() {
+        for attr in der_children(rdn.content).unwrap_or_default() {
+            let Some(fields) = der_children(attr.content) else {
+                continue;
+            };
+            let (Some(oid), Some(value)) = (fields.first(), fields.get(1)) else {
+                continue;
+            };
+            let label = match oid.content {
+                [0x55, 0x04, id] => NAME_ATTRIBUTES
+                    .iter()
+                    .find(|(attr_id, _)| attr_id == id)
+                    .map(|(_, label)| *label),
+                _ => None,
+            };
+            if let Some(label) = label {
+                parts.push(format!("{}={}", label, decode_der_string(value)));
+            }
+        }
+    }
+    if parts.is_empty() {
+        "unnamed subject".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+fn decode_der_string(value: &Der<'_>) -> String {
+    if value.tag == TAG_BMP_STRING {
+        let units: Vec<u16> = value
+            .content
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(value.content).into_owned()
+    }
+}
+
+/// Formats a UTCTime or GeneralizedTime as `YYYY-MM-DD`.
+fn format_time(time: &Der<'_>) -> String {
+    let Ok(text) = std::str::from_utf8(time.content) else {
+        return String::new();
+    };
+    if !text.is_ascii() {
+        return String::new();
+    }
+    let full = match time.tag {
+        TAG_UTC_TIME if text.len() >= 6 => {
+            let century = if &text[..2] < "50" { "20" } else { "19" };
+            format!("{}{}", century, &text[..6])
+        }
+        TAG_GENERALIZED_TIME if text.len() >= 8 => text[..8].to_string(),
+        _ => return String::new(),
+    };
+    format!("{}-{}-{}", &full[..4], &full[4..6], &full[6..8])
+}
+
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or(0) as i64;
+    // Days since 1970-01-01 to a civil date (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use zip::ZipArchive;
+
+    use super::*;
+
+    const SIGNED_JAR: &[u8] = include_bytes!("../tests/fixtures/signed.jar");
+
+    fn entries() -> Vec<(String, Vec<u8>)> {
+        let mut archive = ZipArchive::new(Cursor::new(SIGNED_JAR)).unwrap();
+        (0..archive.len())
+            .map(|i| {
+                let mut entry = archive.by_index(i).unwrap();
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data).unwrap();
+                (entry.name().to_string(), data)
+            })
+            .collect()
+    }
+
+    fn verify(
+        entry_digest: impl FnMut(&str, DigestAlgorithm) -> Option<Vec<u8>>,
+    ) -> SignatureReport {
+        let entries = entries();
+        let content = |name: &str| entries.iter().find(|(n, _)| n == name).unwrap().1.clone();
+        let signature_file = SignatureFile {
+            path: "META-INF/T.SF".to_string(),
+            data: content("META-INF/T.SF"),
+            block: Some(("META-INF/T.RSA".to_string(), content("META-INF/T.RSA"))),
+        };
+        let names: Vec<String> = entries.iter().map(|(name, _)| name.clone()).collect();
+        verify_jar_signatures(&content(MANIFEST_PATH), &[signature_file], &names, entry_digest)
+    }
+
+    #[test]
+    fn pkcs7_block_names_its_signer() {
+        let block = entries()
+            .into_iter()
+            .find(|(name, _)| name == "META-INF/T.RSA")
+            .unwrap()
+            .1;
+        assert!(parse_signed_data(&block).is_ok());
+
+        let entries = entries();
+        let report = verify(|name, algorithm| {
+            let (_, data) = entries.iter().find(|(n, _)| n == name)?;
+            Some(algorithm.digest(data))
+        });
+        assert_eq!(report.signers.len(), 1);
+        assert!(report.signers[0].contains("CN=Test Signer"), "{:?}", report.signers);
+        assert!(report.invalid.is_empty(), "{:?}", report.invalid);
+        assert!(report.modified.is_empty());
+        assert!(report.unsigned.is_empty());
+    }
+
+    #[test]
+    fn changed_entry_is_modified() {
+        let report = verify(|name, algorithm| Some(algorithm.digest(name.as_bytes())));
+        assert_eq!(report.modified, ["p/Loader.class", "p/Hook.class"]);
+    }
+
+    #[test]
+    fn unreadable_entry_is_not_a_mismatch() {
+        let report = verify(|_, _| None);
+        assert!(report.modified.is_empty());
+        assert!(report.invalid.is_empty());
+    }
+
+    #[test]
+    fn truncated_block_is_rejected() {
+        let block = entries()
+            .into_iter()
+            .find(|(name, _)| name == "META-INF/T.RSA")
+            .unwrap()
+            .1;
+        assert!(parse_signed_data(&block[..block.len() / 2]).is_err());
+    }
+}
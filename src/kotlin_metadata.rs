@@ -0,0 +1,375 @@
+//! Decoder for the `kotlin/Metadata` annotation the Kotlin compiler puts on
+//! every class it emits. `d1` holds protobuf messages describing the
+//! declarations, packed into strings; `d2` is the string table they index.
+//! Only the parts needed to recover declared names are read.
+
+use crate::types::{ClassDetails, ElementValue};
+
+pub const KOTLIN_METADATA_ANNOTATION: &str = "kotlin/Metadata";
+/// Every Kotlin class with a null check or `!!` calls into this.
+pub const KOTLIN_INTRINSICS: &str = "kotlin/jvm/internal/Intrinsics";
+
+/// `d1` strings in this mode hold one byte per char after a NUL marker.
+const UTF8_MODE_MARKER: char = '\u{0}';
+
+/// Kotlin class kinds, from `Metadata.k`.
+const KIND_CLASS: i32 = 1;
+const KIND_FILE: i32 = 2;
+const KIND_SYNTHETIC_CLASS: i32 = 3;
+const KIND_MULTIFILE_CLASS_PART: i32 = 5;
+
+#[derive(Debug, Clone)]
+pub struct KotlinMetadata {
+    /// Source names of declared functions, properties, nested classes,
+    /// type aliases and enum entries, plus the JVM names they compile to.
+    pub declared_names: Vec<String>,
+    /// Source names of declared functions only.
+    pub functions: Vec<String>,
+    /// The raw `d2` string table.
+    pub strings: Vec<String>,
+}
+
+impl KotlinMetadata {
+    pub fn declares(&self, name: &str) -> bool {
+        self.declared_names.iter().any(|n| n == name) || self.strings.iter().any(|s| s == name)
+    }
+}
+
+/// `None` when the class has no `kotlin/Metadata`; `Some(Err(..))` when it
+/// does but the payload does not decode.
+pub fn kotlin_metadata(details: &ClassDetails) -> Option<Result<KotlinMetadata, String>> {
+    let annotation = details
+        .annotations
+        .iter()
+        .find(|a| a.type_name() == KOTLIN_METADATA_ANNOTATION)?;
+    let strings = |name: &str| -> Vec<String> {
+        match annotation.element(name) {
+            Some(ElementValue::Array(values)) => values
+                .iter()
+                .filter_map(|v| match v {
+                    ElementValue::String(s) => Some(s.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    };
+    let kind = match annotation.element("k") {
+        Some(ElementValue::Int(k)) => *k,
+        _ => KIND_CLASS,
+    };
+    Some(decode(kind, &strings("d1"), strings("d2")))
+}
+
+fn decode(kind: i32, d1: &[String], d2: Vec<String>) -> Result<KotlinMetadata, String> {
+    let mut metadata = KotlinMetadata {
+        declared_names: Vec::new(),
+        functions: Vec::new(),
+        strings: d2,
+    };
+    if d1.is_empty() {
+        return match kind {
+            KIND_CLASS | KIND_FILE | KIND_MULTIFILE_CLASS_PART => {
+                Err("d1 is empty for a class or file".to_string())
+            }
+            _ => Ok(metadata),
+        };
+    }
+
+    let bytes = decode_bytes(d1)?;
+    let mut reader = ProtoReader::new(&bytes);
+    // The payload opens with a length-delimited JvmProtoBuf.StringTableTypes.
+    let string_table_len = reader.varint()? as usize;
+    reader.skip(string_table_len)?;
+    let message = reader.rest();
+
+    let (functions, properties, type_aliases) = match kind {
+        KIND_CLASS => (9, 10, 11),
+        KIND_FILE | KIND_MULTIFILE_CLASS_PART => (3, 4, 5),
+        // Lambdas carry a bare Function message.
+        KIND_SYNTHETIC_CLASS => {
+            metadata.read_function(message)?;
+            return Ok(metadata);
+        }
+        _ => return Ok(metadata),
+    };
+
+    let mut reader = ProtoReader::new(message);
+    while let Some((field, value)) = reader.field()? {
+        match (field, value) {
+            (f, FieldValue::Bytes(b)) if f == functions => metadata.read_function(b)?,
+            (f, FieldValue::Bytes(b)) if f == properties => metadata.read_property(b)?,
+            (f, FieldValue::Bytes(b)) if f == type_aliases => {
+                let name = metadata.name_field(b, 2)?;
+                metadata.declared_names.extend(name);
+            }
+            // Class.companion_object_name
+            (4, FieldValue::Varint(index)) if kind == KIND_CLASS => {
+                let name = metadata.string(index)?;
+                metadata.declared_names.push(name);
+            }
+            // Class.nested_class_name, packed
+            (7, FieldValue::Bytes(b)) if kind == KIND_CLASS => {
+                let mut packed = ProtoReader::new(b);
+                while !packed.is_empty() {
+                    let name = metadata.string(packed.varint()?)?;
+                    metadata.declared_names.push(name);
+                }
+            }
+            (7, FieldValue::Varint(index)) if kind == KIND_CLASS => {
+                let name = metadata.string(index)?;
+                metadata.declared_names.push(name);
+            }
+            // Class.enum_entry
+            (13, FieldValue::Bytes(b)) if kind == KIND_CLASS => {
+                let name = metadata.name_field(b, 1)?;
+                metadata.declared_names.extend(name);
+            }
+            _ => {}
+        }
+    }
+    Ok(metadata)
+}
+
+impl KotlinMetadata {
+    fn string(&self, index: u64) -> Result<String, String> {
+        self.strings
+            .get(index as usize)
+            .cloned()
+            .ok_or_else(|| format!("name index {} is outside the {}-entry d2 table", index, self.strings.len()))
+    }
+
+    fn name_field(&self, message: &[u8], field_number: u64) -> Result<Option<String>, String> {
+        let mut reader = ProtoReader::new(message);
+        while let Some((field, value)) = reader.field()? {
+            if let (f, FieldValue::Varint(index)) = (field, value)
+                && f == field_number
+            {
+                return self.string(index).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// `Function.name` (2) plus the JVM name in the `method_signature`
+    /// extension (100).
+    fn read_function(&mut self, message: &[u8]) -> Result<(), String> {
+        let mut reader = ProtoReader::new(message);
+        while let Some((field, value)) = reader.field()? {
+            match (field, value) {
+                (2, FieldValue::Varint(index)) => {
+                    let name = self.string(index)?;
+                    self.functions.push(name.clone());
+                    self.declared_names.push(name);
+                }
+                (100, FieldValue::Bytes(signature)) => {
+                    let name = self.name_field(signature, 1)?;
+                    self.declared_names.extend(name);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// `Property.name` (2) plus the backing field, getter and setter names
+    /// in the `property_signature` extension (100).
+    fn read_property(&mut self, message: &[u8]) -> Result<(), String> {
+        let mut reader = ProtoReader::new(message);
+        while let Some((field, value)) = reader.field()? {
+            match (field, value) {
+                (2, FieldValue::Varint(index)) => {
+                    let name = self.string(index)?;
+                    self.declared_names.push(name);
+                }
+                (100, FieldValue::Bytes(signature)) => {
+                    let mut signature = ProtoReader::new(signature);
+                    while let Some((field, value)) = signature.field()? {
+                        if let (1..=4, FieldValue::Bytes(member)) = (field, value) {
+                            let name = self.name_field(member, 1)?;
+                            self.declared_names.extend(name);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reverses `BitEncoding.encodeBytes` from the Kotlin compiler.
+fn decode_bytes(d1: &[String]) -> Result<Vec<u8>, String> {
+    let joined: String = d1.concat();
+    if let Some(rest) = joined.strip_prefix(UTF8_MODE_MARKER) {
+        return rest
+            .chars()
+            .map(|c| u8::try_from(c as u32).map_err(|_| format!("char {:?} in 8-bit d1", c)))
+            .collect();
+    }
+
+    // Legacy mode: 7 bits per char, each shifted by one.
+    let mut septets = Vec::with_capacity(joined.len());
+    for c in joined.chars() {
+        let value = u8::try_from(c as u32).map_err(|_| format!("char {:?} in 7-bit d1", c))?;
+        septets.push(value.wrapping_add(0x7F) & 0x7F);
+    }
+    let length = septets.len() * 7 / 8;
+    let mut bytes = Vec::with_capacity(length);
+    let (mut index, mut bit) = (0, 0);
+    for _ in 0..length {
+        let low = septets[index] >> bit;
+        index += 1;
+        let high = (septets.get(index).copied().unwrap_or(0) & ((1 << (bit + 1)) - 1)) << (7 - bit);
+        bytes.push(low.wrapping_add(high));
+        if bit == 6 {
+            index += 1;
+            bit = 0;
+        } else {
+            bit += 1;
+        }
+    }
+    Ok(bytes)
+}
+
+enum FieldValue<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+struct ProtoReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ProtoReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        ProtoReader { data, position: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.data[self.position.min(self.data.len())..]
+    }
+
+    fn skip(&mut self, length: usize) -> Result<(), String> {
+        if self.data.len() - self.position < length {
+            return Err("truncated protobuf".to_string());
+        }
+        self.position += length;
+        Ok(())
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self.data.get(self.position).ok_or("truncated varint")?;
+            self.position += 1;
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("varint too long".to_string())
+    }
+
+    fn field(&mut self) -> Result<Option<(u64, FieldValue<'a>)>, String> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let (field, wire_type) = (key >> 3, key & 7);
+        if field == 0 {
+            return Err("protobuf field number 0".to_string());
+        }
+        let value = match wire_type {
+            0 => FieldValue::Varint(self.varint()?),
+            1 => {
+                self.skip(8)?;
+                FieldValue::Fixed
+            }
+            2 => {
+                let length = self.varint()? as usize;
+                let start = self.position;
+                self.skip(length)?;
+                FieldValue::Bytes(&self.data[start..start + length])
+            }
+            5 => {
+                self.skip(4)?;
+                FieldValue::Fixed
+            }
+            other => return Err(format!("unsupported protobuf wire type {}", other)),
+        };
+        Ok(Some((field, value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A `Class` message declaring function `run`, property `token` and
+    /// nested class `Inner`, after an empty string table.
+    const CLASS_MESSAGE: &[u8] = &[
+        0x00, // StringTableTypes, empty
+        0x4A, 0x02, 0x10, 0x00, // function { name: 0 }
+        0x52, 0x02, 0x10, 0x01, // property { name: 1 }
+        0x38, 0x02, // nested_class_name: 2
+    ];
+
+    fn d2() -> Vec<String> {
+        ["run", "token", "Inner"].map(String::from).to_vec()
+    }
+
+    fn utf8_mode(bytes: &[u8]) -> String {
+        std::iter::once(UTF8_MODE_MARKER)
+            .chain(bytes.iter().map(|&b| char::from(b)))
+            .collect()
+    }
+
+    /// `BitEncoding.encodeBytes` without the 8-bit marker.
+    fn legacy_mode(bytes: &[u8]) -> String {
+        let bits = bytes.len() * 8;
+        (0..bits.div_ceil(7))
+            .map(|septet| {
+                let value = (0..7)
+                    .map(|k| 7 * septet + k)
+                    .filter(|&bit| bit < bits && bytes[bit / 8] >> (bit % 8) & 1 == 1)
+                    .fold(0u8, |value, bit| value | 1 << (bit % 7));
+                char::from((value + 1) & 0x7F)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reads_declared_names_in_either_encoding() {
+        for d1 in [utf8_mode(CLASS_MESSAGE), legacy_mode(CLASS_MESSAGE)] {
+            let metadata = decode(KIND_CLASS, &[d1], d2()).unwrap();
+            assert_eq!(metadata.functions, ["run"]);
+            assert_eq!(metadata.declared_names, ["run", "token", "Inner"]);
+            assert!(metadata.declares("Inner"));
+        }
+    }
+
+    #[test]
+    fn lambdas_carry_a_bare_function() {
+        let d1 = utf8_mode(&[0x00, 0x10, 0x00]);
+        let metadata = decode(KIND_SYNTHETIC_CLASS, &[d1], d2()).unwrap();
+        assert_eq!(metadata.functions, ["run"]);
+    }
+
+    #[test]
+    fn rejects_missing_or_dangling_payloads() {
+        assert!(decode(KIND_CLASS, &[], d2()).is_err());
+        assert!(decode(KIND_SYNTHETIC_CLASS, &[], d2()).is_ok());
+
+        let d1 = utf8_mode(&[0x00, 0x4A, 0x02, 0x10, 0x07]);
+        assert!(decode(KIND_CLASS, &[d1], d2()).is_err());
+        let truncated = utf8_mode(&[0x00, 0x4A, 0x05, 0x10]);
+        assert!(decode(KIND_CLASS, &[truncated], d2()).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! Pins the archives of a game instance so later changes can be detected
+//! before launch.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::errors::ScanError;
+use crate::utils::sha256_hex;
+
+pub const DEFAULT_LOCKFILE_NAME: &str = "collapsefindobf.lock";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedArchive {
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Lockfile {
+    /// Archives keyed by their `/`-separated path relative to the instance.
+    pub archives: BTreeMap<String, LockedArchive>,
+}
+
+#[derive(Debug, Default)]
+pub struct LockDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+}
+
+impl LockDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+impl Lockfile {
+    /// Hashes every `.jar` and `.zip` under `instance`.
+    pub fn record(instance: &Path) -> Result<Self, ScanError> {
+        let mut archives = BTreeMap::new();
+        for entry in WalkDir::new(instance).into_iter() {
+            let entry = entry.map_err(std::io::Error::from)?;
+            let path = entry.path();
+            if !entry.file_type().is_file()
+                || !path.extension().is_some_and(|ext| ext == "jar" || ext == "zip")
+            {
+                continue;
+            }
+            let data = std::fs::read(path)?;
+            let relative = path.strip_prefix(instance).unwrap_or(path);
+            let key = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            archives.insert(
+                key,
+                LockedArchive {
+                    size: data.len() as u64,
+                    sha256: sha256_hex(&data),
+                },
+            );
+        }
+        Ok(Lockfile { archives })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ScanError> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ScanError> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// What changed in `current` relative to this approved state.
+    pub fn diff(&self, current: &Lockfile) -> LockDiff {
+        let mut diff = LockDiff::default();
+        for (name, locked) in &self.archives {
+            match current.archives.get(name) {
+                None => diff.removed.push(name.clone()),
+                Some(archive) if archive != locked => diff.modified.push(name.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.added = current
+            .archives
+            .keys()
+            .filter(|name| !self.archives.contains_key(*name))
+            .cloned()
+            .collect();
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_reports_added_removed_and_modified_archives() {
+        let instance = tempfile::tempdir().unwrap();
+        let mods = instance.path().join("mods");
+        std::fs::create_dir(&mods).unwrap();
+        std::fs::write(mods.join("kept.jar"), b"kept").unwrap();
+        std::fs::write(mods.join("changed.jar"), b"before").unwrap();
+        std::fs::write(mods.join("gone.jar"), b"gone").unwrap();
+        std::fs::write(mods.join("notes.txt"), b"ignored").unwrap();
+
+        let lockfile_path = instance.path().join(DEFAULT_LOCKFILE_NAME);
+        Lockfile::record(instance.path()).unwrap().save(&lockfile_path).unwrap();
+        let locked = Lockfile::load(&lockfile_path).unwrap();
+        assert_eq!(locked.archives.len(), 3);
+        assert!(locked.diff(&Lockfile::record(instance.path()).unwrap()).is_empty());
+
+        std::fs::write(mods.join("changed.jar"), b"after").unwrap();
+        std::fs::remove_file(mods.join("gone.jar")).unwrap();
+        std::fs::write(mods.join("new.jar"), b"new").unwrap();
+
+        let diff = locked.diff(&Lockfile::record(instance.path()).unwrap());
+        assert_eq!(diff.added, ["mods/new.jar"]);
+        assert_eq!(diff.removed, ["mods/gone.jar"]);
+        assert_eq!(diff.modified, ["mods/changed.jar"]);
+    }
+}
@@ -1,20 +1,47 @@
 #![cfg_attr(all(not(debug_assertions), feature = "gui"), windows_subsystem = "windows")]
 #[macro_use]
 mod color_macros;
+mod allowlist;
+mod annotations;
+mod bytecode;
+mod cfg;
+mod clamav;
+mod class_recovery;
 mod config;
 mod detection;
+mod emulator;
 mod errors;
 mod filters;
+#[cfg(all(feature = "cli", not(feature = "gui")))]
+mod fingerprint;
+mod jar_signature;
+mod kotlin_metadata;
+#[cfg(all(feature = "cli", not(feature = "gui")))]
+mod lockfile;
+mod malware_signatures;
+mod manifest;
+mod mixin;
+mod mod_metadata;
+mod native;
 mod parser;
+mod reconstruct;
+mod reflection;
 mod scanner;
 mod types;
 mod utils;
+mod yara;
+mod zip_structure;
 
 #[cfg(feature = "gui")]
 mod gui;
 
+#[cfg(test)]
+mod test_support;
+
 #[cfg(all(feature = "cli", not(feature = "gui")))]
 use {
+    crate::annotations::AnnotationRule,
+    crate::scanner::class::MIN_REPORTED_DANGER_SCORE,
     crate::scanner::scan::CollapseFindOBFScanner,
     crate::types::{DetectionMode, FindingType, ScanResult, ScannerOptions},
     clap::Parser,
@@ -93,8 +120,458 @@ struct Args {
     #[clap(long, value_parser)]
     max_file_size: Option<usize>,
 
+    #[clap(long, value_parser)]
+    max_compression_ratio: Option<u64>,
+
+    #[clap(long, value_parser)]
+    max_total_uncompressed_mb: Option<u64>,
+
+    #[clap(long, value_parser)]
+    max_archive_entries: Option<usize>,
+
+    #[clap(long, value_parser)]
+    max_nesting_depth: Option<usize>,
+
     #[clap(long, action = clap::ArgAction::SetTrue)]
     show: bool,
+
+    /// Print the structure and annotations of every scanned class.
+    #[clap(long, action = clap::ArgAction::SetTrue)]
+    inspect: bool,
+
+    /// Flag annotations matching TYPE[:ELEMENT=VALUE], e.g. `*.EventHandler`
+    /// or `Inject:method=*getSession*`. Wildcards are allowed in both parts.
+    #[clap(long = "annotation-rule", action = clap::ArgAction::Append, value_parser = AnnotationRule::parse)]
+    annotation_rules: Vec<AnnotationRule>,
+
+    /// ClamAV `.hdb`, `.hsb` or `.ndb` database, or a directory of them.
+    #[clap(long = "clamav-db", action = clap::ArgAction::Append, value_parser)]
+    clamav_databases: Vec<PathBuf>,
+
+    /// YARA rule file, or a directory of `.yar`/`.yara` files, run against
+    /// every decompressed entry and class string pool.
+    #[clap(long = "yara", action = clap::ArgAction::Append, value_parser)]
+    yara_rules: Vec<PathBuf>,
+
+    /// Known-good digest list; matching archives and entries are not scanned.
+    #[clap(long = "allowlist", action = clap::ArgAction::Append, value_parser)]
+    allowlists: Vec<PathBuf>,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[cfg(all(feature = "cli", not(feature = "gui")))]
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Validate a malware signature pack and install it in place of the
+    /// current one.
+    UpdateSignatures {
+        #[clap(value_parser)]
+        file: PathBuf,
+    },
+    /// Record the digests of every JAR in a directory of trusted archives,
+    /// and of every entry in them, into a known-good allowlist.
+    BuildAllowlist {
+        #[clap(value_parser)]
+        dir: PathBuf,
+        /// Allowlist to create, or to extend if it already exists.
+        #[clap(long, value_parser)]
+        output: PathBuf,
+        /// Source label recorded for every digest, instead of the JAR's file name.
+        #[clap(long)]
+        label: Option<String>,
+    },
+    /// Record the name, size and SHA-256 of every archive in an instance.
+    Lock {
+        #[clap(value_parser)]
+        instance: PathBuf,
+        /// Lockfile to write; defaults to `collapsefindobf.lock` in the instance.
+        #[clap(long, value_parser)]
+        lockfile: Option<PathBuf>,
+    },
+    /// Compare an instance against its lockfile and scan the archives that
+    /// were added or modified. Exits 0 when nothing changed, 2 when archives
+    /// changed, and 3 when a changed archive has findings or cannot be scanned.
+    Verify {
+        #[clap(value_parser)]
+        instance: PathBuf,
+        /// Lockfile to check against; defaults to `collapsefindobf.lock` in the instance.
+        #[clap(long, value_parser)]
+        lockfile: Option<PathBuf>,
+    },
+    /// Compare two versions of an archive: changed classes and members,
+    /// string constants, method references, and findings new to `new`.
+    Diff {
+        #[clap(value_parser)]
+        old: PathBuf,
+        #[clap(value_parser)]
+        new: PathBuf,
+    },
+    /// Group archives by shared code, reporting the classes and IOCs each
+    /// group has in common.
+    Cluster {
+        /// Archives, or directories searched for `.jar`/`.zip` files.
+        #[clap(value_parser, required = true)]
+        paths: Vec<PathBuf>,
+        /// Similarity (0-1) at which two archives join the same cluster.
+        #[clap(long, default_value_t = 0.6, value_parser = parse_threshold)]
+        threshold: f64,
+    },
+}
+
+/// A similarity, which only means something from 0 to 1.
+#[cfg(all(feature = "cli", not(feature = "gui")))]
+fn parse_threshold(text: &str) -> Result<f64, String> {
+    let threshold: f64 = text
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", text))?;
+    if (0.0..=1.0).contains(&threshold) {
+        Ok(threshold)
+    } else {
+        Err(format!("{} is not between 0 and 1", text))
+    }
+}
+
+#[cfg(all(feature = "cli", not(feature = "gui")))]
+fn run_command(args: &Args, command: &Command) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Command::UpdateSignatures { file } => {
+            let (previous_version, database, destination) =
+                malware_signatures::install_pack(file)?;
+            println!(
+                "{} Signature pack updated: {} -> {} ({} signatures)",
+                green_text!("✅"),
+                previous_version,
+                database.version.bright_white(),
+                database.len()
+            );
+            println!("   {}", destination.display().to_string().dimmed());
+        }
+        Command::BuildAllowlist { dir, output, label } => {
+            let mut allowlist = if output.exists() {
+                allowlist::Allowlist::load(std::slice::from_ref(output))?
+            } else {
+                allowlist::Allowlist::default()
+            };
+            let (mut archives, mut entries) = (0, 0);
+            for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+                let path = entry.path();
+                if !entry.file_type().is_file()
+                    || !path.extension().is_some_and(|ext| ext == "jar" || ext == "zip")
+                {
+                    continue;
+                }
+                let source = label.clone().unwrap_or_else(|| {
+                    path.file_name().unwrap_or_default().to_string_lossy().into_owned()
+                });
+                match allowlist.add_archive(path, &source) {
+                    Ok(count) => {
+                        archives += 1;
+                        entries += count;
+                    }
+                    Err(e) => eprintln!(
+                        "{} Warning: Skipping {}: {}",
+                        yellow_text!("⚠️"),
+                        path.display(),
+                        e
+                    ),
+                }
+            }
+            allowlist.save(output)?;
+            println!(
+                "{} Allowlisted {} archives and {} entries ({} distinct digests)",
+                green_text!("✅"),
+                archives,
+                entries,
+                allowlist.archives.len() + allowlist.entries.len()
+            );
+            println!("   {}", output.display().to_string().dimmed());
+        }
+        Command::Lock { instance, lockfile } => {
+            let destination = lockfile
+                .clone()
+                .unwrap_or_else(|| instance.join(lockfile::DEFAULT_LOCKFILE_NAME));
+            let locked = lockfile::Lockfile::record(instance)?;
+            locked.save(&destination)?;
+            println!(
+                "{} Locked {} archives",
+                green_text!("🔒"),
+                locked.archives.len()
+            );
+            println!("   {}", destination.display().to_string().dimmed());
+        }
+        Command::Diff { old, new } => {
+            configure_threading(args)?;
+            let scanner = CollapseFindOBFScanner::new(create_scanner_options(args))?;
+            let diff = scanner.diff_archives(old, new)?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print_archive_diff(&diff);
+            }
+        }
+        Command::Cluster { paths, threshold } => {
+            configure_threading(args)?;
+            let scanner = CollapseFindOBFScanner::new(create_scanner_options(args))?;
+            let report = scanner.cluster_archives(paths, *threshold)?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_cluster_report(&report);
+            }
+        }
+        Command::Verify { instance, lockfile } => {
+            let code = verify_instance(args, instance, lockfile.as_deref())?;
+            if code != 0 {
+                std::process::exit(code);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "cli", not(feature = "gui")))]
+fn print_archive_diff(diff: &scanner::diff::ArchiveDiff) {
+    use scanner::diff::ClassChange;
+
+    let count = |change| diff.classes.iter().filter(|c| c.change == change).count();
+    println!(
+        "\n{} {}",
+        "🔀".bright_white().bold(),
+        format!("Diff: {} -> {}", diff.old, diff.new).bright_white().bold()
+    );
+    println!(
+        "   Classes: {} added, {} removed, {} modified, {} unchanged",
+        count(ClassChange::Added).to_string().green(),
+        count(ClassChange::Removed).to_string().red(),
+        count(ClassChange::Modified).to_string().yellow(),
+        diff.unchanged_classes
+    );
+
+    for class in &diff.classes {
+        let marker = match class.change {
+            ClassChange::Added => "+".green(),
+            ClassChange::Removed => "-".red(),
+            ClassChange::Modified => "~".yellow(),
+        };
+        println!("\n {} {}", marker, class.path.bright_cyan());
+        let sections: [(&str, &Vec<String>); 9] = [
+            ("", &class.hierarchy_changes),
+            ("+ method ", &class.added_methods),
+            ("- method ", &class.removed_methods),
+            ("+ field ", &class.added_fields),
+            ("- field ", &class.removed_fields),
+            ("~ ", &class.access_changes),
+            ("+ string ", &class.added_strings),
+            ("- string ", &class.removed_strings),
+            ("+ calls ", &class.added_method_refs),
+        ];
+        for (label, items) in sections {
+            for item in items {
+                if label.ends_with("string ") {
+                    println!("     {}{:?}", label, utils::truncate_string(item, 120));
+                } else {
+                    println!("     {}{}", label, item);
+                }
+            }
+        }
+    }
+
+    if diff.new_findings.is_empty() {
+        println!("\n{} No findings new to {}", green_text!("✅"), diff.new);
+    } else {
+        println!(
+            "\n{} {}",
+            "🚨".red().bold(),
+            "Findings only in the new version:".bright_white().bold()
+        );
+        for finding in &diff.new_findings {
+            println!(
+                "   • {}: {}: {}",
+                finding.file_path.bright_cyan(),
+                finding.finding_type,
+                finding.message
+            );
+        }
+    }
+}
+
+#[cfg(all(feature = "cli", not(feature = "gui")))]
+fn print_cluster_report(report: &scanner::cluster::ClusterReport) {
+    println!(
+        "\n{} {}",
+        "🧬".bright_white().bold(),
+        format!(
+            "{} cluster(s) across {} archives (threshold {:.2})",
+            report.clusters.len(),
+            report.archives,
+            report.threshold
+        )
+        .bright_white()
+        .bold()
+    );
+
+    for (index, cluster) in report.clusters.iter().enumerate() {
+        println!(
+            "\n {} {}",
+            format!("Cluster {}", index + 1).bright_cyan().bold(),
+            format!("({} archives, cohesion {:.2})", cluster.members.len(), cluster.cohesion).dimmed()
+        );
+        for member in &cluster.members {
+            println!("   • {}", member.path.bright_white());
+        }
+        if !cluster.shared_classes.is_empty() {
+            println!("   {}", format!("Shared classes ({}):", cluster.shared_classes.len()).yellow());
+            for shared in cluster.shared_classes.iter().take(10) {
+                let names: Vec<&str> = shared.class_names.iter().map(String::as_str).collect();
+                println!(
+                    "     {} in {} archives: {}{}",
+                    shared.structure.dimmed(),
+                    shared.archives,
+                    utils::truncate_string(&names.join(", "), 100),
+                    if shared.identical_strings { "" } else { " (strings differ)" }
+                );
+            }
+            if cluster.shared_classes.len() > 10 {
+                println!("     ... and {} more", cluster.shared_classes.len() - 10);
+            }
+        }
+        if !cluster.iocs.is_empty() {
+            println!("   {}", "IOCs:".red());
+            for (ioc, count) in &cluster.iocs {
+                println!("     {} ({}/{})", ioc, count, cluster.members.len());
+            }
+        }
+    }
+
+    if !report.unclustered.is_empty() {
+        println!(
+            "\n {} {}",
+            "Unclustered:".dimmed(),
+            report.unclustered.len()
+        );
+    }
+}
+
+#[cfg(all(feature = "cli", not(feature = "gui")))]
+fn verify_instance(
+    args: &Args,
+    instance: &Path,
+    lockfile_path: Option<&Path>,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let lockfile_path = lockfile_path
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| instance.join(lockfile::DEFAULT_LOCKFILE_NAME));
+    let locked = lockfile::Lockfile::load(&lockfile_path)?;
+    let current = lockfile::Lockfile::record(instance)?;
+    let diff = locked.diff(&current);
+
+    // Only archives that differ from the approved state are worth scanning.
+    let mut results = Vec::new();
+    let mut errors = Vec::new();
+    if !diff.added.is_empty() || !diff.modified.is_empty() {
+        configure_threading(args)?;
+        let scanner = CollapseFindOBFScanner::new(create_scanner_options(args))?;
+        for name in diff.added.iter().chain(&diff.modified) {
+            match scanner.scan_jar_file(&instance.join(name)) {
+                // The scan's own threshold, so informational findings alone
+                // do not fail verification.
+                Ok(scanned) => results.extend(
+                    scanned
+                        .into_iter()
+                        .filter(|r| {
+                            !r.matches.is_empty()
+                                && (r.danger_score >= MIN_REPORTED_DANGER_SCORE
+                                    || scanner.options.verbose)
+                        })
+                        .map(|r| (name.clone(), r)),
+                ),
+                Err(e) => errors.push((name.clone(), e.to_string())),
+            }
+        }
+    }
+
+    let code = if !results.is_empty() || !errors.is_empty() {
+        3
+    } else if !diff.is_empty() {
+        2
+    } else {
+        0
+    };
+
+    if args.json {
+        let json_output = json!({
+            "lockfile": lockfile_path.display().to_string(),
+            "added": diff.added,
+            "removed": diff.removed,
+            "modified": diff.modified,
+            "results": results
+                .iter()
+                .map(|(archive, result)| json!({ "archive": archive, "result": result }))
+                .collect::<Vec<_>>(),
+            "errors": errors
+                .iter()
+                .map(|(archive, error)| json!({ "archive": archive, "error": error }))
+                .collect::<Vec<_>>(),
+            "exit_code": code,
+        });
+        println!("{}", serde_json::to_string_pretty(&json_output)?);
+        return Ok(code);
+    }
+
+    println!(
+        "\n{} {}",
+        "🔒".bright_white().bold(),
+        format!("Lockfile: {}", lockfile_path.display()).bright_white().bold()
+    );
+    for name in &diff.added {
+        println!("   {} {}", "+ added:   ".green(), name.bright_white());
+    }
+    for name in &diff.removed {
+        println!("   {} {}", "- removed: ".red(), name.bright_white());
+    }
+    for name in &diff.modified {
+        println!("   {} {}", "~ modified:".yellow(), name.bright_white());
+    }
+
+    for (archive, result) in &results {
+        println!(
+            "\n{} {} {}",
+            "⚠️".red(),
+            format!("{} ({})", result.file_path, archive).bright_white().bold(),
+            format!("[{}/10]", result.danger_score).red()
+        );
+        for (finding_type, message) in result.matches.iter() {
+            println!("     • {}: {}", finding_type, message);
+        }
+    }
+    for (archive, error) in &errors {
+        println!(
+            "\n{} Could not scan {}: {}",
+            "❌".red(),
+            archive.bright_white(),
+            error
+        );
+    }
+
+    match code {
+        0 => println!(
+            "\n{} Instance matches the lockfile ({} archives)",
+            green_text!("✅"),
+            current.archives.len()
+        ),
+        2 => println!(
+            "\n{} {} archive(s) changed; no findings in the changed archives",
+            yellow_text!("⚠️"),
+            diff.added.len() + diff.removed.len() + diff.modified.len()
+        ),
+        _ => println!(
+            "\n{} Changed archives have findings; review before launching",
+            "❌".red()
+        ),
+    }
+    Ok(code)
 }
 
 #[cfg(all(feature = "cli", not(feature = "gui")))]
@@ -137,6 +614,11 @@ fn create_scanner_options(args: &Args) -> ScannerOptions {
         ignore_keywords_file: args.ignore_keywords.clone(),
         exclude_patterns: args.exclude.clone(),
         find_patterns: args.find.clone(),
+        annotation_rules: args.annotation_rules.clone(),
+        clamav_databases: args.clamav_databases.clone(),
+        yara_rules: args.yara_rules.clone(),
+        allowlists: args.allowlists.clone(),
+        inspect: args.inspect,
         progress: None,
     }
 }
@@ -166,6 +648,22 @@ fn apply_env_overrides(args: &Args) {
     if let Some(mb) = args.available_memory_mb {
         std::env::set_var("COLLAPSEFINDOBF_AVAILABLE_MEMORY_OVERRIDE_MB", mb.to_string());
     }
+
+    if let Some(ratio) = args.max_compression_ratio {
+        std::env::set_var("COLLAPSEFINDOBF_MAX_COMPRESSION_RATIO", ratio.to_string());
+    }
+
+    if let Some(mb) = args.max_total_uncompressed_mb {
+        std::env::set_var("COLLAPSEFINDOBF_MAX_TOTAL_UNCOMPRESSED_MB", mb.to_string());
+    }
+
+    if let Some(entries) = args.max_archive_entries {
+        std::env::set_var("COLLAPSEFINDOBF_MAX_ARCHIVE_ENTRIES", entries.to_string());
+    }
+
+    if let Some(depth) = args.max_nesting_depth {
+        std::env::set_var("COLLAPSEFINDOBF_MAX_NESTING_DEPTH", depth.to_string());
+    }
 }
 
 #[cfg(all(feature = "cli", not(feature = "gui")))]
@@ -291,6 +789,139 @@ fn print_optional_configurations(scanner: &CollapseFindOBFScanner, args: &Args)
     }
 }
 
+/// The archive a result was found in. Nested entries are reported as
+/// `outer!/entry`, nested archives under their own entry name, and
+/// everything else belongs to the scanned target.
+#[cfg(all(feature = "cli", not(feature = "gui")))]
+fn archive_of(file_path: &str, target: &str) -> String {
+    let lower = file_path.to_ascii_lowercase();
+    if lower.ends_with(".jar") || lower.ends_with(".zip") {
+        return file_path.to_string();
+    }
+    match file_path.rfind("!/") {
+        Some(end) => file_path[..end].to_string(),
+        None => target.to_string(),
+    }
+}
+
+/// Heads an archive's part of the report with the mods it declares.
+#[cfg(all(feature = "cli", not(feature = "gui")))]
+fn print_archive_header(archive: &str, identities: &[(String, mod_metadata::ModMetadata)]) {
+    let mods: Vec<&mod_metadata::ModMetadata> = identities
+        .iter()
+        .filter(|(label, _)| label == archive)
+        .map(|(_, metadata)| metadata)
+        .collect();
+    if mods.is_empty() {
+        println!("\n  {} {}", "📦".bright_white().bold(), archive.bright_cyan().bold());
+    }
+    for metadata in mods {
+        println!(
+            "\n  {} {} {}",
+            "📦".bright_white().bold(),
+            archive.bright_cyan().bold(),
+            format!("— {}", metadata.identity()).bright_white()
+        );
+        if !metadata.entrypoints.is_empty() {
+            let entrypoints: Vec<String> = metadata
+                .entrypoints
+                .iter()
+                .map(|e| format!("{} {}", e.kind, e.class_name.replace('/', ".")))
+                .collect();
+            println!("     {}", format!("Entrypoints: {}", entrypoints.join(", ")).dimmed());
+        }
+    }
+}
+
+#[cfg(all(feature = "cli", not(feature = "gui")))]
+fn print_known_good(known_good: &[(String, String)]) {
+    if known_good.is_empty() {
+        return;
+    }
+    // Library entries can run into the thousands, so only count them per source.
+    let mut per_source: std::collections::BTreeMap<&str, usize> = std::collections::BTreeMap::new();
+    for (_, source) in known_good {
+        *per_source.entry(source.as_str()).or_default() += 1;
+    }
+    println!(
+        "\n{} {}",
+        "✅".bright_white().bold(),
+        "Verified Known-Good:".bright_white().bold()
+    );
+    for (source, count) in per_source {
+        println!(
+            "   • {}: {} {}",
+            source.bright_cyan(),
+            count,
+            if count == 1 { "file" } else { "files" }
+        );
+    }
+}
+
+#[cfg(all(feature = "cli", not(feature = "gui")))]
+fn print_class_inspection(results: &[ScanResult]) {
+    println!(
+        "\n{}",
+        "╔══════════════════════════════════════════════════════════════════════════════╗"
+            .bright_blue()
+            .bold()
+    );
+    println!(
+        "{}",
+        "║                              CLASS INSPECTION                                ║"
+            .bright_blue()
+            .bold()
+    );
+    println!(
+        "{}",
+        "╚══════════════════════════════════════════════════════════════════════════════╝"
+            .bright_blue()
+            .bold()
+    );
+
+    let mut inspected: Vec<&ScanResult> = results
+        .iter()
+        .filter(|r| r.class_details.is_some())
+        .collect();
+    inspected.sort_by_key(|r| &r.file_path);
+
+    for result in inspected {
+        let Some(details) = &result.class_details else {
+            continue;
+        };
+        println!(
+            "\n  {} {}",
+            "📄".bright_white().bold(),
+            details.class_name.replace('/', ".").bright_cyan().bold()
+        );
+        println!("    {}", result.file_path.dimmed());
+        println!(
+            "    extends {}",
+            details.superclass_name.replace('/', ".").bright_white()
+        );
+        if !details.interfaces.is_empty() {
+            let interfaces: Vec<String> =
+                details.interfaces.iter().map(|i| i.replace('/', ".")).collect();
+            println!("    implements {}", interfaces.join(", ").bright_white());
+        }
+        println!(
+            "    {} fields, {} methods",
+            details.fields.len(),
+            details.methods.len()
+        );
+        println!("    {}", details.debug_info.summary().dimmed());
+        for (site, annotation) in details.annotation_sites() {
+            let retention = if annotation.visible { "" } else { " (invisible)" };
+            println!(
+                "    {} {}{}",
+                format!("{}:", site).dimmed(),
+                annotation.to_string().bright_white(),
+                retention.dimmed()
+            );
+        }
+    }
+}
+
 #[cfg(all(feature = "cli", not(feature = "gui")))]
 fn calculate_scan_score(
     sorted_significant_results: &[&ScanResult],
@@ -364,6 +995,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 fn run_cli() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
     apply_env_overrides(&args);
+    if let Some(command) = &args.command {
+        return run_command(&args, command);
+    }
     let options = create_scanner_options(&args);
 
     if !args.json {
@@ -411,6 +1045,27 @@ fn run_cli() -> Result<(), Box<dyn std::error::Error>> {
                     "total_findings": total_findings,
                     "risk_level": risk_level,
                     "score": avg_danger_score,
+                    "partial_scan": results.iter().any(|r| r.partial_scan),
+                    "mods": scanner
+                        .mod_identities
+                        .lock()
+                        .unwrap()
+                        .iter()
+                        .map(|(file_path, metadata)| json!({
+                            "file_path": file_path,
+                            "metadata": metadata,
+                        }))
+                        .collect::<Vec<_>>(),
+                    "known_good": scanner
+                        .known_good
+                        .lock()
+                        .unwrap()
+                        .iter()
+                        .map(|(file_path, source)| json!({
+                            "file_path": file_path,
+                            "source": source,
+                        }))
+                        .collect::<Vec<_>>(),
                     "results": sorted_significant_results
                 });
 
@@ -436,6 +1091,10 @@ fn run_cli() -> Result<(), Box<dyn std::error::Error>> {
                     false
                 };
 
+                if args.inspect {
+                    print_class_inspection(&results);
+                }
+
                 println!(
                     "\n{}",
                     "╔══════════════════════════════════════════════════════════════════════════════╗"
@@ -454,6 +1113,16 @@ fn run_cli() -> Result<(), Box<dyn std::error::Error>> {
                         .bright_blue()
                         .bold()
                 );
+                {
+                    let identities = scanner.mod_identities.lock().unwrap();
+                    let mut archives: Vec<&String> =
+                        identities.iter().map(|(label, _)| label).collect();
+                    archives.dedup();
+                    for archive in archives {
+                        print_archive_header(archive, &identities);
+                    }
+                }
+                print_known_good(&scanner.known_good.lock().unwrap());
 
                 if !potentially_scannable {
                     println!(
@@ -510,10 +1179,19 @@ fn run_cli() -> Result<(), Box<dyn std::error::Error>> {
                         .bold()
                     );
 
-                    let mut findings_by_type: HashMap<FindingType, Vec<(String, String)>> =
-                        HashMap::new();
-
+                    let target = path.display().to_string();
+                    let identities = scanner.mod_identities.lock().unwrap();
+                    let mut by_archive: std::collections::BTreeMap<
+                        String,
+                        HashMap<FindingType, Vec<(String, String)>>,
+                    > = std::collections::BTreeMap::new();
+                    for (label, _) in identities.iter() {
+                        by_archive.entry(label.clone()).or_default();
+                    }
                     for result in &sorted_significant_results {
+                        let findings_by_type = by_archive
+                            .entry(archive_of(&result.file_path, &target))
+                            .or_default();
                         for (finding_type, value) in result.matches.iter() {
                             findings_by_type
                                 .entry(finding_type.clone())
@@ -522,41 +1200,46 @@ fn run_cli() -> Result<(), Box<dyn std::error::Error>> {
                         }
                     }
 
-                    let mut finding_types: Vec<FindingType> =
-                        findings_by_type.keys().cloned().collect();
-                    finding_types.sort_by_key(|t| t.to_string());
-
-                    for ftype in &finding_types {
-                        let entries = findings_by_type.get(ftype).unwrap();
-                        let (icon, color) = ftype.with_emoji();
+                    // A loose class file has no archive to head its findings.
+                    let target_is_archive = archive_of(&target, "") == target;
+                    for (archive, findings_by_type) in &by_archive {
+                        if *archive != target || target_is_archive {
+                            print_archive_header(archive, &identities);
+                        }
 
-                        println!(
-                            "\n  {} {} ({})",
-                            icon.color(color).bold(),
-                            ftype.to_string().color(color).bold(),
-                            entries.len().to_string().bright_white()
-                        );
+                        let mut finding_types: Vec<&FindingType> =
+                            findings_by_type.keys().collect();
+                        finding_types.sort_by_key(|t| t.to_string());
 
-                        let mut sorted_entries = entries.clone();
-                        sorted_entries.sort_by(|a, b| {
-                            let file_cmp = a.0.cmp(&b.0);
-                            if file_cmp == std::cmp::Ordering::Equal {
-                                a.1.cmp(&b.1)
-                            } else {
-                                file_cmp
-                            }
-                        });
+                        for ftype in finding_types {
+                            let entries = &findings_by_type[ftype];
+                            let (icon, color) = ftype.with_emoji();
 
-                        for (file_path, value) in sorted_entries {
                             println!(
-                                "    • {}: {}",
-                                file_path.bright_cyan(),
-                                value.bright_white()
+                                "\n  {} {} ({})",
+                                icon.color(color).bold(),
+                                ftype.to_string().color(color).bold(),
+                                entries.len().to_string().bright_white()
                             );
+
+                            let mut sorted_entries = entries.clone();
+                            sorted_entries.sort();
+
+                            for (file_path, value) in sorted_entries {
+                                println!(
+                                    "    • {}: {}",
+                                    file_path.bright_cyan(),
+                                    value.bright_white()
+                                );
+                            }
                         }
                     }
                 }
 
+                if args.inspect {
+                    print_class_inspection(&results);
+                }
+
                 println!(
                     "\n{}",
                     "╔══════════════════════════════════════════════════════════════════════════════╗"
@@ -575,6 +1258,7 @@ fn run_cli() -> Result<(), Box<dyn std::error::Error>> {
                         .bright_blue()
                         .bold()
                 );
+                print_known_good(&scanner.known_good.lock().unwrap());
 
                 if total_findings > 0 {
                     let files_with_findings = sorted_significant_results.len();
@@ -689,17 +1373,47 @@ fn run_cli() -> Result<(), Box<dyn std::error::Error>> {
                 }
             }
 
-            let found_custom_jvm = *scanner.found_custom_jvm_indicator.lock().unwrap();
-            if found_custom_jvm && !args.json {
+            let partial: Vec<&str> = results
+                .iter()
+                .filter(|r| r.partial_scan)
+                .map(|r| r.file_path.as_str())
+                .collect();
+            if !partial.is_empty() && !args.json {
+                println!(
+                    "\n{} {}",
+                    "🧨".red().bold(),
+                    "Partial Scan:".red().bold()
+                );
+                println!(
+                    "   {}",
+                    "Resource limits stopped part of the scan; see the Resource Limit findings for what was not analyzed.".red()
+                );
+                for path in partial {
+                    println!("   • {}", path);
+                }
+            }
+
+            let unrecovered = scanner.unrecovered_class_entries.lock().unwrap();
+            if !unrecovered.is_empty() && !args.json {
                 println!(
                     "\n{} {}",
                     "👻".cyan().bold(),
-                    "Custom JVM Warning:".yellow().bold()
+                    "Undecodable Class Entries:".yellow().bold()
                 );
                 println!(
                     "   {}",
-                    "Files with unusual magic bytes detected. These may require custom JVM or ClassLoader.".yellow()
+                    format!(
+                        "{} class entr(ies) are not valid class files and could not be recovered by XOR key search or gzip/zlib/LZ4 unwrapping; they were not analyzed.",
+                        unrecovered.len()
+                    )
+                    .yellow()
                 );
+                for path in unrecovered.iter().take(5) {
+                    println!("   • {}", path);
+                }
+                if unrecovered.len() > 5 {
+                    println!("   ... and {} more", unrecovered.len() - 5);
+                }
             }
         }
         Err(e) => {
@@ -724,3 +1438,18 @@ fn run_cli() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[cfg(all(test, feature = "cli", not(feature = "gui")))]
+mod tests {
+    use super::parse_threshold;
+
+    #[test]
+    fn threshold_must_be_a_similarity() {
+        assert_eq!(parse_threshold("0.6"), Ok(0.6));
+        assert_eq!(parse_threshold("1"), Ok(1.0));
+        assert!(parse_threshold("1.5").is_err());
+        assert!(parse_threshold("-0.1").is_err());
+        assert!(parse_threshold("NaN").is_err());
+        assert!(parse_threshold("high").is_err());
+    }
+}
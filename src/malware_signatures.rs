@@ -0,0 +1,375 @@
+//! Named detections for known malware families, loaded from an offline
+//! signature pack. A signature combines entry digests, class name
+//! patterns, string/IOC sets and structural traits; see
+//! `signatures/malware.json` for the format.
+
+use std::cell::OnceCell;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use wildmatch::WildMatch;
+
+use crate::errors::ScanError;
+use crate::reflection::{self, LoadingKind};
+use crate::types::ClassDetails;
+
+/// The pack shipped with the binary, used until one is installed.
+pub const DEFAULT_PACK: &str = include_str!("../signatures/malware.json");
+const INSTALLED_PACK_FILE_NAME: &str = "collapsefindobf-signatures.json";
+
+const NETWORK_CLASSES: &[&str] = &[
+    "java/net/Socket",
+    "java/net/URL",
+    "java/net/HttpURLConnection",
+    "java/net/http/HttpClient",
+    "javax/net/ssl/HttpsURLConnection",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignaturePack {
+    pub version: String,
+    pub signatures: Vec<MalwareSignature>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MalwareSignature {
+    pub family: String,
+    pub variant: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// SHA-256 of a class or resource entry; any one matches on its own.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sha256: Vec<String>,
+    /// Wildcard patterns over the internal class name, dots or slashes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub class_patterns: Vec<String>,
+    /// Substrings looked for in the class's string constants and decrypted strings.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub strings: Vec<String>,
+    /// How many of `strings` must be present; all of them by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_strings: Option<usize>,
+    /// Named traits, or `calls:Owner.method` and `references:Class`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub traits: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Trait {
+    DefinesClasses,
+    UrlClassLoader,
+    ExtendsClassLoader,
+    NativeMethods,
+    ProcessExec,
+    Reflection,
+    Network,
+    StaticInitializer,
+    Calls { owner: String, name: String },
+    References(String),
+}
+
+impl Trait {
+    fn parse(text: &str) -> Result<Self, String> {
+        if let Some(target) = text.strip_prefix("calls:") {
+            let (owner, name) = target
+                .rsplit_once('.')
+                .filter(|(owner, name)| !owner.is_empty() && !name.is_empty())
+                .ok_or_else(|| format!("expected calls:Owner.method, got '{}'", text))?;
+            return Ok(Trait::Calls {
+                owner: owner.replace('.', "/"),
+                name: name.to_string(),
+            });
+        }
+        if let Some(class_name) = text.strip_prefix("references:") {
+            if class_name.is_empty() {
+                return Err(format!("empty class in '{}'", text));
+            }
+            return Ok(Trait::References(class_name.replace('.', "/")));
+        }
+        Ok(match text {
+            "defines_classes" => Trait::DefinesClasses,
+            "url_class_loader" => Trait::UrlClassLoader,
+            "extends_class_loader" => Trait::ExtendsClassLoader,
+            "native_methods" => Trait::NativeMethods,
+            "process_exec" => Trait::ProcessExec,
+            "reflection" => Trait::Reflection,
+            "network" => Trait::Network,
+            "static_initializer" => Trait::StaticInitializer,
+            other => return Err(format!("unknown trait '{}'", other)),
+        })
+    }
+}
+
+/// What a class exposes to trait checks, computed once per class.
+struct ClassTraits<'a> {
+    details: &'a ClassDetails,
+    loading_kinds: OnceCell<Vec<LoadingKind>>,
+}
+
+impl ClassTraits<'_> {
+    fn calls(&self, owner: &str, name: &str) -> bool {
+        (1..=self.details.constant_pool.len() as u16)
+            .filter_map(|index| self.details.member_ref(index))
+            .any(|member| member.owner == owner && member.name == name)
+    }
+
+    fn references(&self, class_name: &str) -> bool {
+        self.details
+            .referenced_classes()
+            .any(|referenced| referenced == class_name)
+    }
+
+    fn loads(&self, kind: LoadingKind) -> bool {
+        self.loading_kinds
+            .get_or_init(|| {
+                reflection::find_dynamic_loading(self.details)
+                    .into_iter()
+                    .map(|site| site.kind)
+                    .collect()
+            })
+            .contains(&kind)
+    }
+
+    fn has(&self, class_trait: &Trait) -> bool {
+        const ACC_NATIVE: u16 = 0x0100;
+
+        match class_trait {
+            Trait::DefinesClasses => self.loads(LoadingKind::DefinesClasses),
+            Trait::UrlClassLoader => self.references("java/net/URLClassLoader"),
+            Trait::ExtendsClassLoader => reflection::extends_class_loader(self.details),
+            Trait::NativeMethods => self
+                .details
+                .methods
+                .iter()
+                .any(|m| m.access_flags & ACC_NATIVE != 0),
+            Trait::ProcessExec => {
+                self.calls("java/lang/Runtime", "exec")
+                    || self.calls("java/lang/ProcessBuilder", "start")
+            }
+            Trait::Reflection => self.loads(LoadingKind::Reflection),
+            Trait::Network => NETWORK_CLASSES.iter().any(|c| self.references(c)),
+            Trait::StaticInitializer => self.details.methods.iter().any(|m| m.name == "<clinit>"),
+            Trait::Calls { owner, name } => self.calls(owner, name),
+            Trait::References(class_name) => self.references(class_name),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CompiledSignature {
+    signature: MalwareSignature,
+    class_patterns: Vec<WildMatch>,
+    traits: Vec<Trait>,
+}
+
+/// A validated pack, ready for matching.
+#[derive(Debug)]
+pub struct SignatureDatabase {
+    pub version: String,
+    signatures: Vec<CompiledSignature>,
+}
+
+/// A signature that matched, with the evidence for it.
+pub struct SignatureMatch<'a> {
+    pub signature: &'a MalwareSignature,
+    pub evidence: String,
+}
+
+impl SignatureDatabase {
+    pub fn parse(json: &str) -> Result<Self, ScanError> {
+        let pack: SignaturePack = serde_json::from_str(json)?;
+        let signatures = pack
+            .signatures
+            .into_iter()
+            .enumerate()
+            .map(|(index, signature)| {
+                compile(signature)
+                    .map_err(|e| ScanError::SignaturePackError(format!("signature {}: {}", index + 1, e)))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(SignatureDatabase {
+            version: pack.version,
+            signatures,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    /// The installed pack if there is a valid one, else the embedded pack.
+    /// The error is returned alongside so the caller can warn about it.
+    pub fn load() -> (Self, Option<ScanError>) {
+        let installed = installed_pack_path()
+            .filter(|path| path.is_file())
+            .map(|path| {
+                std::fs::read_to_string(&path)
+                    .map_err(ScanError::from)
+                    .and_then(|json| Self::parse(&json))
+            });
+        match installed {
+            Some(Ok(database)) => (database, None),
+            Some(Err(e)) => (Self::embedded(), Some(e)),
+            None => (Self::embedded(), None),
+        }
+    }
+
+    fn embedded() -> Self {
+        Self::parse(DEFAULT_PACK).expect("embedded signature pack is valid")
+    }
+
+    /// Signatures naming this digest among their `sha256` entries.
+    pub fn match_digest(&self, sha256: &str) -> Vec<SignatureMatch<'_>> {
+        self.signatures
+            .iter()
+            .filter(|compiled| compiled.signature.sha256.iter().any(|d| d == sha256))
+            .map(|compiled| SignatureMatch {
+                signature: &compiled.signature,
+                evidence: format!("SHA-256 {}", sha256),
+            })
+            .collect()
+    }
+
+    /// Signatures matching a class by digest, or by every condition group
+    /// they specify besides digests.
+    pub fn match_class(&self, details: &ClassDetails, sha256: &str) -> Vec<SignatureMatch<'_>> {
+        let mut matches = self.match_digest(sha256);
+        let class_traits = ClassTraits {
+            details,
+            loading_kinds: OnceCell::new(),
+        };
+        for compiled in &self.signatures {
+            if matches
+                .iter()
+                .any(|m| std::ptr::eq(m.signature, &compiled.signature))
+            {
+                continue;
+            }
+            if let Some(evidence) = compiled.match_structure(details, &class_traits) {
+                matches.push(SignatureMatch {
+                    signature: &compiled.signature,
+                    evidence,
+                });
+            }
+        }
+        matches
+    }
+}
+
+impl CompiledSignature {
+    fn match_structure(&self, details: &ClassDetails, class_traits: &ClassTraits) -> Option<String> {
+        let signature = &self.signature;
+        if self.class_patterns.is_empty() && signature.strings.is_empty() && self.traits.is_empty() {
+            return None;
+        }
+
+        let mut evidence = Vec::new();
+        if !self.class_patterns.is_empty() {
+            let pattern = self
+                .class_patterns
+                .iter()
+                .zip(&signature.class_patterns)
+                .find(|(pattern, _)| pattern.matches(&details.class_name))?;
+            evidence.push(format!("class name {}", pattern.1));
+        }
+
+        if !signature.strings.is_empty() {
+            let found: Vec<&str> = signature
+                .strings
+                .iter()
+                .filter(|needle| {
+                    details.strings.iter().any(|s| s.contains(needle.as_str()))
+                        || details
+                            .recovered_strings
+                            .iter()
+                            .any(|r| r.value.contains(needle.as_str()))
+                })
+                .map(String::as_str)
+                .collect();
+            if found.len() < signature.min_strings.unwrap_or(signature.strings.len()) {
+                return None;
+            }
+            evidence.push(format!("strings {}", found.join(", ")));
+        }
+
+        if !self.traits.is_empty() {
+            if !self.traits.iter().all(|t| class_traits.has(t)) {
+                return None;
+            }
+            evidence.push(format!("traits {}", signature.traits.join(", ")));
+        }
+        Some(evidence.join("; "))
+    }
+}
+
+fn compile(signature: MalwareSignature) -> Result<CompiledSignature, String> {
+    if signature.family.trim().is_empty() || signature.variant.trim().is_empty() {
+        return Err("family and variant are required".to_string());
+    }
+    let mut signature = signature;
+    for digest in &mut signature.sha256 {
+        *digest = digest.to_ascii_lowercase();
+        if digest.len() != 64 || !digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(format!("'{}' is not a SHA-256 digest", digest));
+        }
+    }
+    if let Some(min_strings) = signature.min_strings
+        && (min_strings == 0 || min_strings > signature.strings.len())
+    {
+        return Err(format!(
+            "min_strings {} is outside 1..={}",
+            min_strings,
+            signature.strings.len()
+        ));
+    }
+    if signature.sha256.is_empty()
+        && signature.class_patterns.is_empty()
+        && signature.strings.is_empty()
+        && signature.traits.is_empty()
+    {
+        return Err(format!(
+            "{} {} has no conditions",
+            signature.family, signature.variant
+        ));
+    }
+    let traits = signature
+        .traits
+        .iter()
+        .map(|t| Trait::parse(t))
+        .collect::<Result<_, _>>()?;
+    let class_patterns = signature
+        .class_patterns
+        .iter()
+        .map(|p| WildMatch::new(&p.replace('.', "/")))
+        .collect();
+    Ok(CompiledSignature {
+        signature,
+        class_patterns,
+        traits,
+    })
+}
+
+/// `COLLAPSEFINDOBF_SIGNATURES`, or a pack next to the executable.
+pub fn installed_pack_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("COLLAPSEFINDOBF_SIGNATURES") {
+        return Some(PathBuf::from(path));
+    }
+    let exe = std::env::current_exe().ok()?;
+    Some(exe.parent()?.join(INSTALLED_PACK_FILE_NAME))
+}
+
+/// Validates the pack at `source` and installs it in place of the current
+/// one. Returns the replaced version, the new database and where it went.
+#[cfg(all(feature = "cli", not(feature = "gui")))]
+pub fn install_pack(
+    source: &std::path::Path,
+) -> Result<(String, SignatureDatabase, PathBuf), ScanError> {
+    let json = std::fs::read_to_string(source)?;
+    let database = SignatureDatabase::parse(&json)?;
+    let destination = installed_pack_path().ok_or_else(|| {
+        ScanError::SignaturePackError("no location to install the pack to".to_string())
+    })?;
+    let (previous, _) = SignatureDatabase::load();
+    std::fs::write(&destination, json)?;
+    Ok((previous.version, database, destination))
+}
@@ -0,0 +1,277 @@
+pub const MANIFEST_PATH: &str = "META-INF/MANIFEST.MF";
+
+const AGENT_ATTRIBUTES: &[&str] = &["Premain-Class", "Agent-Class", "Launcher-Agent-Class"];
+const CAPABILITY_ATTRIBUTES: &[(&str, &str)] = &[
+    ("Can-Redefine-Classes", "redefine loaded classes"),
+    ("Can-Retransform-Classes", "retransform loaded classes"),
+    ("Can-Set-Native-Method-Prefix", "rewrite native method bindings"),
+];
+const MODULE_ACCESS_ATTRIBUTES: &[&str] = &["Add-Opens", "Add-Exports", "Enable-Native-Access"];
+
+/// One section of a manifest: the main section has no `Name`.
+#[derive(Debug, Clone, Default)]
+pub struct ManifestSection {
+    pub name: Option<String>,
+    pub attributes: Vec<(String, String)>,
+}
+
+impl ManifestSection {
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Manifest {
+    pub main: ManifestSection,
+    /// Per-entry sections, keyed by their `Name` attribute.
+    pub entries: Vec<ManifestSection>,
+}
+
+/// Parses the `Key: value` sections of a manifest, joining continuation
+/// lines (those starting with a single space) onto the previous value.
+pub fn parse_manifest(text: &str) -> Manifest {
+    let mut sections: Vec<ManifestSection> = vec![ManifestSection::default()];
+    let mut section_open = true;
+
+    for line in text.split("\r\n").flat_map(|l| l.split(['\n', '\r'])) {
+        if line.is_empty() {
+            section_open = false;
+            continue;
+        }
+        if !section_open {
+            sections.push(ManifestSection::default());
+            section_open = true;
+        }
+        let section = sections.last_mut().expect("at least one section");
+
+        if let Some(continuation) = line.strip_prefix(' ') {
+            if let Some((_, value)) = section.attributes.last_mut() {
+                value.push_str(continuation);
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.strip_prefix(' ').unwrap_or(value).to_string();
+        section.attributes.push((key.trim().to_string(), value));
+    }
+
+    let mut sections = sections.into_iter();
+    let main = sections.next().unwrap_or_default();
+    let entries = sections
+        .filter(|s| !s.attributes.is_empty())
+        .map(|mut s| {
+            s.name = s.attribute("Name").map(str::to_string);
+            s
+        })
+        .collect();
+    Manifest { main, entries }
+}
+
+/// Splits raw manifest bytes into sections the way signature files digest
+/// them: each section keeps its line breaks and the blank line ending it.
+pub fn raw_manifest_sections(data: &[u8]) -> Vec<&[u8]> {
+    let mut sections = Vec::new();
+    let mut start = 0;
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let line_start = pos;
+        let line_end = data[pos..]
+            .iter()
+            .position(|&b| b == b'\n' || b == b'\r')
+            .map_or(data.len(), |i| pos + i);
+        pos = if data[line_end..].starts_with(b"\r\n") {
+            line_end + 2
+        } else {
+            (line_end + 1).min(data.len())
+        };
+
+        if line_end == line_start {
+            if line_start > start {
+                sections.push(&data[start..pos]);
+            }
+            start = pos;
+        }
+    }
+    if start < data.len() {
+        sections.push(&data[start..]);
+    }
+    sections
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestIssueKind {
+    /// An agent entry point the JVM runs before or alongside the application.
+    Agent,
+    /// Instrumentation rights requested by an agent.
+    Capability,
+    ModuleAccess,
+    ClassPath,
+    MissingClass,
+    MainClass,
+}
+
+#[derive(Debug, Clone)]
+pub struct ManifestIssue {
+    pub kind: ManifestIssueKind,
+    pub message: String,
+}
+
+/// Checks the main section for agent entry points, instrumentation
+/// capabilities, module access overrides and class path injection.
+/// `has_entry` tells whether the JAR contains a given entry path.
+pub fn analyze_manifest(manifest: &Manifest, has_entry: impl Fn(&str) -> bool) -> Vec<ManifestIssue> {
+    use ManifestIssueKind::*;
+
+    let mut issues = Vec::new();
+    let mut issue = |kind, message: String| issues.push(ManifestIssue { kind, message });
+    let main = &manifest.main;
+
+    let mut declared_classes = Vec::new();
+    for key in AGENT_ATTRIBUTES {
+        if let Some(class) = main.attribute(key) {
+            issue(Agent, format!("{} declares a Java agent: {}", key, class));
+            declared_classes.push((*key, class));
+        }
+    }
+    if let Some(class) = main.attribute("Main-Class") {
+        issue(MainClass, format!("Main-Class: {}", class));
+        declared_classes.push(("Main-Class", class));
+    }
+    for (key, class) in declared_classes {
+        let path = format!("{}.class", class.trim().replace('.', "/"));
+        if !has_entry(&path) {
+            issue(
+                MissingClass,
+                format!("{} {} is not in the JAR", key, class.trim()),
+            );
+        }
+    }
+
+    for (key, capability) in CAPABILITY_ATTRIBUTES {
+        if main
+            .attribute(key)
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case("true"))
+        {
+            issue(Capability, format!("{}: agent may {}", key, capability));
+        }
+    }
+
+    for key in MODULE_ACCESS_ATTRIBUTES {
+        if let Some(value) = main.attribute(key)
+            && !value.trim().is_empty()
+        {
+            issue(ModuleAccess, format!("{}: {}", key, value.trim()));
+        }
+    }
+
+    if let Some(class_path) = main.attribute("Class-Path") {
+        for entry in class_path.split_whitespace() {
+            if let Some(reason) = unusual_class_path_entry(entry) {
+                issue(ClassPath, format!("Class-Path entry '{}' {}", entry, reason));
+            }
+        }
+    }
+
+    issues
+}
+
+/// Class-Path entries are meant to be relative URLs next to the JAR.
+fn unusual_class_path_entry(entry: &str) -> Option<&'static str> {
+    let lower = entry.to_ascii_lowercase();
+    if lower.starts_with("http:") || lower.starts_with("https:") || lower.starts_with("ftp:") {
+        Some("loads code from a remote URL")
+    } else if lower.contains(':') && !lower.starts_with("file:") && lower.find(':') != Some(1) {
+        Some("uses a non-file URL scheme")
+    } else if lower.starts_with("file:")
+        || entry.starts_with('/')
+        || entry.starts_with('\\')
+        || lower.find(':') == Some(1)
+    {
+        Some("is an absolute path")
+    } else if entry.split(['/', '\\']).any(|part| part == "..") {
+        Some("points outside the JAR's directory")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AGENT_MANIFEST: &str = "Manifest-Version: 1.0\r\n\
+        Premain-Class: p.Agent\r\n\
+        Can-Retransform-Classes: true\r\n\
+        Add-Opens: java.base/java.lang java.base/jav\r\n a.util\r\n\
+        Class-Path: lib/ok.jar https://example.com/x.jar ../up.jar /abs.jar\r\n\
+        \r\n\
+        Name: p/Agent.class\r\n\
+        SHA-256-Digest: abc=\r\n\
+        \r\n";
+
+    #[test]
+    fn flags_agents_capabilities_and_class_path() {
+        let manifest = parse_manifest(AGENT_MANIFEST);
+        assert_eq!(
+            manifest.main.attribute("add-opens"),
+            Some("java.base/java.lang java.base/java.util")
+        );
+        assert_eq!(manifest.entries.len(), 1);
+        assert_eq!(manifest.entries[0].name.as_deref(), Some("p/Agent.class"));
+
+        let issues = analyze_manifest(&manifest, |_| false);
+        let kinds: Vec<ManifestIssueKind> = issues.iter().map(|issue| issue.kind).collect();
+        use ManifestIssueKind::*;
+        assert_eq!(
+            kinds,
+            [
+                Agent,
+                MissingClass,
+                Capability,
+                ModuleAccess,
+                ClassPath,
+                ClassPath,
+                ClassPath
+            ]
+        );
+        assert!(issues[4].message.contains("remote URL"));
+        assert!(issues[5].message.contains("outside"));
+        assert!(issues[6].message.contains("absolute"));
+
+        let issues = analyze_manifest(&manifest, |path| path == "p/Agent.class");
+        assert!(!issues.iter().any(|issue| issue.kind == MissingClass));
+    }
+
+    #[test]
+    fn splits_raw_sections_with_their_line_breaks() {
+        let sections = raw_manifest_sections(AGENT_MANIFEST.as_bytes());
+        assert_eq!(sections.len(), 2);
+        assert!(sections[0].ends_with(b"x.jar ../up.jar /abs.jar\r\n\r\n"));
+        assert_eq!(
+            sections[1],
+            b"Name: p/Agent.class\r\nSHA-256-Digest: abc=\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn tolerates_malformed_manifests() {
+        let manifest = parse_manifest(" orphan continuation\rno colon here\r\r\n\nKey:value");
+        assert!(manifest.main.attributes.is_empty());
+        assert_eq!(manifest.entries.len(), 1);
+        assert_eq!(manifest.entries[0].attribute("Key"), Some("value"));
+        assert_eq!(manifest.entries[0].name, None);
+
+        for data in [&b"\r"[..], b"\n\n\r\n", b"A: b\r", b"A: b\r\n\r"] {
+            let sections = raw_manifest_sections(data);
+            assert_eq!(sections.concat(), data.trim_ascii_start());
+        }
+        assert!(analyze_manifest(&parse_manifest(""), |_| true).is_empty());
+    }
+}
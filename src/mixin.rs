@@ -0,0 +1,197 @@
+use serde_json::Value;
+
+use crate::types::{ClassDetails, ElementValue};
+
+pub const MIXIN_ANNOTATION: &str = "org/spongepowered/asm/mixin/Mixin";
+/// Manifest attribute Forge reads mixin config paths from.
+pub const MANIFEST_MIXIN_CONFIGS: &str = "MixinConfigs";
+
+/// Game and library classes a mixin has no business patching unless it is
+/// after credentials, traffic or the file system. Entries ending in `/` are
+/// package prefixes. Minecraft classes are listed under their Yarn, Mojang,
+/// MCP and intermediary names.
+const SENSITIVE_TARGETS: &[(&str, &str)] = &[
+    ("net/minecraft/client/util/Session", "session"),
+    ("net/minecraft/client/session/Session", "session"),
+    ("net/minecraft/client/User", "session"),
+    ("net/minecraft/util/Session", "session"),
+    ("net/minecraft/class_320", "session"),
+    ("com/mojang/authlib/", "authentication"),
+    ("net/minecraft/client/network/ClientLoginNetworkHandler", "authentication"),
+    ("net/minecraft/client/multiplayer/ClientHandshakePacketListenerImpl", "authentication"),
+    ("net/minecraft/client/network/NetHandlerLoginClient", "authentication"),
+    ("net/minecraft/network/ClientConnection", "networking"),
+    ("net/minecraft/network/Connection", "networking"),
+    ("net/minecraft/network/NetworkManager", "networking"),
+    ("net/minecraft/class_2535", "networking"),
+    ("io/netty/", "networking"),
+    ("java/net/", "networking"),
+    ("javax/net/", "networking"),
+    ("java/io/", "file I/O"),
+    ("java/nio/file/", "file I/O"),
+    ("java/nio/channels/", "file I/O"),
+    ("java/lang/Runtime", "process execution"),
+    ("java/lang/ProcessBuilder", "process execution"),
+    ("java/lang/ClassLoader", "class loading"),
+];
+
+#[derive(Debug, Clone)]
+pub struct MixinConfig {
+    /// Internal names of the mixin classes, for all environments.
+    pub mixins: Vec<String>,
+    /// Config plugin class, which runs before any mixin is applied.
+    pub plugin: Option<String>,
+}
+
+/// Parses a `*.mixins.json` config into the mixin classes it applies.
+pub fn parse_mixin_config(text: &str) -> Result<MixinConfig, String> {
+    let json: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let package = json.get("package").and_then(Value::as_str).unwrap_or_default();
+    let qualify = |name: &str| {
+        let name = if package.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", package, name)
+        };
+        name.replace('.', "/")
+    };
+
+    let mixins = ["mixins", "client", "server"]
+        .iter()
+        .filter_map(|key| json.get(key).and_then(Value::as_array))
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(qualify)
+        .collect();
+    let plugin = json
+        .get("plugin")
+        .and_then(Value::as_str)
+        .map(|plugin| plugin.replace('.', "/"));
+    Ok(MixinConfig { mixins, plugin })
+}
+
+/// Classes a mixin patches, from the `value` and `targets` of its `@Mixin`
+/// annotation. `None` when the class has no `@Mixin`.
+pub fn mixin_targets(details: &ClassDetails) -> Option<Vec<String>> {
+    let mixin = details
+        .annotations
+        .iter()
+        .find(|a| a.type_name() == MIXIN_ANNOTATION)?;
+
+    let mut targets = Vec::new();
+    for key in ["value", "targets"] {
+        let values = match mixin.element(key) {
+            Some(ElementValue::Array(values)) => values.iter().collect(),
+            Some(value) => vec![value],
+            None => Vec::new(),
+        };
+        for value in values {
+            let target = match value {
+                ElementValue::Class(descriptor) => descriptor
+                    .strip_prefix('L')
+                    .and_then(|d| d.strip_suffix(';'))
+                    .unwrap_or(descriptor)
+                    .to_string(),
+                ElementValue::String(name) => name.replace('.', "/"),
+                _ => continue,
+            };
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+    }
+    Some(targets)
+}
+
+/// Why a mixin target is sensitive, if it is.
+pub fn sensitive_target_category(target: &str) -> Option<&'static str> {
+    SENSITIVE_TARGETS
+        .iter()
+        .find(|(pattern, _)| {
+            if pattern.ends_with('/') {
+                target.starts_with(pattern)
+            } else {
+                target == *pattern || target.strip_prefix(pattern).is_some_and(|r| r.starts_with('$'))
+            }
+        })
+        .map(|(_, category)| *category)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_class_structure;
+    use crate::test_support::ClassBuilder;
+    use crate::types::Annotation;
+
+    fn mixin(elements: Vec<(String, ElementValue)>) -> ClassDetails {
+        let data = ClassBuilder::new("p/mixin/SessionMixin").build();
+        let mut details =
+            parse_class_structure(&data, "p/mixin/SessionMixin.class", false).unwrap();
+        details.annotations.push(Annotation {
+            type_descriptor: format!("L{};", MIXIN_ANNOTATION),
+            visible: false,
+            elements,
+        });
+        details
+    }
+
+    #[test]
+    fn resolves_config_classes_and_targets() {
+        let config = parse_mixin_config(
+            r#"{"package": "p.mixin", "plugin": "p.Plugin",
+                "mixins": ["SessionMixin"], "client": ["client.Hud"], "server": []}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.mixins,
+            ["p/mixin/SessionMixin", "p/mixin/client/Hud"]
+        );
+        assert_eq!(config.plugin.as_deref(), Some("p/Plugin"));
+
+        let details = mixin(vec![
+            (
+                "value".to_string(),
+                ElementValue::Array(vec![ElementValue::Class(
+                    "Lnet/minecraft/client/session/Session;".to_string(),
+                )]),
+            ),
+            (
+                "targets".to_string(),
+                ElementValue::String("java.io.File".to_string()),
+            ),
+        ]);
+        let targets = mixin_targets(&details).unwrap();
+        assert_eq!(
+            targets,
+            ["net/minecraft/client/session/Session", "java/io/File"]
+        );
+        let categories: Vec<_> = targets
+            .iter()
+            .map(|t| sensitive_target_category(t))
+            .collect();
+        assert_eq!(categories, [Some("session"), Some("file I/O")]);
+        assert_eq!(
+            sensitive_target_category("net/minecraft/client/User$Type"),
+            Some("session")
+        );
+        assert_eq!(
+            sensitive_target_category("net/minecraft/client/UserData"),
+            None
+        );
+    }
+
+    #[test]
+    fn tolerates_malformed_configs_and_annotations() {
+        assert!(parse_mixin_config("[").is_err());
+        let config = parse_mixin_config(r#"{"mixins": "NotAList", "client": [1, "A"]}"#).unwrap();
+        assert_eq!(config.mixins, ["A"]);
+        assert_eq!(config.plugin, None);
+
+        let details = mixin(vec![("value".to_string(), ElementValue::Int(3))]);
+        assert_eq!(mixin_targets(&details).unwrap(), Vec::<String>::new());
+        let mut plain = details.clone();
+        plain.annotations.clear();
+        assert!(mixin_targets(&plain).is_none());
+    }
+}
@@ -0,0 +1,424 @@
+use serde::Serialize;
+use serde_json::Value;
+
+/// Metadata files read by each loader, relative to the JAR root.
+pub const METADATA_FILES: &[(&str, ModLoader)] = &[
+    ("fabric.mod.json", ModLoader::Fabric),
+    ("quilt.mod.json", ModLoader::Quilt),
+    ("META-INF/mods.toml", ModLoader::Forge),
+    ("META-INF/neoforge.mods.toml", ModLoader::NeoForge),
+    ("mcmod.info", ModLoader::LegacyForge),
+    ("plugin.yml", ModLoader::Bukkit),
+    ("paper-plugin.yml", ModLoader::Paper),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ModLoader {
+    Fabric,
+    Quilt,
+    Forge,
+    NeoForge,
+    LegacyForge,
+    Bukkit,
+    Paper,
+}
+
+impl std::fmt::Display for ModLoader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModLoader::Fabric => write!(f, "Fabric"),
+            ModLoader::Quilt => write!(f, "Quilt"),
+            ModLoader::Forge => write!(f, "Forge"),
+            ModLoader::NeoForge => write!(f, "NeoForge"),
+            ModLoader::LegacyForge => write!(f, "Forge (legacy)"),
+            ModLoader::Bukkit => write!(f, "Bukkit"),
+            ModLoader::Paper => write!(f, "Paper"),
+        }
+    }
+}
+
+impl ModLoader {
+    /// Forge-style loaders find mod classes by annotation rather than
+    /// listing them; these are the annotation descriptors they look for.
+    pub fn mod_annotations(&self) -> &'static [&'static str] {
+        match self {
+            ModLoader::Forge => &["Lnet/minecraftforge/fml/common/Mod;"],
+            ModLoader::NeoForge => &["Lnet/neoforged/fml/common/Mod;"],
+            ModLoader::LegacyForge => &[
+                "Lcpw/mods/fml/common/Mod;",
+                "Lnet/minecraftforge/fml/common/Mod;",
+            ],
+            _ => &[],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Entrypoint {
+    /// The entrypoint key, e.g. `main` or `client`, or `@Mod` for annotated classes.
+    pub kind: String,
+    /// Internal class name, e.g. `com/example/ExampleMod`.
+    pub class_name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModMetadata {
+    pub loader: ModLoader,
+    pub id: String,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub entrypoints: Vec<Entrypoint>,
+    /// Mixin config files the mod asks the loader to apply.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mixin_configs: Vec<String>,
+}
+
+impl ModMetadata {
+    #[cfg(all(feature = "cli", not(feature = "gui")))]
+    /// e.g. `Example Mod (examplemod 1.0.0, Fabric)`.
+    pub fn identity(&self) -> String {
+        let mut id = self.id.clone();
+        if let Some(version) = &self.version {
+            id = format!("{} {}", id, version);
+        }
+        match &self.name {
+            Some(name) if *name != self.id => format!("{} ({}, {})", name, id, self.loader),
+            _ => format!("{} ({})", id, self.loader),
+        }
+    }
+}
+
+/// Parses one metadata file. Forge-style files can declare several mods.
+pub fn parse_mod_metadata(loader: ModLoader, text: &str) -> Result<Vec<ModMetadata>, String> {
+    match loader {
+        ModLoader::Fabric => parse_fabric(text).map(|m| vec![m]),
+        ModLoader::Quilt => parse_quilt(text).map(|m| vec![m]),
+        ModLoader::Forge | ModLoader::NeoForge => Ok(parse_mods_toml(loader, text)),
+        ModLoader::LegacyForge => parse_mcmod_info(text),
+        ModLoader::Bukkit | ModLoader::Paper => parse_plugin_yml(loader, text).map(|m| vec![m]),
+    }
+}
+
+fn parse_fabric(text: &str) -> Result<ModMetadata, String> {
+    let json: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let mut entrypoints = entrypoints_from_json(json.get("entrypoints"));
+    if let Some(adapters) = json.get("languageAdapters").and_then(Value::as_object) {
+        for class in adapters.values().filter_map(Value::as_str) {
+            entrypoints.push(entrypoint("languageAdapter", class));
+        }
+    }
+    Ok(ModMetadata {
+        loader: ModLoader::Fabric,
+        id: json_string(&json, "id").ok_or("missing \"id\"")?,
+        name: json_string(&json, "name"),
+        version: json_string(&json, "version"),
+        entrypoints,
+        mixin_configs: mixin_configs_from_json(json.get("mixins")),
+    })
+}
+
+fn parse_quilt(text: &str) -> Result<ModMetadata, String> {
+    let json: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let loader = json.get("quilt_loader").ok_or("missing \"quilt_loader\"")?;
+    Ok(ModMetadata {
+        loader: ModLoader::Quilt,
+        id: json_string(loader, "id").ok_or("missing \"id\"")?,
+        name: loader
+            .get("metadata")
+            .and_then(|metadata| json_string(metadata, "name")),
+        version: json_string(loader, "version"),
+        entrypoints: entrypoints_from_json(loader.get("entrypoints")),
+        mixin_configs: mixin_configs_from_json(json.get("mixin")),
+    })
+}
+
+/// Entrypoint values are a class name, an `{"value": ...}` object, or an
+/// array of either. `Class::member` references point into `Class`.
+fn entrypoints_from_json(entrypoints: Option<&Value>) -> Vec<Entrypoint> {
+    let Some(entrypoints) = entrypoints.and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    let mut result = Vec::new();
+    for (kind, value) in entrypoints {
+        let values = match value {
+            Value::Array(values) => values.iter().collect(),
+            other => vec![other],
+        };
+        for value in values {
+            let class = match value {
+                Value::String(class) => Some(class.as_str()),
+                Value::Object(object) => object.get("value").and_then(Value::as_str),
+                _ => None,
+            };
+            if let Some(class) = class {
+                result.push(entrypoint(kind, class));
+            }
+        }
+    }
+    result
+}
+
+/// A config path, a `{"config": ...}` object, or an array of either.
+fn mixin_configs_from_json(mixins: Option<&Value>) -> Vec<String> {
+    let values = match mixins {
+        Some(Value::Array(values)) => values.iter().collect(),
+        Some(other) => vec![other],
+        None => Vec::new(),
+    };
+    values
+        .into_iter()
+        .filter_map(|value| match value {
+            Value::String(path) => Some(path.clone()),
+            Value::Object(object) => object.get("config").and_then(Value::as_str).map(str::to_string),
+            _ => None,
+        })
+        .collect()
+}
+
+fn parse_mcmod_info(text: &str) -> Result<Vec<ModMetadata>, String> {
+    let json: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let mods = match &json {
+        Value::Array(mods) => mods,
+        Value::Object(object) => object
+            .get("modList")
+            .and_then(Value::as_array)
+            .ok_or("missing \"modList\"")?,
+        _ => return Err("expected a list of mods".to_string()),
+    };
+    Ok(mods
+        .iter()
+        .filter_map(|m| {
+            Some(ModMetadata {
+                loader: ModLoader::LegacyForge,
+                id: json_string(m, "modid")?,
+                name: json_string(m, "name"),
+                version: json_string(m, "version"),
+                entrypoints: Vec::new(),
+                mixin_configs: Vec::new(),
+            })
+        })
+        .collect())
+}
+
+/// Reads the `[[mods]]` and `[[mixins]]` tables of a `mods.toml`. Only
+/// single-line string values are needed, so this is a line scanner rather
+/// than a TOML parser.
+fn parse_mods_toml(loader: ModLoader, text: &str) -> Vec<ModMetadata> {
+    let mut mods = Vec::new();
+    let mut mixin_configs = Vec::new();
+    let mut table = String::new();
+    let mut multiline: Option<&str> = None;
+
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(delimiter) = multiline {
+            if line.contains(delimiter) {
+                multiline = None;
+            }
+            continue;
+        }
+        if line.starts_with('[') {
+            table = if line.starts_with("[[") {
+                line.trim_start_matches('[').trim_end_matches(']').trim().to_string()
+            } else {
+                String::new()
+            };
+            if table == "mods" {
+                mods.push(ModMetadata {
+                    loader,
+                    id: String::new(),
+                    name: None,
+                    version: None,
+                    entrypoints: Vec::new(),
+                    mixin_configs: Vec::new(),
+                });
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        for delimiter in ["\"\"\"", "'''"] {
+            if value.starts_with(delimiter) && !value[3..].contains(delimiter) {
+                multiline = Some(delimiter);
+            }
+        }
+        if multiline.is_some() {
+            continue;
+        }
+        let value = toml_string(value);
+        if table == "mixins" {
+            if key.trim() == "config" {
+                mixin_configs.extend(value);
+            }
+            continue;
+        }
+        let Some(current) = mods.last_mut().filter(|_| table == "mods") else {
+            continue;
+        };
+        match key.trim() {
+            "modId" => current.id = value.unwrap_or_default(),
+            "version" => current.version = value,
+            "displayName" => current.name = value,
+            _ => {}
+        }
+    }
+    mods.retain(|m| !m.id.is_empty());
+    // Mixin configs belong to the file, not to one mod.
+    if let Some(first) = mods.first_mut() {
+        first.mixin_configs = mixin_configs;
+    }
+    mods
+}
+
+fn toml_string(value: &str) -> Option<String> {
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let rest = &value[1..];
+    let end = rest.find(quote)?;
+    Some(rest[..end].to_string())
+}
+
+/// Reads the top-level scalar keys of a `plugin.yml` or `paper-plugin.yml`.
+fn parse_plugin_yml(loader: ModLoader, text: &str) -> Result<ModMetadata, String> {
+    let mut name = None;
+    let mut version = None;
+    let mut entrypoints = Vec::new();
+
+    for line in text.lines() {
+        if line.starts_with([' ', '\t', '#', '-']) {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.split(" #").next().unwrap_or_default().trim();
+        let value = value
+            .strip_prefix(['"', '\''])
+            .and_then(|v| v.strip_suffix(['"', '\'']))
+            .unwrap_or(value);
+        if value.is_empty() {
+            continue;
+        }
+        match key.trim() {
+            "name" => name = Some(value.to_string()),
+            "version" => version = Some(value.to_string()),
+            "main" | "bootstrapper" | "loader" => entrypoints.push(entrypoint(key.trim(), value)),
+            _ => {}
+        }
+    }
+
+    let id = name.clone().ok_or("missing \"name\"")?;
+    Ok(ModMetadata {
+        loader,
+        id,
+        name,
+        version,
+        entrypoints,
+        mixin_configs: Vec::new(),
+    })
+}
+
+fn json_string(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+fn entrypoint(kind: &str, reference: &str) -> Entrypoint {
+    let class = reference.split("::").next().unwrap_or(reference).trim();
+    Entrypoint {
+        kind: kind.to_string(),
+        class_name: class.replace('.', "/"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classes(metadata: &ModMetadata) -> Vec<(&str, &str)> {
+        metadata
+            .entrypoints
+            .iter()
+            .map(|e| (e.kind.as_str(), e.class_name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn reads_fabric_entrypoints_and_member_references() {
+        let text = r#"{
+            "schemaVersion": 1, "id": "example", "version": "1.0",
+            "entrypoints": {
+                "main": ["com.example.Main", {"value": "com.example.Init::init"}],
+                "client": "com.example.Client::FIELD"
+            },
+            "languageAdapters": {"kotlin": "com.example.Adapter"},
+            "mixins": ["example.mixins.json", {"config": "client.mixins.json"}]
+        }"#;
+        let mods = parse_mod_metadata(ModLoader::Fabric, text).unwrap();
+        assert_eq!(mods.len(), 1);
+        assert_eq!(mods[0].id, "example");
+        assert_eq!(
+            classes(&mods[0]),
+            [
+                ("client", "com/example/Client"),
+                ("main", "com/example/Main"),
+                ("main", "com/example/Init"),
+                ("languageAdapter", "com/example/Adapter"),
+            ]
+        );
+        assert_eq!(
+            mods[0].mixin_configs,
+            ["example.mixins.json", "client.mixins.json"]
+        );
+    }
+
+    #[test]
+    fn skips_multiline_strings_in_mods_toml() {
+        let text = r#"
+modLoader="javafml"
+[[mods]]
+modId="first"
+version="1.0"
+description='''
+modId="fake"
+[[mods]]
+modId="injected"
+'''
+displayName="First"
+[[mods]]
+modId = 'second'
+description="""one line"""
+[[mixins]]
+config="first.mixins.json"
+"#;
+        let mods = parse_mod_metadata(ModLoader::Forge, text).unwrap();
+        let ids: Vec<&str> = mods.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, ["first", "second"]);
+        assert_eq!(mods[0].name.as_deref(), Some("First"));
+        assert_eq!(mods[0].mixin_configs, ["first.mixins.json"]);
+    }
+
+    #[test]
+    fn reads_plugin_yml_scalars() {
+        let text = "name: \"Example\" # comment\nversion: 2.0\nmain: com.example.Plugin\n  main: nested.Ignored\n";
+        let mods = parse_mod_metadata(ModLoader::Bukkit, text).unwrap();
+        assert_eq!(mods[0].id, "Example");
+        assert_eq!(mods[0].version.as_deref(), Some("2.0"));
+        assert_eq!(classes(&mods[0]), [("main", "com/example/Plugin")]);
+    }
+
+    #[test]
+    fn rejects_malformed_metadata() {
+        assert!(parse_mod_metadata(ModLoader::Fabric, "{").is_err());
+        assert!(parse_mod_metadata(ModLoader::Fabric, r#"{"name": "no id"}"#).is_err());
+        assert!(parse_mod_metadata(ModLoader::Quilt, r#"{"id": "x"}"#).is_err());
+        assert!(parse_mod_metadata(ModLoader::LegacyForge, "42").is_err());
+        assert!(parse_mod_metadata(ModLoader::Bukkit, "version: 1\n").is_err());
+        let mods = parse_mod_metadata(
+            ModLoader::NeoForge,
+            "[[mods]]\nmodId=\"unterminated\ndescription='''\n",
+        )
+        .unwrap();
+        assert!(mods.is_empty());
+    }
+}
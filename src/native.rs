@@ -0,0 +1,775 @@
+use std::collections::BTreeSet;
+
+const MAX_SYMBOLS: usize = 20_000;
+const MAX_STRINGS: usize = 4_000;
+const MIN_STRING_LENGTH: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NativeFormat {
+    Elf,
+    Pe,
+    MachO,
+}
+
+impl std::fmt::Display for NativeFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NativeFormat::Elf => write!(f, "ELF"),
+            NativeFormat::Pe => write!(f, "PE"),
+            NativeFormat::MachO => write!(f, "Mach-O"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NativeLibrary {
+    pub format: NativeFormat,
+    pub architecture: String,
+    /// Shared libraries / DLLs the binary links against.
+    pub libraries: Vec<String>,
+    pub imports: Vec<String>,
+    pub exports: Vec<String>,
+    pub strings: Vec<String>,
+}
+
+/// A JNI entry point decoded from an exported `Java_*` symbol.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JniSymbol {
+    /// Internal class name, e.g. `com/example/Foo`.
+    pub class_name: String,
+    pub method_name: String,
+}
+
+/// Imports that rarely appear in a mod's helper library but are the bread
+/// and butter of injectors, stealers and droppers.
+const SUSPICIOUS_IMPORTS: &[(&str, &str)] = &[
+    ("CreateRemoteThread", "process injection"),
+    ("WriteProcessMemory", "process injection"),
+    ("VirtualAllocEx", "process injection"),
+    ("NtUnmapViewOfSection", "process injection"),
+    ("QueueUserAPC", "process injection"),
+    ("SetWindowsHookExA", "input hooking"),
+    ("SetWindowsHookExW", "input hooking"),
+    ("GetAsyncKeyState", "input hooking"),
+    ("CryptUnprotectData", "credential access"),
+    ("URLDownloadToFileA", "download and execute"),
+    ("URLDownloadToFileW", "download and execute"),
+    ("InternetOpenUrlA", "network access"),
+    ("InternetOpenUrlW", "network access"),
+    ("WinHttpOpen", "network access"),
+    ("ShellExecuteA", "process execution"),
+    ("ShellExecuteW", "process execution"),
+    ("WinExec", "process execution"),
+    ("CreateProcessA", "process execution"),
+    ("CreateProcessW", "process execution"),
+    ("RegSetValueExA", "persistence"),
+    ("RegSetValueExW", "persistence"),
+    ("IsDebuggerPresent", "anti-debugging"),
+    ("CheckRemoteDebuggerPresent", "anti-debugging"),
+    ("ptrace", "anti-debugging"),
+    ("execve", "process execution"),
+    ("execvp", "process execution"),
+    ("system", "process execution"),
+    ("popen", "process execution"),
+    ("curl_easy_perform", "network access"),
+];
+
+fn u16_at(data: &[u8], offset: usize, big_endian: bool) -> Option<u16> {
+    let bytes: [u8; 2] = data.get(offset..offset.checked_add(2)?)?.try_into().ok()?;
+    Some(if big_endian {
+        u16::from_be_bytes(bytes)
+    } else {
+        u16::from_le_bytes(bytes)
+    })
+}
+
+fn u32_at(data: &[u8], offset: usize, big_endian: bool) -> Option<u32> {
+    let bytes: [u8; 4] = data.get(offset..offset.checked_add(4)?)?.try_into().ok()?;
+    Some(if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    })
+}
+
+fn u64_at(data: &[u8], offset: usize, big_endian: bool) -> Option<u64> {
+    let bytes: [u8; 8] = data.get(offset..offset.checked_add(8)?)?.try_into().ok()?;
+    Some(if big_endian {
+        u64::from_be_bytes(bytes)
+    } else {
+        u64::from_le_bytes(bytes)
+    })
+}
+
+fn c_string_at(data: &[u8], offset: usize) -> Option<String> {
+    let tail = data.get(offset..)?;
+    let end = tail.iter().position(|&b| b == 0)?;
+    std::str::from_utf8(&tail[..end]).ok().map(str::to_string)
+}
+
+/// Identifies native binaries by magic. Fat Mach-O shares `CAFEBABE` with
+/// class files; the arch count that follows is tiny where a class file has
+/// its version (major >= 45), which tells them apart.
+pub fn sniff_native_format(data: &[u8]) -> Option<NativeFormat> {
+    match data.get(..4)? {
+        [0x7f, b'E', b'L', b'F'] => Some(NativeFormat::Elf),
+        [b'M', b'Z', ..] => {
+            let pe_offset = u32_at(data, 0x3c, false)? as usize;
+            (data.get(pe_offset..pe_offset.checked_add(4)?)? == b"PE\0\0").then_some(NativeFormat::Pe)
+        }
+        [0xfe, 0xed, 0xfa, 0xce | 0xcf] | [0xce | 0xcf, 0xfa, 0xed, 0xfe] => {
+            Some(NativeFormat::MachO)
+        }
+        [0xca, 0xfe, 0xba, 0xbe] => {
+            let count = u32_at(data, 4, true)?;
+            (count > 0 && count < 45).then_some(NativeFormat::MachO)
+        }
+        _ => None,
+    }
+}
+
+pub fn parse_native_library(data: &[u8]) -> Option<NativeLibrary> {
+    let format = sniff_native_format(data)?;
+    let mut library = match format {
+        NativeFormat::Elf => parse_elf(data)?,
+        NativeFormat::Pe => parse_pe(data)?,
+        NativeFormat::MachO => parse_macho(data)?,
+    };
+    library.strings = extract_strings(data);
+    Some(library)
+}
+
+fn empty_library(format: NativeFormat, architecture: String) -> NativeLibrary {
+    NativeLibrary {
+        format,
+        architecture,
+        libraries: Vec::new(),
+        imports: Vec::new(),
+        exports: Vec::new(),
+        strings: Vec::new(),
+    }
+}
+
+fn parse_elf(data: &[u8]) -> Option<NativeLibrary> {
+    let is_64 = *data.get(4)? == 2;
+    let be = *data.get(5)? == 2;
+    let machine = u16_at(data, 18, be)?;
+    let architecture = match machine {
+        0x03 => "x86",
+        0x3e => "x86_64",
+        0x28 => "arm",
+        0xb7 => "aarch64",
+        0xf3 => "riscv",
+        0x08 => "mips",
+        0x14 | 0x15 => "powerpc",
+        _ => "unknown",
+    };
+    let mut library = empty_library(NativeFormat::Elf, architecture.to_string());
+
+    let (sh_offset, sh_entsize, sh_count) = if is_64 {
+        (
+            u64_at(data, 0x28, be)? as usize,
+            u16_at(data, 0x3a, be)? as usize,
+            u16_at(data, 0x3c, be)? as usize,
+        )
+    } else {
+        (
+            u32_at(data, 0x20, be)? as usize,
+            u16_at(data, 0x2e, be)? as usize,
+            u16_at(data, 0x30, be)? as usize,
+        )
+    };
+
+    struct Section {
+        kind: u32,
+        offset: usize,
+        size: usize,
+        link: usize,
+        entsize: usize,
+    }
+    let sections: Vec<Section> = (0..sh_count)
+        .filter_map(|i| {
+            let base = sh_offset
+                .checked_add(i.checked_mul(sh_entsize)?)
+                .filter(|&base| base < data.len())?;
+            Some(if is_64 {
+                Section {
+                    kind: u32_at(data, base + 4, be)?,
+                    offset: u64_at(data, base + 0x18, be)? as usize,
+                    size: u64_at(data, base + 0x20, be)? as usize,
+                    link: u32_at(data, base + 0x28, be)? as usize,
+                    entsize: u64_at(data, base + 0x38, be)? as usize,
+                }
+            } else {
+                Section {
+                    kind: u32_at(data, base + 4, be)?,
+                    offset: u32_at(data, base + 0x10, be)? as usize,
+                    size: u32_at(data, base + 0x14, be)? as usize,
+                    link: u32_at(data, base + 0x18, be)? as usize,
+                    entsize: u32_at(data, base + 0x24, be)? as usize,
+                }
+            })
+        })
+        .collect();
+
+    const SHT_SYMTAB: u32 = 2;
+    const SHT_DYNAMIC: u32 = 6;
+    const SHT_DYNSYM: u32 = 11;
+    const DT_NEEDED: u64 = 1;
+
+    let mut imports = BTreeSet::new();
+    let mut exports = BTreeSet::new();
+    for section in &sections {
+        let Some(strtab) = sections.get(section.link) else {
+            continue;
+        };
+        if section.offset >= data.len() || section.entsize > 0x100 {
+            continue;
+        }
+        match section.kind {
+            SHT_SYMTAB | SHT_DYNSYM => {
+                let entsize = if section.entsize == 0 {
+                    if is_64 { 24 } else { 16 }
+                } else {
+                    section.entsize
+                };
+                for i in 0..(section.size / entsize).min(MAX_SYMBOLS) {
+                    let base = section.offset + i * entsize;
+                    let (name, info, shndx) = if is_64 {
+                        (
+                            u32_at(data, base, be),
+                            data.get(base + 4).copied(),
+                            u16_at(data, base + 6, be),
+                        )
+                    } else {
+                        (
+                            u32_at(data, base, be),
+                            data.get(base + 12).copied(),
+                            u16_at(data, base + 14, be),
+                        )
+                    };
+                    let (Some(name), Some(info), Some(shndx)) = (name, info, shndx) else {
+                        break;
+                    };
+                    let Some(name) = c_string_at(data, strtab.offset.saturating_add(name as usize)) else {
+                        continue;
+                    };
+                    let binding = info >> 4;
+                    if name.is_empty() || binding == 0 {
+                        continue;
+                    }
+                    if shndx == 0 {
+                        imports.insert(name);
+                    } else {
+                        exports.insert(name);
+                    }
+                }
+            }
+            SHT_DYNAMIC => {
+                let entsize = if is_64 { 16 } else { 8 };
+                for i in 0..section.size / entsize {
+                    let base = section.offset + i * entsize;
+                    let (tag, value) = if is_64 {
+                        (u64_at(data, base, be), u64_at(data, base + 8, be))
+                    } else {
+                        (
+                            u32_at(data, base, be).map(u64::from),
+                            u32_at(data, base + 4, be).map(u64::from),
+                        )
+                    };
+                    let (Some(tag), Some(value)) = (tag, value) else {
+                        break;
+                    };
+                    if tag == 0 {
+                        break;
+                    }
+                    if tag == DT_NEEDED
+                        && let Some(name) = c_string_at(data, strtab.offset.saturating_add(value as usize))
+                    {
+                        library.libraries.push(name);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    library.imports = imports.into_iter().collect();
+    library.exports = exports.into_iter().collect();
+    Some(library)
+}
+
+fn parse_pe(data: &[u8]) -> Option<NativeLibrary> {
+    let pe = u32_at(data, 0x3c, false)? as usize;
+    let coff = pe + 4;
+    let machine = u16_at(data, coff, false)?;
+    let section_count = u16_at(data, coff + 2, false)? as usize;
+    let optional_size = u16_at(data, coff + 16, false)? as usize;
+    let optional = coff + 20;
+    let is_64 = u16_at(data, optional, false)? == 0x20b;
+
+    let architecture = match machine {
+        0x14c => "x86",
+        0x8664 => "x86_64",
+        0xaa64 => "aarch64",
+        0x1c0 | 0x1c4 => "arm",
+        _ => "unknown",
+    };
+    let mut library = empty_library(NativeFormat::Pe, architecture.to_string());
+
+    let directories = optional + if is_64 { 112 } else { 96 };
+    let sections: Vec<(u32, u32, u32)> = (0..section_count)
+        .filter_map(|i| {
+            let base = optional + optional_size + i * 40;
+            Some((
+                u32_at(data, base + 12, false)?,
+                u32_at(data, base + 8, false)?.max(u32_at(data, base + 16, false)?),
+                u32_at(data, base + 20, false)?,
+            ))
+        })
+        .collect();
+    let rva_to_offset = |rva: u32| -> Option<usize> {
+        sections
+            .iter()
+            .find(|(va, size, _)| rva >= *va && rva < va.saturating_add(*size))
+            .map(|(va, _, raw)| (rva - va) as usize + *raw as usize)
+    };
+
+    if let Some(export_rva) = u32_at(data, directories, false).filter(|&r| r != 0)
+        && let Some(export) = rva_to_offset(export_rva)
+    {
+        let name_count = u32_at(data, export + 24, false).unwrap_or(0) as usize;
+        let names = u32_at(data, export + 32, false).and_then(rva_to_offset);
+        if let Some(names) = names {
+            for i in 0..name_count.min(MAX_SYMBOLS) {
+                let Some(name) = u32_at(data, names + i * 4, false)
+                    .and_then(rva_to_offset)
+                    .and_then(|offset| c_string_at(data, offset))
+                else {
+                    break;
+                };
+                library.exports.push(name);
+            }
+        }
+    }
+
+    if let Some(import_rva) = u32_at(data, directories + 8, false).filter(|&r| r != 0)
+        && let Some(mut descriptor) = rva_to_offset(import_rva)
+    {
+        let thunk_size = if is_64 { 8 } else { 4 };
+        let ordinal_flag = if is_64 { 1u64 << 63 } else { 1u64 << 31 };
+        while library.imports.len() < MAX_SYMBOLS {
+            let (Some(lookup), Some(name), Some(first_thunk)) = (
+                u32_at(data, descriptor, false),
+                u32_at(data, descriptor + 12, false),
+                u32_at(data, descriptor + 16, false),
+            ) else {
+                break;
+            };
+            if name == 0 {
+                break;
+            }
+            if let Some(dll) = rva_to_offset(name).and_then(|o| c_string_at(data, o)) {
+                library.libraries.push(dll);
+            }
+
+            let thunks = if lookup != 0 { lookup } else { first_thunk };
+            if let Some(mut thunk) = rva_to_offset(thunks) {
+                loop {
+                    let entry = if is_64 {
+                        u64_at(data, thunk, false)
+                    } else {
+                        u32_at(data, thunk, false).map(u64::from)
+                    };
+                    let Some(entry) = entry.filter(|&e| e != 0) else {
+                        break;
+                    };
+                    if entry & ordinal_flag == 0
+                        && let Some(function) = rva_to_offset(entry as u32)
+                            .and_then(|o| c_string_at(data, o + 2))
+                    {
+                        library.imports.push(function);
+                    }
+                    thunk += thunk_size;
+                    if library.imports.len() >= MAX_SYMBOLS {
+                        break;
+                    }
+                }
+            }
+            descriptor += 20;
+        }
+    }
+
+    Some(library)
+}
+
+fn macho_architecture(cpu_type: u32) -> &'static str {
+    match cpu_type {
+        7 => "x86",
+        0x0100_0007 => "x86_64",
+        12 => "arm",
+        0x0100_000c => "arm64",
+        18 => "powerpc",
+        _ => "unknown",
+    }
+}
+
+fn parse_macho(data: &[u8]) -> Option<NativeLibrary> {
+    if data.get(..4)? == [0xca, 0xfe, 0xba, 0xbe] {
+        let count = u32_at(data, 4, true)? as usize;
+        let mut architectures = Vec::new();
+        let mut first_slice = None;
+        for i in 0..count {
+            let base = 8 + i * 20;
+            architectures.push(macho_architecture(u32_at(data, base, true)?));
+            let offset = u32_at(data, base + 8, true)? as usize;
+            let size = u32_at(data, base + 12, true)? as usize;
+            first_slice.get_or_insert((offset, size));
+        }
+        let (offset, size) = first_slice?;
+        let slice = data.get(offset..offset.checked_add(size)?)?;
+        let mut library = parse_thin_macho(slice)?;
+        library.architecture = architectures.join("+");
+        return Some(library);
+    }
+    parse_thin_macho(data)
+}
+
+fn parse_thin_macho(data: &[u8]) -> Option<NativeLibrary> {
+    let magic = u32_at(data, 0, false)?;
+    let (is_64, be) = match magic {
+        0xfeed_facf => (true, false),
+        0xfeed_face => (false, false),
+        0xcffa_edfe => (true, true),
+        0xcefa_edfe => (false, true),
+        _ => return None,
+    };
+    let cpu_type = u32_at(data, 4, be)?;
+    let command_count = u32_at(data, 16, be)? as usize;
+    let mut library = empty_library(NativeFormat::MachO, macho_architecture(cpu_type).to_string());
+
+    const LC_SYMTAB: u32 = 0x2;
+    const LC_LOAD_DYLIB: u32 = 0xc;
+    const LC_LOAD_WEAK_DYLIB: u32 = 0x8000_0018;
+    const N_EXT: u8 = 0x01;
+    const N_TYPE: u8 = 0x0e;
+    const N_UNDF: u8 = 0x0;
+    const N_SECT: u8 = 0xe;
+
+    let mut imports = BTreeSet::new();
+    let mut exports = BTreeSet::new();
+    let mut command = if is_64 { 32 } else { 28 };
+    for _ in 0..command_count.min(4096) {
+        let kind = u32_at(data, command, be)?;
+        let size = u32_at(data, command + 4, be)? as usize;
+        match kind {
+            LC_SYMTAB => {
+                let symbols = u32_at(data, command + 8, be)? as usize;
+                let symbol_count = u32_at(data, command + 12, be)? as usize;
+                let strings = u32_at(data, command + 16, be)? as usize;
+                let entry_size = if is_64 { 16 } else { 12 };
+                for i in 0..symbol_count.min(MAX_SYMBOLS) {
+                    let base = symbols + i * entry_size;
+                    let (Some(name), Some(flags)) =
+                        (u32_at(data, base, be), data.get(base + 4).copied())
+                    else {
+                        break;
+                    };
+                    if flags & N_EXT == 0 {
+                        continue;
+                    }
+                    let Some(name) = c_string_at(data, strings.saturating_add(name as usize)) else {
+                        continue;
+                    };
+                    // Mach-O prefixes C symbols with an underscore.
+                    let name = name.strip_prefix('_').unwrap_or(&name).to_string();
+                    match flags & N_TYPE {
+                        N_UNDF => {
+                            imports.insert(name);
+                        }
+                        N_SECT => {
+                            exports.insert(name);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            LC_LOAD_DYLIB | LC_LOAD_WEAK_DYLIB => {
+                let name_offset = u32_at(data, command + 8, be)? as usize;
+                if let Some(name) = c_string_at(data, command.saturating_add(name_offset)) {
+                    library.libraries.push(name);
+                }
+            }
+            _ => {}
+        }
+        if size == 0 {
+            break;
+        }
+        command += size;
+    }
+
+    library.imports = imports.into_iter().collect();
+    library.exports = exports.into_iter().collect();
+    Some(library)
+}
+
+fn extract_strings(data: &[u8]) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut start = None;
+    for (i, &b) in data.iter().enumerate() {
+        let printable = b == b'\t' || (0x20..0x7f).contains(&b);
+        match (printable, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                if i - s >= MIN_STRING_LENGTH {
+                    strings.push(String::from_utf8_lossy(&data[s..i]).into_owned());
+                    if strings.len() >= MAX_STRINGS {
+                        return strings;
+                    }
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start
+        && data.len() - s >= MIN_STRING_LENGTH
+    {
+        strings.push(String::from_utf8_lossy(&data[s..]).into_owned());
+    }
+    strings
+}
+
+impl NativeLibrary {
+    /// Decodes exported `Java_<class>_<method>[__<signature>]` symbols.
+    pub fn jni_symbols(&self) -> Vec<JniSymbol> {
+        self.exports
+            .iter()
+            .filter_map(|export| decode_jni_symbol(export.strip_prefix("Java_")?))
+            .collect()
+    }
+
+    /// Whether the library can bind natives through `RegisterNatives`, in
+    /// which case missing `Java_*` exports prove nothing.
+    pub fn registers_natives_dynamically(&self) -> bool {
+        self.exports.iter().any(|e| e == "JNI_OnLoad")
+            || self.imports.iter().any(|i| i == "RegisterNatives")
+    }
+
+    pub fn suspicious_imports(&self) -> Vec<(&'static str, &'static str)> {
+        SUSPICIOUS_IMPORTS
+            .iter()
+            .filter(|(name, _)| self.imports.iter().any(|i| i == name))
+            .copied()
+            .collect()
+    }
+}
+
+fn decode_jni_symbol(mangled: &str) -> Option<JniSymbol> {
+    let mut decoded = String::new();
+    let mut chars = mangled.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '_' {
+            decoded.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some('1') => {
+                chars.next();
+                decoded.push('_');
+            }
+            Some('2') => {
+                chars.next();
+                decoded.push(';');
+            }
+            Some('3') => {
+                chars.next();
+                decoded.push('[');
+            }
+            Some('0') => {
+                chars.next();
+                let hex: String = chars.by_ref().take(4).collect();
+                decoded.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+            }
+            // A double underscore starts the overload signature.
+            Some('_') => break,
+            _ => decoded.push('/'),
+        }
+    }
+
+    let (class_name, method_name) = decoded.rsplit_once('/')?;
+    Some(JniSymbol {
+        class_name: class_name.to_string(),
+        method_name: method_name.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn put(image: &mut Vec<u8>, offset: usize, bytes: &[u8]) {
+        if image.len() < offset + bytes.len() {
+            image.resize(offset + bytes.len(), 0);
+        }
+        image[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    /// 64-bit little-endian ELF with a dynamic symbol table and one
+    /// `DT_NEEDED` library.
+    fn elf() -> Vec<u8> {
+        let strings = b"\0libc.so.6\0Java_p_Native_run\0system\0";
+        let (strtab, symtab, dynamic, headers) = (0x40, 0x80, 0xc8, 0x100);
+        let mut image = Vec::new();
+        put(&mut image, 0, b"\x7fELF\x02\x01\x01");
+        put(&mut image, 18, &0x3eu16.to_le_bytes());
+        put(&mut image, 0x28, &(headers as u64).to_le_bytes());
+        put(&mut image, 0x3a, &64u16.to_le_bytes());
+        put(&mut image, 0x3c, &4u16.to_le_bytes());
+        put(&mut image, strtab, strings);
+        for (i, (name, info, shndx)) in [(11u32, 0x12u8, 1u16), (29, 0x12, 0)].iter().enumerate() {
+            let symbol = symtab + (i + 1) * 24;
+            put(&mut image, symbol, &name.to_le_bytes());
+            put(&mut image, symbol + 4, &[*info]);
+            put(&mut image, symbol + 6, &shndx.to_le_bytes());
+        }
+        put(&mut image, dynamic, &1u64.to_le_bytes());
+        put(&mut image, dynamic + 8, &1u64.to_le_bytes());
+        let sections = [
+            (3u32, strtab, strings.len(), 0u32, 0u64),
+            (11, symtab, 3 * 24, 1, 24),
+            (6, dynamic, 2 * 16, 1, 16),
+        ];
+        for (i, (kind, offset, size, link, entsize)) in sections.iter().enumerate() {
+            let header = headers + (i + 1) * 64;
+            put(&mut image, header + 4, &kind.to_le_bytes());
+            put(&mut image, header + 0x18, &(*offset as u64).to_le_bytes());
+            put(&mut image, header + 0x20, &(*size as u64).to_le_bytes());
+            put(&mut image, header + 0x28, &link.to_le_bytes());
+            put(&mut image, header + 0x38, &entsize.to_le_bytes());
+        }
+        image
+    }
+
+    /// PE32+ with one section holding an export and an import directory.
+    fn pe() -> Vec<u8> {
+        let (optional, section_va, section_raw) = (0x58, 0x1000u32, 0x200usize);
+        let at = |rva: u32| section_raw + (rva - section_va) as usize;
+        let mut image = Vec::new();
+        put(&mut image, 0, b"MZ");
+        put(&mut image, 0x3c, &0x40u32.to_le_bytes());
+        put(&mut image, 0x40, b"PE\0\0");
+        put(&mut image, 0x44, &0x8664u16.to_le_bytes());
+        put(&mut image, 0x46, &1u16.to_le_bytes());
+        put(&mut image, 0x54, &240u16.to_le_bytes());
+        put(&mut image, optional, &0x20bu16.to_le_bytes());
+        put(&mut image, optional + 112, &0x1100u32.to_le_bytes());
+        put(&mut image, optional + 120, &0x1000u32.to_le_bytes());
+        let section = optional + 240;
+        put(&mut image, section + 8, &0x200u32.to_le_bytes());
+        put(&mut image, section + 12, &section_va.to_le_bytes());
+        put(&mut image, section + 16, &0x200u32.to_le_bytes());
+        put(
+            &mut image,
+            section + 20,
+            &(section_raw as u32).to_le_bytes(),
+        );
+
+        put(&mut image, at(0x1000), &0x1040u32.to_le_bytes());
+        put(&mut image, at(0x100c), &0x1060u32.to_le_bytes());
+        put(&mut image, at(0x1010), &0x1040u32.to_le_bytes());
+        put(&mut image, at(0x1040), &0x1080u64.to_le_bytes());
+        put(&mut image, at(0x1060), b"KERNEL32.dll\0");
+        put(&mut image, at(0x1082), b"CreateRemoteThread\0");
+        put(&mut image, at(0x1100 + 24), &1u32.to_le_bytes());
+        put(&mut image, at(0x1100 + 32), &0x1140u32.to_le_bytes());
+        put(&mut image, at(0x1140), &0x1150u32.to_le_bytes());
+        put(&mut image, at(0x1150), b"Java_p_Native_run\0");
+        image.resize(section_raw + 0x200, 0);
+        image
+    }
+
+    /// 64-bit little-endian Mach-O with a dylib load and a symbol table.
+    fn macho() -> Vec<u8> {
+        let (dylib, symtab, symbols, strings) = (32, 88, 112, 144);
+        let names = b"\0_Java_p_Native_run\0_ptrace\0";
+        let mut image = Vec::new();
+        put(&mut image, 0, &0xfeed_facfu32.to_le_bytes());
+        put(&mut image, 4, &0x0100_0007u32.to_le_bytes());
+        put(&mut image, 16, &2u32.to_le_bytes());
+        put(&mut image, dylib, &0xcu32.to_le_bytes());
+        put(&mut image, dylib + 4, &56u32.to_le_bytes());
+        put(&mut image, dylib + 8, &24u32.to_le_bytes());
+        put(&mut image, dylib + 24, b"/usr/lib/libSystem.B.dylib\0");
+        put(&mut image, symtab, &0x2u32.to_le_bytes());
+        put(&mut image, symtab + 4, &24u32.to_le_bytes());
+        put(&mut image, symtab + 8, &(symbols as u32).to_le_bytes());
+        put(&mut image, symtab + 12, &2u32.to_le_bytes());
+        put(&mut image, symtab + 16, &(strings as u32).to_le_bytes());
+        put(&mut image, symbols, &1u32.to_le_bytes());
+        put(&mut image, symbols + 4, &[0x0f]);
+        put(&mut image, symbols + 16, &20u32.to_le_bytes());
+        put(&mut image, symbols + 20, &[0x01]);
+        put(&mut image, strings, names);
+        image
+    }
+
+    fn native_run() -> JniSymbol {
+        JniSymbol {
+            class_name: "p/Native".to_string(),
+            method_name: "run".to_string(),
+        }
+    }
+
+    #[test]
+    fn reads_elf_symbols_and_needed_libraries() {
+        let library = parse_native_library(&elf()).unwrap();
+        assert_eq!(library.format, NativeFormat::Elf);
+        assert_eq!(library.architecture, "x86_64");
+        assert_eq!(library.libraries, ["libc.so.6"]);
+        assert_eq!(library.imports, ["system"]);
+        assert_eq!(library.jni_symbols(), [native_run()]);
+        assert_eq!(
+            library.suspicious_imports(),
+            [("system", "process execution")]
+        );
+    }
+
+    #[test]
+    fn reads_pe_exports_and_imports() {
+        let library = parse_native_library(&pe()).unwrap();
+        assert_eq!(library.format, NativeFormat::Pe);
+        assert_eq!(library.architecture, "x86_64");
+        assert_eq!(library.libraries, ["KERNEL32.dll"]);
+        assert_eq!(library.imports, ["CreateRemoteThread"]);
+        assert_eq!(library.jni_symbols(), [native_run()]);
+    }
+
+    #[test]
+    fn reads_macho_symbols_and_dylibs() {
+        let library = parse_native_library(&macho()).unwrap();
+        assert_eq!(library.format, NativeFormat::MachO);
+        assert_eq!(library.architecture, "x86_64");
+        assert_eq!(library.libraries, ["/usr/lib/libSystem.B.dylib"]);
+        assert_eq!(library.imports, ["ptrace"]);
+        assert_eq!(library.jni_symbols(), [native_run()]);
+    }
+
+    #[test]
+    fn truncated_binaries_do_not_panic() {
+        for image in [elf(), pe(), macho()] {
+            for length in 0..image.len() {
+                parse_native_library(&image[..length]);
+            }
+        }
+    }
+
+    #[test]
+    fn decodes_escaped_jni_names() {
+        let symbol = decode_jni_symbol("com_example_Foo_1bar_native__I").unwrap();
+        assert_eq!(symbol.class_name, "com/example/Foo_bar");
+        assert_eq!(symbol.method_name, "native");
+        assert_eq!(decode_jni_symbol("run"), None);
+        assert_eq!(decode_jni_symbol("p_Bad_0zz"), None);
+    }
+}
@@ -1,5 +1,8 @@
 use crate::errors::ScanError;
-use crate::types::{ClassDetails, ConstantPoolEntry, FieldInfo, MethodInfo};
+use crate::types::{
+    Annotation, BootstrapMethod, ClassDetails, CodeAttribute, ConstantPoolEntry, DebugInfo,
+    ElementValue, ExceptionTableEntry, FieldInfo, MethodInfo,
+};
 use byteorder::{BigEndian, ReadBytesExt};
 #[cfg(feature = "cli")]
 use colored::Colorize;
@@ -83,33 +86,31 @@ fn parse_constant_pool(
                 }
 
                 let utf8_bytes = &data[current_pos..end_pos];
-                let (cow, _encoding_used, _) = UTF_8.decode(utf8_bytes);
-
-                constant_pool.push(ConstantPoolEntry::Utf8(cow.into_owned()));
+                constant_pool.push(ConstantPoolEntry::Utf8(decode_modified_utf8(utf8_bytes)));
                 cursor.seek(SeekFrom::Current(length as i64))?;
                 1
             }
 
             3 | 4 => {
                 check_bounds(cursor, 4, file_path_str, "Integer/Float data")?;
+                let bits = cursor.read_u32::<BigEndian>()?;
                 constant_pool.push(if tag == 3 {
-                    ConstantPoolEntry::Integer
+                    ConstantPoolEntry::Integer(bits as i32)
                 } else {
-                    ConstantPoolEntry::Float
+                    ConstantPoolEntry::Float(f32::from_bits(bits))
                 });
-                cursor.seek(SeekFrom::Current(4))?;
                 1
             }
 
             5 | 6 => {
                 check_bounds(cursor, 8, file_path_str, "Long/Double data")?;
 
+                let bits = cursor.read_u64::<BigEndian>()?;
                 constant_pool.push(if tag == 5 {
-                    ConstantPoolEntry::Long
+                    ConstantPoolEntry::Long(bits as i64)
                 } else {
-                    ConstantPoolEntry::Double
+                    ConstantPoolEntry::Double(f64::from_bits(bits))
                 });
-                cursor.seek(SeekFrom::Current(8))?;
                 constant_pool.push(ConstantPoolEntry::Placeholder);
                 2
             }
@@ -154,37 +155,43 @@ fn parse_constant_pool(
 
             15 => {
                 check_bounds(cursor, 3, file_path_str, "MethodHandle data")?;
-                constant_pool.push(ConstantPoolEntry::MethodHandle);
-                cursor.seek(SeekFrom::Current(3))?;
+                let reference_kind = cursor.read_u8()?;
+                let reference_index = cursor.read_u16::<BigEndian>()?;
+                constant_pool.push(ConstantPoolEntry::MethodHandle(
+                    reference_kind,
+                    reference_index,
+                ));
                 1
             }
 
             16 => {
                 check_bounds(cursor, 2, file_path_str, "MethodType index")?;
-                constant_pool.push(ConstantPoolEntry::MethodType);
-                cursor.seek(SeekFrom::Current(2))?;
+                constant_pool.push(ConstantPoolEntry::MethodType(
+                    cursor.read_u16::<BigEndian>()?,
+                ));
                 1
             }
 
             17 | 18 => {
                 check_bounds(cursor, 4, file_path_str, "Dynamic/InvokeDynamic data")?;
+                let bootstrap_index = cursor.read_u16::<BigEndian>()?;
+                let name_and_type_index = cursor.read_u16::<BigEndian>()?;
                 constant_pool.push(if tag == 17 {
-                    ConstantPoolEntry::Dynamic
+                    ConstantPoolEntry::Dynamic(bootstrap_index, name_and_type_index)
                 } else {
-                    ConstantPoolEntry::InvokeDynamic
+                    ConstantPoolEntry::InvokeDynamic(bootstrap_index, name_and_type_index)
                 });
-                cursor.seek(SeekFrom::Current(4))?;
                 1
             }
 
             19 | 20 => {
                 check_bounds(cursor, 2, file_path_str, "Module/Package index")?;
+                let name_index = cursor.read_u16::<BigEndian>()?;
                 constant_pool.push(if tag == 19 {
-                    ConstantPoolEntry::Module
+                    ConstantPoolEntry::Module(name_index)
                 } else {
-                    ConstantPoolEntry::Package
+                    ConstantPoolEntry::Package(name_index)
                 });
-                cursor.seek(SeekFrom::Current(2))?;
                 1
             }
             _ => {
@@ -211,16 +218,149 @@ fn parse_constant_pool(
     Ok(constant_pool)
 }
 
-fn skip_attributes(
+/// Class files store `CONSTANT_Utf8` as modified UTF-8: NUL is `C0 80` and
+/// supplementary characters are encoded as surrogate pairs. Plain UTF-8
+/// decoding mangles both, which matters for encrypted string constants.
+fn decode_modified_utf8(bytes: &[u8]) -> String {
+    // Modified UTF-8 only differs for NUL and supplementary characters, both
+    // invalid as standard UTF-8, so most entries take the fast path.
+    if let Some(text) = UTF_8.decode_without_bom_handling_and_without_replacement(bytes) {
+        return text.into_owned();
+    }
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        let continuation = |offset: usize| {
+            bytes
+                .get(i + offset)
+                .filter(|&&c| c & 0xc0 == 0x80)
+                .map(|&c| (c & 0x3f) as u16)
+        };
+        if b & 0x80 == 0 {
+            units.push(b as u16);
+            i += 1;
+        } else if b & 0xe0 == 0xc0
+            && let Some(c1) = continuation(1)
+        {
+            units.push(((b & 0x1f) as u16) << 6 | c1);
+            i += 2;
+        } else if b & 0xf0 == 0xe0
+            && let (Some(c1), Some(c2)) = (continuation(1), continuation(2))
+        {
+            units.push(((b & 0x0f) as u16) << 12 | c1 << 6 | c2);
+            i += 3;
+        } else {
+            units.push(0xfffd);
+            i += 1;
+        }
+    }
+    String::from_utf16_lossy(&units)
+}
+
+fn utf8_at(pool: &[ConstantPoolEntry], index: u16) -> Option<&str> {
+    match pool.get((index as usize).checked_sub(1)?) {
+        Some(ConstantPoolEntry::Utf8(s)) => Some(s.as_str()),
+        _ => None,
+    }
+}
+
+fn parse_code_attribute(
+    cursor: &mut Cursor<&[u8]>,
+    attribute_length: u64,
+    constant_pool: &[ConstantPoolEntry],
+    file_path_str: &str,
+) -> Result<CodeAttribute, ScanError> {
+    let attribute_end = cursor.position() + attribute_length;
+
+    check_bounds(cursor, 8, file_path_str, "Code header")?;
+    let max_stack = cursor.read_u16::<BigEndian>()?;
+    let max_locals = cursor.read_u16::<BigEndian>()?;
+    let code_length = cursor.read_u32::<BigEndian>()? as u64;
+
+    check_bounds(
+        cursor,
+        code_length,
+        file_path_str,
+        &format!("Code bytes (len {})", code_length),
+    )?;
+    let code_start = cursor.position() as usize;
+    let code = cursor.get_ref()[code_start..code_start + code_length as usize].to_vec();
+    cursor.seek(SeekFrom::Current(code_length as i64))?;
+
+    check_bounds(cursor, 2, file_path_str, "exception_table_length")?;
+    let exception_table_length = cursor.read_u16::<BigEndian>()?;
+    check_bounds(
+        cursor,
+        exception_table_length as u64 * 8,
+        file_path_str,
+        "exception table",
+    )?;
+    let mut exception_table = Vec::with_capacity(exception_table_length as usize);
+    for _ in 0..exception_table_length {
+        exception_table.push(ExceptionTableEntry {
+            start_pc: cursor.read_u16::<BigEndian>()?,
+            end_pc: cursor.read_u16::<BigEndian>()?,
+            handler_pc: cursor.read_u16::<BigEndian>()?,
+            catch_type: cursor.read_u16::<BigEndian>()?,
+        });
+    }
+
+    // Only the presence of the debug tables matters, so a truncated
+    // attribute list is not an error.
+    let mut has_line_numbers = false;
+    let mut has_local_variables = false;
+    if check_bounds(cursor, 2, file_path_str, "Code attributes_count").is_ok() {
+        let attributes_count = cursor.read_u16::<BigEndian>()?;
+        for _ in 0..attributes_count {
+            if check_bounds(cursor, 6, file_path_str, "Code attribute header").is_err() {
+                break;
+            }
+            let name_index = cursor.read_u16::<BigEndian>()?;
+            let length = cursor.read_u32::<BigEndian>()? as u64;
+            match utf8_at(constant_pool, name_index) {
+                Some("LineNumberTable") => has_line_numbers = true,
+                Some("LocalVariableTable") => has_local_variables = true,
+                _ => {}
+            }
+            if cursor.position() + length > attribute_end {
+                break;
+            }
+            cursor.seek(SeekFrom::Current(length as i64))?;
+        }
+    }
+
+    cursor.seek(SeekFrom::Start(attribute_end))?;
+
+    Ok(CodeAttribute {
+        max_stack,
+        max_locals,
+        code,
+        exception_table,
+        has_line_numbers,
+        has_local_variables,
+    })
+}
+
+#[derive(Default)]
+struct MemberAttributes {
+    code: Option<CodeAttribute>,
+    annotations: Vec<Annotation>,
+    parameter_annotations: Vec<Vec<Annotation>>,
+}
+
+/// Reads the attributes of a field or method, keeping its `Code` and
+/// annotations and skipping the rest.
+fn parse_member_attributes(
     cursor: &mut Cursor<&[u8]>,
     attributes_count: u16,
+    constant_pool: &[ConstantPoolEntry],
     file_path_str: &str,
-    _member_type: &str,
-    _member_index: u16,
-) -> Result<(), ScanError> {
+) -> Result<MemberAttributes, ScanError> {
+    let mut attributes = MemberAttributes::default();
     for _attr_index in 0..attributes_count {
         check_bounds(cursor, 6, file_path_str, "attribute header")?;
-        let _attribute_name_index = cursor.read_u16::<BigEndian>()?;
+        let attribute_name_index = cursor.read_u16::<BigEndian>()?;
         let attribute_length = cursor.read_u32::<BigEndian>()? as u64;
 
         check_bounds(
@@ -229,10 +369,263 @@ fn skip_attributes(
             file_path_str,
             &format!("attribute data (len {})", attribute_length),
         )?;
+        let attribute_end = cursor.position() + attribute_length;
+
+        // As at class level, a malformed annotation only loses the annotations.
+        match utf8_at(constant_pool, attribute_name_index) {
+            Some("Code") if attributes.code.is_none() => {
+                attributes.code = Some(parse_code_attribute(
+                    cursor,
+                    attribute_length,
+                    constant_pool,
+                    file_path_str,
+                )?);
+            }
+            Some(name @ ("RuntimeVisibleAnnotations" | "RuntimeInvisibleAnnotations")) => {
+                let visible = name == "RuntimeVisibleAnnotations";
+                if let Ok(parsed) = parse_annotations(cursor, constant_pool, file_path_str, visible) {
+                    attributes.annotations.extend(parsed);
+                }
+            }
+            Some(
+                name @ ("RuntimeVisibleParameterAnnotations"
+                | "RuntimeInvisibleParameterAnnotations"),
+            ) => {
+                let visible = name == "RuntimeVisibleParameterAnnotations";
+                if let Ok(parsed) =
+                    parse_parameter_annotations(cursor, constant_pool, file_path_str, visible)
+                {
+                    if attributes.parameter_annotations.len() < parsed.len() {
+                        attributes.parameter_annotations.resize(parsed.len(), Vec::new());
+                    }
+                    for (all, parameter) in attributes.parameter_annotations.iter_mut().zip(parsed) {
+                        all.extend(parameter);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        cursor.seek(SeekFrom::Start(attribute_end))?;
+    }
+    Ok(attributes)
+}
+
+/// Annotations nest through element values; real code never goes this deep.
+const MAX_ANNOTATION_DEPTH: usize = 16;
+
+fn parse_annotations(
+    cursor: &mut Cursor<&[u8]>,
+    constant_pool: &[ConstantPoolEntry],
+    file_path_str: &str,
+    visible: bool,
+) -> Result<Vec<Annotation>, ScanError> {
+    check_bounds(cursor, 2, file_path_str, "num_annotations")?;
+    let num_annotations = cursor.read_u16::<BigEndian>()?;
+    let mut annotations = Vec::with_capacity(num_annotations as usize);
+    for _ in 0..num_annotations {
+        annotations.push(parse_annotation(cursor, constant_pool, file_path_str, visible, 0)?);
+    }
+    Ok(annotations)
+}
+
+fn parse_parameter_annotations(
+    cursor: &mut Cursor<&[u8]>,
+    constant_pool: &[ConstantPoolEntry],
+    file_path_str: &str,
+    visible: bool,
+) -> Result<Vec<Vec<Annotation>>, ScanError> {
+    check_bounds(cursor, 1, file_path_str, "num_parameters")?;
+    let num_parameters = cursor.read_u8()?;
+    (0..num_parameters)
+        .map(|_| parse_annotations(cursor, constant_pool, file_path_str, visible))
+        .collect()
+}
+
+fn parse_annotation(
+    cursor: &mut Cursor<&[u8]>,
+    constant_pool: &[ConstantPoolEntry],
+    file_path_str: &str,
+    visible: bool,
+    depth: usize,
+) -> Result<Annotation, ScanError> {
+    check_bounds(cursor, 4, file_path_str, "annotation header")?;
+    let type_index = cursor.read_u16::<BigEndian>()?;
+    let num_pairs = cursor.read_u16::<BigEndian>()?;
+    let mut elements = Vec::with_capacity(num_pairs as usize);
+    for _ in 0..num_pairs {
+        check_bounds(cursor, 2, file_path_str, "annotation element name")?;
+        let name_index = cursor.read_u16::<BigEndian>()?;
+        let value = parse_element_value(cursor, constant_pool, file_path_str, visible, depth)?;
+        elements.push((
+            utf8_at(constant_pool, name_index).unwrap_or_default().to_string(),
+            value,
+        ));
+    }
+    Ok(Annotation {
+        type_descriptor: utf8_at(constant_pool, type_index)
+            .unwrap_or_default()
+            .to_string(),
+        visible,
+        elements,
+    })
+}
+
+fn parse_element_value(
+    cursor: &mut Cursor<&[u8]>,
+    constant_pool: &[ConstantPoolEntry],
+    file_path_str: &str,
+    visible: bool,
+    depth: usize,
+) -> Result<ElementValue, ScanError> {
+    let malformed = |msg: String| ScanError::ClassParseError {
+        path: file_path_str.to_string(),
+        msg,
+    };
+    if depth > MAX_ANNOTATION_DEPTH {
+        return Err(malformed("Annotation nesting too deep".to_string()));
+    }
+    check_bounds(cursor, 3, file_path_str, "element_value")?;
+    let tag = cursor.read_u8()?;
+    if tag == b'@' {
+        return Ok(ElementValue::Annotation(Box::new(parse_annotation(
+            cursor,
+            constant_pool,
+            file_path_str,
+            visible,
+            depth + 1,
+        )?)));
+    }
+    let index = cursor.read_u16::<BigEndian>()?;
+    let constant = constant_pool.get((index as usize).wrapping_sub(1));
+    let int = || match constant {
+        Some(ConstantPoolEntry::Integer(v)) => Ok(*v),
+        _ => Err(malformed(format!("Element value {} is not an Integer", index))),
+    };
+    let utf8 = |index: u16| {
+        utf8_at(constant_pool, index)
+            .map(str::to_string)
+            .ok_or_else(|| malformed(format!("Element value {} is not a Utf8", index)))
+    };
+
+    Ok(match tag {
+        b'B' | b'I' | b'S' => ElementValue::Int(int()?),
+        b'Z' => ElementValue::Boolean(int()? != 0),
+        b'C' => ElementValue::Char(char::from_u32(int()? as u32).unwrap_or('\u{fffd}')),
+        b'J' => match constant {
+            Some(ConstantPoolEntry::Long(v)) => ElementValue::Long(*v),
+            _ => return Err(malformed(format!("Element value {} is not a Long", index))),
+        },
+        b'F' => match constant {
+            Some(ConstantPoolEntry::Float(v)) => ElementValue::Float(*v),
+            _ => return Err(malformed(format!("Element value {} is not a Float", index))),
+        },
+        b'D' => match constant {
+            Some(ConstantPoolEntry::Double(v)) => ElementValue::Double(*v),
+            _ => return Err(malformed(format!("Element value {} is not a Double", index))),
+        },
+        b's' => ElementValue::String(utf8(index)?),
+        b'c' => ElementValue::Class(utf8(index)?),
+        b'e' => {
+            check_bounds(cursor, 2, file_path_str, "enum constant name")?;
+            let name_index = cursor.read_u16::<BigEndian>()?;
+            ElementValue::Enum {
+                type_descriptor: utf8(index)?,
+                name: utf8(name_index)?,
+            }
+        }
+        b'[' => {
+            let mut values = Vec::with_capacity(index as usize);
+            for _ in 0..index {
+                values.push(parse_element_value(
+                    cursor,
+                    constant_pool,
+                    file_path_str,
+                    visible,
+                    depth + 1,
+                )?);
+            }
+            ElementValue::Array(values)
+        }
+        other => {
+            return Err(malformed(format!(
+                "Unknown element_value tag {:#04x}",
+                other
+            )));
+        }
+    })
+}
+
+#[derive(Default)]
+struct ClassAttributes {
+    bootstrap_methods: Vec<BootstrapMethod>,
+    annotations: Vec<Annotation>,
+    source_file: Option<String>,
+}
+
+fn parse_class_attributes(
+    cursor: &mut Cursor<&[u8]>,
+    constant_pool: &[ConstantPoolEntry],
+    file_path_str: &str,
+) -> Result<ClassAttributes, ScanError> {
+    let mut attributes = ClassAttributes::default();
+
+    check_bounds(cursor, 2, file_path_str, "class attributes_count")?;
+    let attributes_count = cursor.read_u16::<BigEndian>()?;
+    for _attr_index in 0..attributes_count {
+        check_bounds(cursor, 6, file_path_str, "class attribute header")?;
+        let attribute_name_index = cursor.read_u16::<BigEndian>()?;
+        let attribute_length = cursor.read_u32::<BigEndian>()? as u64;
+
+        check_bounds(
+            cursor,
+            attribute_length,
+            file_path_str,
+            &format!("class attribute data (len {})", attribute_length),
+        )?;
+        let attribute_end = cursor.position() + attribute_length;
+
+        let attribute_name = utf8_at(constant_pool, attribute_name_index);
+        if let Some(name @ ("RuntimeVisibleAnnotations" | "RuntimeInvisibleAnnotations")) =
+            attribute_name
+        {
+            // A malformed annotation only loses the annotations, not the class.
+            let visible = name == "RuntimeVisibleAnnotations";
+            if let Ok(parsed) = parse_annotations(cursor, constant_pool, file_path_str, visible) {
+                attributes.annotations.extend(parsed);
+            }
+        } else if attribute_name == Some("SourceFile") {
+            check_bounds(cursor, 2, file_path_str, "sourcefile_index")?;
+            let index = cursor.read_u16::<BigEndian>()?;
+            attributes.source_file = utf8_at(constant_pool, index).map(str::to_string);
+        } else if attribute_name == Some("BootstrapMethods") {
+            check_bounds(cursor, 2, file_path_str, "num_bootstrap_methods")?;
+            let num_bootstrap_methods = cursor.read_u16::<BigEndian>()?;
+            for _ in 0..num_bootstrap_methods {
+                check_bounds(cursor, 4, file_path_str, "bootstrap method header")?;
+                let method_ref = cursor.read_u16::<BigEndian>()?;
+                let num_arguments = cursor.read_u16::<BigEndian>()?;
+                check_bounds(
+                    cursor,
+                    num_arguments as u64 * 2,
+                    file_path_str,
+                    "bootstrap arguments",
+                )?;
+                let mut arguments = Vec::with_capacity(num_arguments as usize);
+                for _ in 0..num_arguments {
+                    arguments.push(cursor.read_u16::<BigEndian>()?);
+                }
+                attributes.bootstrap_methods.push(BootstrapMethod {
+                    method_ref,
+                    arguments,
+                });
+            }
+        }
 
-        cursor.seek(SeekFrom::Current(attribute_length as i64))?;
+        cursor.seek(SeekFrom::Start(attribute_end))?;
     }
-    Ok(())
+
+    Ok(attributes)
 }
 
 pub fn parse_class_structure(
@@ -260,7 +653,7 @@ pub fn parse_class_structure(
         });
     }
     let _minor_version = cursor.read_u16::<BigEndian>()?;
-    let _major_version = cursor.read_u16::<BigEndian>()?;
+    let major_version = cursor.read_u16::<BigEndian>()?;
     let cp_count = cursor.read_u16::<BigEndian>()?;
     if cp_count == 0 {
         return Err(ScanError::ClassParseError {
@@ -403,17 +796,17 @@ pub fn parse_class_structure(
             format!("<INVALID_DESCRIPTOR_INDEX_{}>", descriptor_index)
         });
 
-        skip_attributes(
+        let attributes = parse_member_attributes(
             &mut cursor,
             attributes_count,
+            &constant_pool,
             original_path_str,
-            "field",
-            f_idx,
         )?;
         fields.push(FieldInfo {
             name: field_name,
             descriptor: field_descriptor,
             access_flags: field_access_flags,
+            annotations: attributes.annotations,
         });
     }
 
@@ -459,20 +852,40 @@ pub fn parse_class_structure(
             format!("<INVALID_DESCRIPTOR_INDEX_{}>", descriptor_index)
         });
 
-        skip_attributes(
+        let attributes = parse_member_attributes(
             &mut cursor,
             attributes_count,
+            &constant_pool,
             original_path_str,
-            "method",
-            m_idx,
         )?;
         methods.push(MethodInfo {
             name: method_name,
             descriptor: method_descriptor,
             access_flags: method_access_flags,
+            annotations: attributes.annotations,
+            parameter_annotations: attributes.parameter_annotations,
+            code: attributes.code,
         });
     }
 
+    let class_attributes = parse_class_attributes(&mut cursor, &constant_pool, original_path_str)
+        .unwrap_or_else(|e| {
+            if verbose {
+                eprintln!("{} Class attribute parse error: {}", warn_prefix!(), e);
+            }
+            ClassAttributes::default()
+        });
+    let code_attributes: Vec<&CodeAttribute> = methods.iter().filter_map(|m| m.code.as_ref()).collect();
+    let debug_info = DebugInfo {
+        source_file: class_attributes.source_file,
+        methods_with_code: code_attributes.len(),
+        methods_with_line_numbers: code_attributes.iter().filter(|c| c.has_line_numbers).count(),
+        methods_with_local_variables: code_attributes
+            .iter()
+            .filter(|c| c.has_local_variables)
+            .count(),
+    };
+
     let mut string_set = HashSet::with_capacity(constant_pool.len() / 4);
     for entry in &constant_pool {
         match entry {
@@ -503,11 +916,65 @@ pub fn parse_class_structure(
 
     Ok(ClassDetails {
         class_name,
+        major_version,
         superclass_name,
         interfaces,
         methods,
         fields,
         strings,
         access_flags,
+        recovered_strings: Vec::new(),
+        annotations: class_attributes.annotations,
+        debug_info,
+        constant_pool,
+        bootstrap_methods: class_attributes.bootstrap_methods,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::ClassBuilder;
+
+    const RETURN: &[u8] = &[0xb1];
+
+    fn debug_class() -> Vec<u8> {
+        let mut class = ClassBuilder::new("p/Debug");
+        class
+            .method(
+                0x0009,
+                "a",
+                "()V",
+                RETURN,
+                &["LineNumberTable", "LocalVariableTable"],
+            )
+            .method(0x0009, "b", "()V", RETURN, &["LineNumberTable"])
+            .method(0x0009, "c", "()V", RETURN, &[])
+            .source_file("Debug.java");
+        class.build()
+    }
+
+    #[test]
+    fn counts_debug_tables_and_source_file() {
+        let details = parse_class_structure(&debug_class(), "p/Debug.class", false).unwrap();
+        let debug_info = details.debug_info;
+        assert_eq!(debug_info.source_file.as_deref(), Some("Debug.java"));
+        assert_eq!(debug_info.methods_with_code, 3);
+        assert_eq!(debug_info.methods_with_line_numbers, 2);
+        assert_eq!(debug_info.methods_with_local_variables, 1);
+    }
+
+    #[test]
+    fn truncated_debug_info_does_not_panic() {
+        let data = debug_class();
+        for length in 0..data.len() {
+            let _ = parse_class_structure(&data[..length], "p/Debug.class", false);
+        }
+
+        // A cut-off class attribute loses SourceFile but keeps the methods.
+        let details =
+            parse_class_structure(&data[..data.len() - 2], "p/Debug.class", false).unwrap();
+        assert_eq!(details.debug_info.source_file, None);
+        assert_eq!(details.debug_info.methods_with_line_numbers, 2);
+    }
+}
@@ -0,0 +1,761 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::bytecode::{self, ConstantValue, Instruction, Operand};
+use crate::types::{ClassDetails, MethodInfo, RecoveredString, StringSource};
+
+const MAX_RECOVERED_STRINGS_PER_CLASS: usize = 256;
+const MAX_TRACKED_STACK_DEPTH: usize = 256;
+const MAX_TRACKED_ARRAY_LENGTH: i64 = 4096;
+
+#[derive(Debug, Clone)]
+enum Value {
+    Str(String),
+    Int(i64),
+    Builder(usize),
+    Array(usize),
+    NewString(usize),
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+struct ArrayState {
+    element_type: u8,
+    elements: Vec<i64>,
+    stores: usize,
+    poisoned: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct BuilderState {
+    content: String,
+    pieces: usize,
+    poisoned: bool,
+}
+
+struct MethodState<'a> {
+    details: &'a ClassDetails,
+    method_name: String,
+    stack: Vec<Value>,
+    locals: HashMap<u16, Value>,
+    builders: Vec<BuilderState>,
+    arrays: Vec<ArrayState>,
+    new_strings: usize,
+    recovered: Vec<RecoveredString>,
+}
+
+/// Rebuilds strings that javac split across `StringBuilder.append` chains
+/// or `makeConcatWithConstants` call sites, and strings decoded from
+/// constant `char[]`/`byte[]` initialisers, tracking only constant operands.
+pub fn reconstruct_strings(details: &ClassDetails) -> Vec<RecoveredString> {
+    let pool_strings: HashSet<&str> = details.strings.iter().map(String::as_str).collect();
+    let mut seen = HashSet::new();
+    let mut recovered = Vec::new();
+
+    for method in &details.methods {
+        for candidate in reconstruct_method(details, method) {
+            if recovered.len() >= MAX_RECOVERED_STRINGS_PER_CLASS {
+                return recovered;
+            }
+            if pool_strings.contains(candidate.value.as_str()) {
+                continue;
+            }
+            if seen.insert((candidate.value.clone(), candidate.method.clone())) {
+                recovered.push(candidate);
+            }
+        }
+    }
+
+    recovered
+}
+
+fn reconstruct_method(details: &ClassDetails, method: &MethodInfo) -> Vec<RecoveredString> {
+    let Some(code) = &method.code else {
+        return Vec::new();
+    };
+
+    let instructions = bytecode::decode(&code.code);
+    let mut jump_targets: HashSet<usize> = instructions
+        .iter()
+        .flat_map(Instruction::branch_targets)
+        .filter_map(|target| usize::try_from(target).ok())
+        .collect();
+    jump_targets.extend(
+        code.exception_table
+            .iter()
+            .map(|entry| entry.handler_pc as usize),
+    );
+
+    let mut state = MethodState {
+        details,
+        method_name: bytecode::qualified_method_name(
+            &details.class_name,
+            &method.name,
+            &method.descriptor,
+        ),
+        stack: Vec::new(),
+        locals: HashMap::new(),
+        builders: Vec::new(),
+        arrays: Vec::new(),
+        new_strings: 0,
+        recovered: Vec::new(),
+    };
+
+    for instruction in &instructions {
+        if jump_targets.contains(&instruction.offset) {
+            state.stack.clear();
+            state.locals.clear();
+        }
+        state.step(instruction);
+        if instruction.ends_block() || state.stack.len() > MAX_TRACKED_STACK_DEPTH {
+            state.stack.clear();
+        }
+    }
+
+    state.recovered
+}
+
+impl MethodState<'_> {
+    fn pop(&mut self) -> Value {
+        self.stack.pop().unwrap_or(Value::Unknown)
+    }
+
+    fn pop_n(&mut self, count: usize) -> Vec<Value> {
+        let mut values: Vec<Value> = (0..count).map(|_| self.pop()).collect();
+        values.reverse();
+        values
+    }
+
+    fn push_unknown(&mut self, count: usize) {
+        self.stack
+            .extend(std::iter::repeat_n(Value::Unknown, count));
+    }
+
+    fn poison(&mut self, value: &Value) {
+        match value {
+            Value::Builder(id) => self.builders[*id].poisoned = true,
+            Value::Array(id) => self.arrays[*id].poisoned = true,
+            _ => {}
+        }
+    }
+
+    fn record(&mut self, value: String, source: StringSource) {
+        if value.trim().is_empty() {
+            return;
+        }
+        self.recovered.push(RecoveredString {
+            value,
+            method: self.method_name.clone(),
+            source,
+            helper: None,
+        });
+    }
+
+    fn step(&mut self, instruction: &Instruction) {
+        use bytecode::*;
+
+        match instruction.opcode {
+            ACONST_NULL => self.push_unknown(1),
+            ICONST_M1..=ICONST_5 => self
+                .stack
+                .push(Value::Int(instruction.opcode as i64 - ICONST_M1 as i64 - 1)),
+            LCONST_0 | LCONST_1 => self
+                .stack
+                .push(Value::Int((instruction.opcode - LCONST_0) as i64)),
+            BIPUSH | SIPUSH => {
+                if let Operand::Int(v) = instruction.operand {
+                    self.stack.push(Value::Int(v as i64));
+                }
+            }
+            LDC | LDC_W | LDC2_W => {
+                let value = match instruction.operand {
+                    Operand::Constant(index) => match self.details.loadable_constant(index) {
+                        Some(ConstantValue::String(s)) => Value::Str(s.to_string()),
+                        Some(ConstantValue::Int(v)) => Value::Int(v as i64),
+                        Some(ConstantValue::Long(v)) => Value::Int(v),
+                        _ => Value::Unknown,
+                    },
+                    _ => Value::Unknown,
+                };
+                self.stack.push(value);
+            }
+            ILOAD..=ALOAD | ILOAD_0..=ALOAD_3 => {
+                let value = local_slot(instruction)
+                    .and_then(|slot| self.locals.get(&slot).cloned())
+                    .unwrap_or(Value::Unknown);
+                self.stack.push(value);
+            }
+            ISTORE..=ASTORE | ISTORE_0..=ASTORE_3 => {
+                let value = self.pop();
+                if let Some(slot) = local_slot(instruction) {
+                    self.locals.insert(slot, value);
+                }
+            }
+            IINC => {
+                if let Operand::Iinc(slot, delta) = instruction.operand {
+                    let value = match self.locals.get(&slot) {
+                        Some(Value::Int(v)) => Value::Int(v + delta as i64),
+                        _ => Value::Unknown,
+                    };
+                    self.locals.insert(slot, value);
+                }
+            }
+            POP => {
+                self.pop();
+            }
+            DUP => {
+                let top = self.stack.last().cloned().unwrap_or(Value::Unknown);
+                self.stack.push(top);
+            }
+            SWAP => {
+                let a = self.pop();
+                let b = self.pop();
+                self.stack.push(a);
+                self.stack.push(b);
+            }
+            CHECKCAST => {}
+            NEW => {
+                let is_builder = matches!(
+                    instruction.operand,
+                    Operand::Constant(index)
+                        if matches!(
+                            self.details.class_constant(index),
+                            Some("java/lang/StringBuilder" | "java/lang/StringBuffer")
+                        )
+                );
+                let is_string = matches!(
+                    instruction.operand,
+                    Operand::Constant(index)
+                        if self.details.class_constant(index) == Some("java/lang/String")
+                );
+                if is_builder {
+                    self.builders.push(BuilderState::default());
+                    self.stack.push(Value::Builder(self.builders.len() - 1));
+                } else if is_string {
+                    self.new_strings += 1;
+                    self.stack.push(Value::NewString(self.new_strings));
+                } else {
+                    self.push_unknown(1);
+                }
+            }
+            NEWARRAY => {
+                let length = self.pop();
+                match (length, &instruction.operand) {
+                    (Value::Int(length), Operand::NewArray(element_type))
+                        if (0..=MAX_TRACKED_ARRAY_LENGTH).contains(&length) =>
+                    {
+                        self.arrays.push(ArrayState {
+                            element_type: *element_type,
+                            elements: vec![0; length as usize],
+                            stores: 0,
+                            poisoned: false,
+                        });
+                        self.stack.push(Value::Array(self.arrays.len() - 1));
+                    }
+                    _ => self.push_unknown(1),
+                }
+            }
+            ANEWARRAY | ARRAYLENGTH => {
+                self.pop();
+                self.push_unknown(1);
+            }
+            IALOAD..=SALOAD => {
+                let index = self.pop();
+                let array = self.pop();
+                let element = match (array, index) {
+                    (Value::Array(id), Value::Int(index)) => usize::try_from(index)
+                        .ok()
+                        .and_then(|i| self.arrays[id].elements.get(i))
+                        .map(|&v| Value::Int(v)),
+                    _ => None,
+                };
+                self.stack.push(element.unwrap_or(Value::Unknown));
+            }
+            IASTORE..=SASTORE => {
+                let value = self.pop();
+                let index = self.pop();
+                let array = self.pop();
+                if let Value::Array(id) = array {
+                    self.store_element(id, instruction.opcode, index, value);
+                } else {
+                    self.poison(&value);
+                }
+            }
+            GETSTATIC | GETFIELD => {
+                if instruction.opcode == GETFIELD {
+                    self.pop();
+                }
+                self.push_unknown(1);
+            }
+            PUTSTATIC | PUTFIELD => {
+                self.pop();
+                if instruction.opcode == PUTFIELD {
+                    self.pop();
+                }
+            }
+            INVOKEVIRTUAL..=INVOKEINTERFACE => self.invoke(instruction),
+            INVOKEDYNAMIC => self.invoke_dynamic(instruction),
+            _ => self.stack.clear(),
+        }
+    }
+
+    fn invoke(&mut self, instruction: &Instruction) {
+        let Operand::Constant(index) = instruction.operand else {
+            self.stack.clear();
+            return;
+        };
+        let Some(member) = self.details.member_ref(index) else {
+            self.stack.clear();
+            return;
+        };
+        let Some((params, return_type)) = bytecode::parse_method_descriptor(member.descriptor)
+        else {
+            self.stack.clear();
+            return;
+        };
+
+        let args = self.pop_n(params.len());
+        let receiver = if instruction.opcode == bytecode::INVOKESTATIC {
+            None
+        } else {
+            Some(self.pop())
+        };
+
+        let is_builder_owner = matches!(
+            member.owner,
+            "java/lang/StringBuilder" | "java/lang/StringBuffer"
+        );
+        if let (true, Some(Value::NewString(marker))) =
+            (member.owner == "java/lang/String" && member.name == "<init>", &receiver)
+        {
+            let marker = *marker;
+            let decoded = self.decode_string_constructor(&params, &args);
+            for slot in self.stack.iter_mut() {
+                if matches!(slot, Value::NewString(m) if *m == marker) {
+                    *slot = match &decoded {
+                        Some((value, _)) => Value::Str(value.clone()),
+                        None => Value::Unknown,
+                    };
+                }
+            }
+            if let Some((value, source)) = decoded {
+                self.record(value, source);
+            }
+            return;
+        }
+
+        if member.owner == "java/lang/String"
+            && matches!(member.name, "valueOf" | "copyValueOf")
+            && params.first() == Some(&"[C")
+        {
+            match self.decode_string_constructor(&params, &args) {
+                Some((value, source)) => {
+                    self.record(value.clone(), source);
+                    self.stack.push(Value::Str(value));
+                }
+                None => self.push_unknown(1),
+            }
+            return;
+        }
+
+        if let (true, Some(Value::Builder(id))) = (is_builder_owner, &receiver) {
+            let id = *id;
+            match member.name {
+                "<init>" => {
+                    match params.first() {
+                        Some(&"Ljava/lang/String;" | &"Ljava/lang/CharSequence;") => {
+                            self.append_to_builder(id, params[0], &args[0])
+                        }
+                        Some(&"I") | None => {}
+                        Some(_) => self.builders[id].poisoned = true,
+                    }
+                    return;
+                }
+                "append" if args.len() == 1 => {
+                    self.append_to_builder(id, params[0], &args[0]);
+                    self.stack.push(Value::Builder(id));
+                    return;
+                }
+                "toString" => {
+                    let builder = &self.builders[id];
+                    if !builder.poisoned && builder.pieces >= 2 {
+                        let value = builder.content.clone();
+                        self.record(value.clone(), StringSource::StringBuilder);
+                        self.stack.push(Value::Str(value));
+                    } else {
+                        self.push_unknown(1);
+                    }
+                    return;
+                }
+                _ => self.builders[id].poisoned = true,
+            }
+        } else {
+            for value in args.iter().chain(receiver.iter()) {
+                self.poison(value);
+            }
+        }
+
+        if return_type != "V" {
+            self.push_unknown(1);
+        }
+    }
+
+    fn store_element(&mut self, id: usize, opcode: u8, index: Value, value: Value) {
+        let array = &mut self.arrays[id];
+        let int_store = matches!(
+            (opcode, array.element_type),
+            (bytecode::IASTORE, bytecode::T_INT)
+                | (bytecode::BASTORE, bytecode::T_BYTE)
+                | (bytecode::CASTORE, bytecode::T_CHAR)
+                | (bytecode::SASTORE, bytecode::T_SHORT)
+        );
+        let slot = match index {
+            Value::Int(i) => usize::try_from(i)
+                .ok()
+                .filter(|&i| i < array.elements.len()),
+            _ => None,
+        };
+
+        match (int_store, slot, value) {
+            (true, Some(slot), Value::Int(v)) => {
+                array.elements[slot] = v;
+                array.stores += 1;
+            }
+            _ => array.poisoned = true,
+        }
+    }
+
+    /// Decodes `new String(char[]/byte[] ...)` and `String.valueOf(char[])`
+    /// when the array was filled entirely from constants.
+    fn decode_string_constructor(
+        &self,
+        params: &[&str],
+        args: &[Value],
+    ) -> Option<(String, StringSource)> {
+        let Some(Value::Array(id)) = args.first() else {
+            return None;
+        };
+        let array = &self.arrays[*id];
+        if array.poisoned || array.stores == 0 {
+            return None;
+        }
+
+        let (start, count) = match (params, args) {
+            ([_], _) | ([_, _], _) => (0, array.elements.len()),
+            ([_, "I", "I", ..], [_, Value::Int(offset), Value::Int(count), ..]) => {
+                (usize::try_from(*offset).ok()?, usize::try_from(*count).ok()?)
+            }
+            _ => return None,
+        };
+        let elements = array.elements.get(start..start.checked_add(count)?)?;
+
+        match (params[0], array.element_type) {
+            ("[C", bytecode::T_CHAR) => {
+                let units: Vec<u16> = elements.iter().map(|&v| v as u16).collect();
+                String::from_utf16(&units)
+                    .ok()
+                    .map(|s| (s, StringSource::CharArray))
+            }
+            ("[B", bytecode::T_BYTE) => {
+                let bytes: Vec<u8> = elements.iter().map(|&v| v as u8).collect();
+                let latin1 = params.len() == 2
+                    && matches!(
+                        args.get(1),
+                        Some(Value::Str(charset))
+                            if charset.eq_ignore_ascii_case("ISO-8859-1")
+                                || charset.eq_ignore_ascii_case("US-ASCII")
+                    );
+                if latin1 {
+                    Some((bytes.iter().map(|&b| b as char).collect(), StringSource::ByteArray))
+                } else {
+                    String::from_utf8(bytes)
+                        .ok()
+                        .map(|s| (s, StringSource::ByteArray))
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn append_to_builder(&mut self, id: usize, param_type: &str, value: &Value) {
+        let piece = render_piece(param_type, value);
+        let builder = &mut self.builders[id];
+        match piece {
+            Some(piece) => {
+                builder.content.push_str(&piece);
+                builder.pieces += 1;
+            }
+            None => builder.poisoned = true,
+        }
+    }
+
+    fn invoke_dynamic(&mut self, instruction: &Instruction) {
+        let Operand::Constant(index) = instruction.operand else {
+            self.stack.clear();
+            return;
+        };
+        let Some((bootstrap, bootstrap_args, name, descriptor)) =
+            self.details.invoke_dynamic(index)
+        else {
+            self.stack.clear();
+            return;
+        };
+        let Some((params, return_type)) = bytecode::parse_method_descriptor(descriptor) else {
+            self.stack.clear();
+            return;
+        };
+
+        let args = self.pop_n(params.len());
+        let is_concat = bootstrap.owner == "java/lang/invoke/StringConcatFactory";
+
+        let rebuilt = match (is_concat, name) {
+            (true, "makeConcatWithConstants") => {
+                self.apply_concat_recipe(bootstrap_args, &params, &args)
+            }
+            (true, "makeConcat") => params
+                .iter()
+                .zip(&args)
+                .map(|(param_type, value)| render_piece(param_type, value))
+                .collect::<Option<String>>()
+                .filter(|_| args.len() >= 2),
+            _ => None,
+        };
+
+        match rebuilt {
+            Some(value) => {
+                self.record(value.clone(), StringSource::IndyConcat);
+                self.stack.push(Value::Str(value));
+            }
+            None if return_type != "V" => self.push_unknown(1),
+            None => {}
+        }
+    }
+
+    fn apply_concat_recipe(
+        &self,
+        bootstrap_args: &[u16],
+        params: &[&str],
+        args: &[Value],
+    ) -> Option<String> {
+        let recipe = match self.details.loadable_constant(*bootstrap_args.first()?)? {
+            ConstantValue::String(recipe) => recipe,
+            _ => return None,
+        };
+
+        let mut result = String::with_capacity(recipe.len());
+        let mut dynamic_args = params.iter().zip(args);
+        let mut constants = bootstrap_args[1..].iter();
+        let mut pieces = 0;
+
+        for c in recipe.chars() {
+            match c {
+                '\u{1}' => {
+                    let (param_type, value) = dynamic_args.next()?;
+                    result.push_str(&render_piece(param_type, value)?);
+                    pieces += 1;
+                }
+                '\u{2}' => {
+                    match self.details.loadable_constant(*constants.next()?)? {
+                        ConstantValue::String(s) => result.push_str(s),
+                        ConstantValue::Int(v) => result.push_str(&v.to_string()),
+                        ConstantValue::Long(v) => result.push_str(&v.to_string()),
+                        _ => return None,
+                    }
+                    pieces += 1;
+                }
+                other => result.push(other),
+            }
+        }
+
+        (pieces >= 1 && !args.is_empty()).then_some(result)
+    }
+}
+
+fn render_piece(param_type: &str, value: &Value) -> Option<String> {
+    match (param_type, value) {
+        ("C", Value::Int(v)) => char::from_u32(*v as u32 & 0xffff).map(String::from),
+        ("I" | "J" | "S" | "B", Value::Int(v)) => Some(v.to_string()),
+        ("Z", Value::Int(v)) => Some((*v != 0).to_string()),
+        (
+            "Ljava/lang/String;" | "Ljava/lang/CharSequence;" | "Ljava/lang/Object;",
+            Value::Str(s),
+        ) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_class_structure;
+    use crate::test_support::ClassBuilder;
+
+    /// Parses a class whose only method runs the code `body` assembles
+    /// against the class's constant pool.
+    fn class_running(body: impl FnOnce(&mut ClassBuilder) -> Vec<u8>) -> ClassDetails {
+        let mut class = ClassBuilder::new("Test");
+        let code = body(&mut class);
+        class.method(0x0009, "run", "()V", &code, &[]);
+        parse_class_structure(&class.build(), "Test.class", false).unwrap()
+    }
+
+    fn reconstructed(details: &ClassDetails) -> Vec<(String, StringSource)> {
+        reconstruct_strings(details)
+            .into_iter()
+            .map(|recovered| (recovered.value, recovered.source))
+            .collect()
+    }
+
+    /// `new StringBuilder().append("http://").append("evil.example")
+    /// .append(':').append(8080).toString()`
+    fn builder_chain(class: &mut ClassBuilder) -> Vec<u8> {
+        let builder = class.class("java/lang/StringBuilder").to_be_bytes();
+        let init = class
+            .method_ref("java/lang/StringBuilder", "<init>", "()V")
+            .to_be_bytes();
+        let append = |class: &mut ClassBuilder, param: &str| {
+            let descriptor = format!("({})Ljava/lang/StringBuilder;", param);
+            class
+                .method_ref("java/lang/StringBuilder", "append", &descriptor)
+                .to_be_bytes()
+        };
+        let append_string = append(class, "Ljava/lang/String;");
+        let append_char = append(class, "C");
+        let append_int = append(class, "I");
+        let to_string = class
+            .method_ref(
+                "java/lang/StringBuilder",
+                "toString",
+                "()Ljava/lang/String;",
+            )
+            .to_be_bytes();
+        let scheme = class.string("http://") as u8;
+        let host = class.string("evil.example") as u8;
+
+        let mut code = vec![0xbb, builder[0], builder[1], 0x59, 0xb7, init[0], init[1]];
+        code.extend([0x12, scheme, 0xb6, append_string[0], append_string[1]]);
+        code.extend([0x12, host, 0xb6, append_string[0], append_string[1]]);
+        code.extend([0x10, b':', 0xb6, append_char[0], append_char[1]]);
+        code.extend([0x11, 0x1f, 0x90, 0xb6, append_int[0], append_int[1]]);
+        code.extend([0xb6, to_string[0], to_string[1], 0x57, 0xb1]);
+        code
+    }
+
+    #[test]
+    fn rebuilds_string_builder_chains() {
+        let details = class_running(builder_chain);
+        let recovered = reconstruct_strings(&details);
+        assert_eq!(recovered.len(), 1);
+        assert_eq!(recovered[0].value, "http://evil.example:8080");
+        assert_eq!(recovered[0].method, "Test.run()V");
+        assert!(matches!(recovered[0].source, StringSource::StringBuilder));
+    }
+
+    #[test]
+    fn truncated_or_unknown_chains_recover_nothing() {
+        let code = builder_chain(&mut ClassBuilder::new("Test"));
+        for len in 0..code.len() - 2 {
+            let details = class_running(|class| {
+                builder_chain(class);
+                code[..len].to_vec()
+            });
+            assert!(
+                reconstructed(&details).is_empty(),
+                "prefix of {} bytes",
+                len
+            );
+        }
+
+        // The host comes from a local rather than a constant.
+        let details = class_running(|class| {
+            let mut code = builder_chain(class);
+            code[12] = 0x2a; // aload_0
+            code[13] = 0x00; // nop
+            code
+        });
+        assert!(reconstructed(&details).is_empty());
+    }
+
+    /// `new String(new char[] {...})`, or `new String(new byte[] {...},
+    /// "ISO-8859-1")` when `charset` is given, storing `elements` at `indexes`
+    /// of an array of `length`.
+    fn array_string(
+        class: &mut ClassBuilder,
+        length: u8,
+        indexes: &[u8],
+        elements: &[u8],
+        charset: Option<&str>,
+    ) -> Vec<u8> {
+        let string = class.class("java/lang/String").to_be_bytes();
+        let (element_type, store, descriptor) = match charset {
+            Some(_) => (
+                bytecode::T_BYTE,
+                bytecode::BASTORE,
+                "([BLjava/lang/String;)V",
+            ),
+            None => (bytecode::T_CHAR, bytecode::CASTORE, "([C)V"),
+        };
+        let init = class
+            .method_ref("java/lang/String", "<init>", descriptor)
+            .to_be_bytes();
+
+        let mut code = vec![0xbb, string[0], string[1], 0x59];
+        code.extend([0x10, length, 0xbc, element_type]);
+        for (&index, &element) in indexes.iter().zip(elements) {
+            code.extend([0x59, 0x10, index, 0x10, element, store]);
+        }
+        if let Some(charset) = charset {
+            code.extend([0x12, class.string(charset) as u8]);
+        }
+        code.extend([0xb7, init[0], init[1], 0x57, 0xb1]);
+        code
+    }
+
+    #[test]
+    fn decodes_constant_char_and_byte_arrays() {
+        let details = class_running(|class| array_string(class, 3, &[0, 1, 2], b"cmd", None));
+        assert!(matches!(
+            &reconstructed(&details)[..],
+            [(value, StringSource::CharArray)] if value == "cmd"
+        ));
+
+        let details = class_running(|class| {
+            array_string(class, 4, &[0, 1, 2, 3], b"caf\xe9", Some("ISO-8859-1"))
+        });
+        assert!(matches!(
+            &reconstructed(&details)[..],
+            [(value, StringSource::ByteArray)] if value == "caf\u{e9}"
+        ));
+    }
+
+    #[test]
+    fn out_of_range_arrays_recover_nothing() {
+        // A store past the end of the array
+        let details = class_running(|class| array_string(class, 2, &[0, 1, 2], b"cmd", None));
+        assert!(reconstructed(&details).is_empty());
+
+        // A negative length
+        let details = class_running(|class| array_string(class, 0xff, &[0, 1, 2], b"cmd", None));
+        assert!(reconstructed(&details).is_empty());
+
+        // A length past what is tracked
+        let details = class_running(|class| {
+            let mut code = array_string(class, 3, &[0, 1, 2], b"cmd", None);
+            code.splice(4..6, [0x11, 0x7f, 0xff]); // sipush 32767
+            code
+        });
+        assert!(reconstructed(&details).is_empty());
+
+        let code = array_string(&mut ClassBuilder::new("Test"), 3, &[0, 1, 2], b"cmd", None);
+        for len in 0..code.len() - 2 {
+            let details = class_running(|class| {
+                array_string(class, 3, &[0, 1, 2], b"cmd", None);
+                code[..len].to_vec()
+            });
+            assert!(
+                reconstructed(&details).is_empty(),
+                "prefix of {} bytes",
+                len
+            );
+        }
+    }
+}
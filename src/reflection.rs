@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+
+use crate::bytecode::{self, ConstantValue, Instruction, Operand};
+use crate::types::ClassDetails;
+
+/// How far back from a call we look for the constant naming its target.
+const MAX_ARGUMENT_LOOKBACK: usize = 12;
+
+const CLASS_LOADER_SUPERCLASSES: &[&str] = &[
+    "java/lang/ClassLoader",
+    "java/security/SecureClassLoader",
+    "java/net/URLClassLoader",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LoadingKind {
+    /// Turns bytes into a class or builds a loader over remote code.
+    DefinesClasses,
+    /// Resolves classes or members by name at runtime.
+    Reflection,
+}
+
+#[derive(Debug, Clone)]
+pub struct DynamicLoadSite {
+    pub kind: LoadingKind,
+    pub api: &'static str,
+    pub method: String,
+    /// The class or member name when it is a constant at the call site.
+    pub target: Option<String>,
+}
+
+fn classify(owner: &str, name: &str, descriptor: &str) -> Option<(LoadingKind, &'static str)> {
+    use LoadingKind::*;
+
+    let returns_class = descriptor.ends_with(")Ljava/lang/Class;");
+    Some(match (owner, name) {
+        // Custom loaders call their own inherited defineClass, so match by shape.
+        (_, "defineClass" | "defineClass1") if returns_class && descriptor.contains("[B") => {
+            (DefinesClasses, "ClassLoader.defineClass")
+        }
+        ("java/lang/invoke/MethodHandles$Lookup", "defineClass") => {
+            (DefinesClasses, "Lookup.defineClass")
+        }
+        ("java/lang/invoke/MethodHandles$Lookup", "defineHiddenClass") => {
+            (DefinesClasses, "Lookup.defineHiddenClass")
+        }
+        ("sun/misc/Unsafe" | "jdk/internal/misc/Unsafe", "defineAnonymousClass") => {
+            (DefinesClasses, "Unsafe.defineAnonymousClass")
+        }
+        ("sun/misc/Unsafe" | "jdk/internal/misc/Unsafe", "defineClass") => {
+            (DefinesClasses, "Unsafe.defineClass")
+        }
+        ("java/net/URLClassLoader", "<init>" | "newInstance") => {
+            (DefinesClasses, "new URLClassLoader")
+        }
+        ("java/lang/Class", "forName") => (Reflection, "Class.forName"),
+        (_, "loadClass") if returns_class && descriptor.starts_with("(Ljava/lang/String;") => {
+            (Reflection, "ClassLoader.loadClass")
+        }
+        ("java/lang/Class", "getMethod") => (Reflection, "Class.getMethod"),
+        ("java/lang/Class", "getDeclaredMethod") => (Reflection, "Class.getDeclaredMethod"),
+        ("java/lang/Class", "getField") => (Reflection, "Class.getField"),
+        ("java/lang/Class", "getDeclaredField") => (Reflection, "Class.getDeclaredField"),
+        ("java/lang/reflect/Method", "invoke") => (Reflection, "Method.invoke"),
+        ("java/lang/invoke/MethodHandles$Lookup", "findStatic") => {
+            (Reflection, "Lookup.findStatic")
+        }
+        ("java/lang/invoke/MethodHandles$Lookup", "findVirtual") => {
+            (Reflection, "Lookup.findVirtual")
+        }
+        ("java/lang/invoke/MethodHandles$Lookup", "findClass") => (Reflection, "Lookup.findClass"),
+        _ => return None,
+    })
+}
+
+pub fn extends_class_loader(details: &ClassDetails) -> bool {
+    CLASS_LOADER_SUPERCLASSES.contains(&details.superclass_name.as_str())
+}
+
+/// Finds calls that define classes from bytes or resolve code by name,
+/// capturing the constant class/member name where the call site has one.
+pub fn find_dynamic_loading(details: &ClassDetails) -> Vec<DynamicLoadSite> {
+    let mut sites = Vec::new();
+
+    for method in &details.methods {
+        let Some(code) = &method.code else {
+            continue;
+        };
+        let instructions = bytecode::decode(&code.code);
+        let jump_targets: HashSet<usize> = instructions
+            .iter()
+            .flat_map(|ins| ins.branch_targets())
+            .filter_map(|t| usize::try_from(t).ok())
+            .chain(code.exception_table.iter().map(|e| e.handler_pc as usize))
+            .collect();
+
+        for (i, instruction) in instructions.iter().enumerate() {
+            if !matches!(
+                instruction.opcode,
+                bytecode::INVOKEVIRTUAL..=bytecode::INVOKEINTERFACE
+            ) {
+                continue;
+            }
+            let Operand::Constant(index) = instruction.operand else {
+                continue;
+            };
+            let Some(member) = details.member_ref(index) else {
+                continue;
+            };
+            let Some((kind, api)) = classify(member.owner, member.name, member.descriptor) else {
+                continue;
+            };
+
+            let takes_name = kind == LoadingKind::Reflection
+                && member.descriptor.contains("Ljava/lang/String;")
+                && api != "Method.invoke";
+            let target = if takes_name {
+                constant_name_argument(details, &instructions[..i], &jump_targets)
+            } else {
+                None
+            };
+
+            sites.push(DynamicLoadSite {
+                kind,
+                api,
+                method: bytecode::qualified_method_name(
+                    &details.class_name,
+                    &method.name,
+                    &method.descriptor,
+                ),
+                target,
+            });
+        }
+    }
+
+    sites
+}
+
+/// Returns the closest string constant loaded before a call within the same
+/// basic block. Names computed at runtime (a call returning `String` in the
+/// way) are reported as unknown rather than guessed.
+fn constant_name_argument(
+    details: &ClassDetails,
+    preceding: &[Instruction],
+    jump_targets: &HashSet<usize>,
+) -> Option<String> {
+    for instruction in preceding.iter().rev().take(MAX_ARGUMENT_LOOKBACK) {
+        if instruction.ends_block() {
+            return None;
+        }
+        match (instruction.opcode, &instruction.operand) {
+            (bytecode::LDC | bytecode::LDC_W, Operand::Constant(index)) => {
+                if let Some(ConstantValue::String(s)) = details.loadable_constant(*index) {
+                    return Some(s.to_string());
+                }
+            }
+            (
+                bytecode::INVOKEVIRTUAL..=bytecode::INVOKEDYNAMIC,
+                Operand::Constant(index),
+            ) => {
+                let returns_string = details
+                    .member_ref(*index)
+                    .map(|m| m.descriptor)
+                    .or_else(|| details.invoke_dynamic(*index).map(|(_, _, _, d)| d))
+                    .is_some_and(|d| d.ends_with(")Ljava/lang/String;"));
+                if returns_string {
+                    return None;
+                }
+            }
+            _ => {}
+        }
+        if jump_targets.contains(&instruction.offset) {
+            return None;
+        }
+    }
+    None
+}
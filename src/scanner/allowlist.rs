@@ -0,0 +1,34 @@
+use crate::scanner::scan::CollapseFindOBFScanner;
+use crate::utils::sha256_hex;
+
+impl CollapseFindOBFScanner {
+    /// Checked ahead of detection, so allowlisted bytes are never analysed.
+    /// Unlike `is_library`, this vouches for exact content rather than a
+    /// package name that anyone can reuse.
+    pub(crate) fn known_good_source(&self, data: &[u8]) -> Option<&str> {
+        if self.allowlist.is_empty() {
+            return None;
+        }
+        self.allowlist.source(&sha256_hex(data))
+    }
+
+    /// Records `path` as verified known-good when its bytes are allowlisted.
+    pub(crate) fn verify_known_good(&self, path: &str, data: &[u8]) -> bool {
+        let Some(source) = self.known_good_source(data) else {
+            return false;
+        };
+        if self.options.verbose {
+            println!(
+                "{} Verified known-good: {} ({})",
+                green_text!("✅"),
+                path,
+                source
+            );
+        }
+        self.known_good
+            .lock()
+            .unwrap()
+            .push((path.to_string(), source.to_string()));
+        true
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use rayon::prelude::*;
+
+use crate::class_recovery::{loader_key_candidates, KeyCandidate};
+use crate::detection::calculate_detection_hash;
+use crate::native::{parse_native_library, sniff_native_format, JniSymbol, NativeLibrary};
+use crate::parser::parse_class_structure;
+use crate::reflection::{find_dynamic_loading, LoadingKind};
+use crate::types::ClassDetails;
+
+/// Classes parsed up front from a JAR so per-class analysis can resolve
+/// references to other classes in the same archive.
+#[derive(Default)]
+pub struct ArchiveContext {
+    /// Parsed classes by entry name, reused when the entry itself is scanned.
+    parsed: HashMap<String, ClassDetails>,
+    /// Entry each class name resolves to, the first in archive order.
+    classes: HashMap<String, String>,
+    native_libraries: HashMap<String, NativeLibrary>,
+    jni_symbols: HashSet<JniSymbol>,
+    binds_natives_dynamically: bool,
+    /// Classes that turn bytes into classes, and keys harvested from them.
+    defining_loaders: Vec<String>,
+    loader_keys: Vec<KeyCandidate>,
+    /// Digest of every entry, scoping findings that drew on other entries.
+    cache_key: u64,
+}
+
+impl ArchiveContext {
+    pub fn build(entries: &[(String, Arc<Vec<u8>>)], verbose: bool) -> Self {
+        let parsed: Vec<(String, ClassDetails)> = entries
+            .par_iter()
+            .filter(|(_, data)| data.starts_with(b"\xCA\xFE\xBA\xBE"))
+            .filter_map(|(name, data)| {
+                Some((name.clone(), parse_class_structure(data, name, verbose).ok()?))
+            })
+            .collect();
+        let mut classes = HashMap::new();
+        for (entry_name, details) in &parsed {
+            classes
+                .entry(details.class_name.clone())
+                .or_insert_with(|| entry_name.clone());
+        }
+        let parsed: HashMap<String, ClassDetails> = parsed.into_iter().collect();
+
+        let mut defining_loaders: Vec<&ClassDetails> = classes
+            .values()
+            .map(|entry_name| &parsed[entry_name])
+            .filter(|details| {
+                find_dynamic_loading(details).iter().any(|site| {
+                    site.kind == LoadingKind::DefinesClasses && site.api != "new URLClassLoader"
+                })
+            })
+            .collect();
+        defining_loaders.sort_by(|a, b| a.class_name.cmp(&b.class_name));
+        let loader_keys = defining_loaders
+            .iter()
+            .flat_map(|details| loader_key_candidates(details))
+            .collect();
+        let defining_loaders = defining_loaders
+            .into_iter()
+            .map(|details| details.class_name.clone())
+            .collect();
+
+        let native_libraries: HashMap<String, NativeLibrary> = entries
+            .par_iter()
+            .filter(|(_, data)| sniff_native_format(data).is_some())
+            .filter_map(|(name, data)| Some((name.clone(), parse_native_library(data)?)))
+            .collect();
+        let jni_symbols = native_libraries
+            .values()
+            .flat_map(NativeLibrary::jni_symbols)
+            .collect();
+        let binds_natives_dynamically = native_libraries
+            .values()
+            .any(NativeLibrary::registers_natives_dynamically);
+
+        let mut digests: Vec<(&str, u64)> = entries
+            .iter()
+            .map(|(name, data)| (name.as_str(), calculate_detection_hash(data)))
+            .collect();
+        digests.sort_unstable();
+        let mut hasher = DefaultHasher::new();
+        digests.hash(&mut hasher);
+        let cache_key = hasher.finish();
+
+        Self {
+            parsed,
+            classes,
+            native_libraries,
+            jni_symbols,
+            binds_natives_dynamically,
+            defining_loaders,
+            loader_keys,
+            cache_key,
+        }
+    }
+
+    pub fn cache_key(&self) -> u64 {
+        self.cache_key
+    }
+
+    pub fn class(&self, name: &str) -> Option<&ClassDetails> {
+        self.parsed.get(self.classes.get(name)?)
+    }
+
+    pub fn classes(&self) -> impl Iterator<Item = &ClassDetails> {
+        self.classes.values().map(|entry_name| &self.parsed[entry_name])
+    }
+
+    /// The class parsed from `entry_name` while building the context.
+    pub fn parsed_entry(&self, entry_name: &str) -> Option<&ClassDetails> {
+        self.parsed.get(entry_name)
+    }
+
+    /// Classes in this archive reachable from `roots` through constant pool
+    /// class references, each mapped to the root it was first reached from.
+    pub fn reachable_from(&self, roots: &[String]) -> HashMap<String, String> {
+        let mut reached: HashMap<String, String> = HashMap::new();
+        let mut queue: VecDeque<(String, String)> = VecDeque::new();
+        for root in roots {
+            if self.classes.contains_key(root) && !reached.contains_key(root) {
+                reached.insert(root.clone(), root.clone());
+                queue.push_back((root.clone(), root.clone()));
+            }
+        }
+        while let Some((class_name, root)) = queue.pop_front() {
+            let Some(details) = self.class(&class_name) else {
+                continue;
+            };
+            for referenced in details.referenced_classes() {
+                if self.classes.contains_key(referenced) && !reached.contains_key(referenced) {
+                    reached.insert(referenced.to_string(), root.clone());
+                    queue.push_back((referenced.to_string(), root.clone()));
+                }
+            }
+        }
+        reached
+    }
+
+    pub fn defining_loaders(&self) -> &[String] {
+        &self.defining_loaders
+    }
+
+    pub fn loader_keys(&self) -> &[KeyCandidate] {
+        &self.loader_keys
+    }
+
+    pub fn native_library(&self, entry_name: &str) -> Option<&NativeLibrary> {
+        self.native_libraries.get(entry_name)
+    }
+
+    /// Whether a declared `native` method has an implementation in one of the
+    /// bundled libraries. `None` when that cannot be judged: no libraries are
+    /// bundled, or one of them registers its natives at load time.
+    pub fn has_jni_binding(&self, class_name: &str, method_name: &str) -> Option<bool> {
+        if self.native_libraries.is_empty() || self.binds_natives_dynamically {
+            return None;
+        }
+        Some(self.jni_symbols.contains(&JniSymbol {
+            class_name: class_name.to_string(),
+            method_name: method_name.to_string(),
+        }))
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use crate::scanner::scan::CollapseFindOBFScanner;
+use crate::types::{FindingType, ResourceInfo, ScanResult};
+
+impl CollapseFindOBFScanner {
+    /// Hits from the loaded ClamAV databases against a whole archive or one
+    /// of its decompressed entries.
+    pub(crate) fn clamav_findings(&self, subject: &str, data: &[u8]) -> Vec<(FindingType, String)> {
+        if self.clamav.is_empty() {
+            return Vec::new();
+        }
+        self.clamav
+            .scan(data)
+            .into_iter()
+            .map(|hit| {
+                (
+                    FindingType::ClamAvMatch {
+                        signature: hit.name.to_string(),
+                    },
+                    format!("{} matches {} ({})", subject, hit.name, hit.evidence),
+                )
+            })
+            .collect()
+    }
+
+    /// Adds findings made outside the class or resource scan to its result,
+    /// re-scoring it, or creates a result when the scan reported nothing.
+    pub(crate) fn with_additional_findings(
+        &self,
+        result: Option<ScanResult>,
+        additional: Vec<(FindingType, String)>,
+        path: &str,
+        resource_info: Option<ResourceInfo>,
+    ) -> Option<ScanResult> {
+        if additional.is_empty() {
+            return result;
+        }
+        let Some(mut result) = result else {
+            return self.create_resource_result(additional, path, resource_info);
+        };
+        let mut findings = result.matches.as_ref().clone();
+        findings.extend(additional);
+        result.danger_score = self.calculate_danger_score(&findings, result.resource_info.as_ref());
+        result.danger_explanation = self.generate_danger_explanation(
+            result.danger_score,
+            &findings,
+            result.resource_info.as_ref(),
+        );
+        result.matches = Arc::new(findings);
+        Some(result)
+    }
+}
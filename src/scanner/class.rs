@@ -1,14 +1,42 @@
 use rayon::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::cell::Cell;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 
+use crate::bytecode::qualified_method_name;
+use crate::cfg::analyze_method;
+use crate::class_recovery::{recover_class, ClassTransform};
 use crate::detection::{cache_safe_string, calculate_detection_hash, is_cached_safe_string};
+use crate::emulator::recover_decrypted_strings;
 use crate::errors::ScanError;
 
+use crate::filters::DISCORD_WEBHOOK_REGEX;
+use crate::kotlin_metadata::{kotlin_metadata, KotlinMetadata, KOTLIN_INTRINSICS};
 use crate::parser::parse_class_structure;
+use crate::reconstruct::reconstruct_strings;
+use crate::reflection::{
+    extends_class_loader, find_dynamic_loading, DynamicLoadSite, LoadingKind,
+};
+use crate::scanner::archive::ArchiveContext;
+use crate::scanner::jar::has_class_file_name;
 use crate::scanner::scan::CollapseFindOBFScanner;
-use crate::types::{ClassDetails, FindingType, ResourceInfo, ScanResult};
-use crate::utils::truncate_string;
+use crate::types::{ClassDetails, FindingType, RecoveredString, ResourceInfo, ScanResult};
+use crate::utils::{sha256_hex, truncate_string};
+
+const ACC_NATIVE: u16 = 0x0100;
+
+/// Findings, parsed details and whether the findings used the archive.
+type ClassAnalysis = (Vec<(FindingType, String)>, ClassDetails, bool);
+
+/// Results scoring lower are only reported in verbose mode.
+pub(crate) const MIN_REPORTED_DANGER_SCORE: u8 = 4;
+/// Subject of digest matches on class entries. Their findings are cached
+/// by content, so the entry path is left to the result they are reported in.
+const CLASS_DATA_SUBJECT: &str = "Class data";
+/// Tail of the summary finding for flagged methods past the report limit.
+const CONTROL_FLOW_OVERFLOW: &str = "more method(s) with obfuscated control flow";
 
 impl CollapseFindOBFScanner {
 
@@ -24,7 +52,7 @@ impl CollapseFindOBFScanner {
         };
 
         let result = self
-            .scan_class_data(&data, &res_info.path, Some(res_info.clone()))?
+            .scan_class_data(&data, &res_info.path, Some(res_info.clone()), None)?
             .unwrap_or_else(|| ScanResult {
                 file_path: res_info.path.clone(),
                 matches: Arc::new(Vec::new()),
@@ -32,6 +60,7 @@ impl CollapseFindOBFScanner {
                 resource_info: Some(res_info.clone()),
                 danger_score: 1,
                 danger_explanation: vec!["No suspicious elements detected.".to_string()],
+                partial_scan: false,
             });
 
         Ok(result)
@@ -42,41 +71,132 @@ impl CollapseFindOBFScanner {
         data: &[u8],
         original_path_str: &str,
         resource_info: Option<ResourceInfo>,
+        archive_context: Option<&ArchiveContext>,
     ) -> Result<Option<ScanResult>, ScanError> {
+        // Findings that drew on the rest of the archive are cached per
+        // archive; all others under the content hash alone.
         let data_hash = calculate_detection_hash(data);
-
-        if let Some(cached_findings) = self.get_cached_findings(data_hash) {
+        let archive_hash = archive_scoped_hash(data_hash, archive_context);
+
+        // The cache is keyed by content, so the name check is applied on top.
+        let disguise = (!has_class_file_name(original_path_str)).then(|| {
+            (
+                FindingType::DisguisedFile,
+                format!("Class file stored as '{}'", original_path_str),
+            )
+        });
+
+        // Cached findings carry no class details, which `--inspect` prints.
+        if let Some(cached_findings) = self
+            .get_cached_findings(data_hash)
+            .or_else(|| self.get_cached_findings(archive_hash))
+            .filter(|_| !self.options.inspect)
+        {
+            let cached_findings = match disguise {
+                Some(finding) => Arc::new(
+                    cached_findings.iter().cloned().chain([finding]).collect(),
+                ),
+                None => cached_findings.clone(),
+            };
             return self.handle_cached_findings(
-                cached_findings.clone(),
+                cached_findings,
                 original_path_str,
                 resource_info,
             );
         }
 
-        let mut findings = Vec::new();
+        // Hashed before parsing, so an entry that does not parse still
+        // meets the known-malware digests.
+        let sha256 = sha256_hex(data);
 
-        if data.len() >= 2 && data[0] != 0xCA && data[1] != 0xFE {
+        if !data.starts_with(&[0xCA, 0xFE]) {
             return self.handle_non_standard_class(
                 data,
-                data_hash,
+                &sha256,
+                archive_hash,
                 original_path_str,
                 resource_info,
-                &mut findings,
+                archive_context,
             );
         }
 
-        let class_details = parse_class_structure(data, original_path_str, self.options.verbose)?;
+        let (mut findings, class_details, uses_archive) =
+            match self.analyze_class_bytes(data, &sha256, original_path_str, archive_context) {
+                Ok(analysis) => analysis,
+                Err(error) => {
+                    let findings = self.known_malware_entry_findings(CLASS_DATA_SUBJECT, &sha256);
+                    return self.unparsed_class_result(
+                        error,
+                        findings,
+                        original_path_str,
+                        resource_info,
+                    );
+                }
+            };
+
+        let cache_key = if uses_archive { archive_hash } else { data_hash };
+        let _cached_arc = self
+            .result_cache
+            .get_with(cache_key, || Arc::new(findings.clone()));
+        findings.extend(disguise);
+
+        self.create_scan_result(findings, class_details, original_path_str, resource_info)
+    }
+
+    /// Runs every class check. The flag is set when the findings may
+    /// depend on other entries: a decrypt helper was looked up outside the
+    /// class, or native methods were checked against bundled libraries.
+    fn analyze_class_bytes(
+        &self,
+        data: &[u8],
+        sha256: &str,
+        original_path_str: &str,
+        archive_context: Option<&ArchiveContext>,
+    ) -> Result<ClassAnalysis, ScanError> {
+        let mut findings = Vec::new();
+        let parsed = archive_context.and_then(|archive| archive.parsed_entry(original_path_str));
+        let mut class_details = match parsed {
+            Some(details) => details.clone(),
+            None => parse_class_structure(data, original_path_str, self.options.verbose)?,
+        };
+        class_details.recovered_strings = reconstruct_strings(&class_details);
+        let looked_outside = Cell::new(false);
+        let decrypted = {
+            let lookup = |name: &str| {
+                if name == class_details.class_name {
+                    Some(&class_details)
+                } else {
+                    looked_outside.set(true);
+                    archive_context.and_then(|archive| archive.class(name))
+                }
+            };
+            recover_decrypted_strings(&class_details, &lookup)
+        };
+        class_details.recovered_strings.extend(decrypted);
+        let uses_archive = looked_outside.get()
+            || class_details.methods.iter().any(|m| m.access_flags & ACC_NATIVE != 0);
 
         self.check_name_obfuscation(&class_details, &mut findings);
+        self.check_control_flow(&class_details, &mut findings);
+        self.check_dynamic_loading(&class_details, &mut findings);
+        self.check_annotation_rules(&class_details, &mut findings);
+        self.check_source_file(&class_details, &mut findings);
+        self.check_known_malware(sha256, &class_details, &mut findings);
+        self.check_yara_rules(data, &class_details, &mut findings);
+        if let Some(archive) = archive_context {
+            self.check_native_methods(&class_details, archive, &mut findings);
+        }
 
+        // Names and descriptors share the pool with literals; only `ldc`
+        // constants can hold a webhook the class actually uses.
+        for constant in class_details.string_constants() {
+            self.check_discord_webhook(constant, &mut findings);
+        }
         let strings_to_scan = self.prepare_strings_for_scanning(&class_details);
         self.scan_strings_for_webhooks_and_obfuscation(&strings_to_scan, &mut findings);
+        self.scan_recovered_strings(&class_details.recovered_strings, &mut findings);
 
-        let _cached_arc = self
-            .result_cache
-            .get_with(data_hash, || Arc::new(findings.clone()));
-
-        self.create_scan_result(findings, class_details, original_path_str, resource_info)
+        Ok((findings, class_details, uses_archive))
     }
 
     fn check_name_obfuscation(
@@ -152,12 +272,15 @@ impl CollapseFindOBFScanner {
             || full_name_lower.starts_with("org/w3c")
             || full_name_lower.starts_with("org/xmlpull")
             || full_name_lower.starts_with("org/tukaani")
-            || full_name_lower.contains("mixins")
             || full_name_lower.contains("libraries")
             || full_name_lower.contains("mappings")
             || full_name_lower.contains("remapper");
 
         if !is_library {
+            let kotlin = kotlin_metadata(details);
+            self.check_kotlin_metadata(details, kotlin.as_ref(), findings);
+            let kotlin = kotlin.and_then(Result::ok);
+
             const KNOWN_SHORT_PATHS: &[&str] = &[
                 "ru", "su", "ua", "us", "uk", "de", "fr", "cn", "jp", "kr", "br",
                 "es", "it", "pl", "cz", "nl", "se", "no", "fi", "dk", "at",
@@ -175,6 +298,9 @@ impl CollapseFindOBFScanner {
                 if KNOWN_SHORT_PATHS.iter().any(|&known| known.eq_ignore_ascii_case(part)) {
                     continue;
                 }
+                if kotlin.as_ref().is_some_and(|k| is_kotlin_class_name(part, k)) {
+                    continue;
+                }
                 if self.is_random_name(part) {
                     findings.push((
                         FindingType::ObfuscationRandomName,
@@ -185,13 +311,22 @@ impl CollapseFindOBFScanner {
             }
 
             let mut short_names_count = 0;
+            const ACC_SYNTHETIC: u16 = 0x1000;
+            let kotlin_generated = |name: &str, access_flags: u16| {
+                kotlin.as_ref().is_some_and(|k| {
+                    access_flags & ACC_SYNTHETIC != 0 || k.declares(name)
+                })
+            };
             for method in &details.methods {
-                if method.name.len() <= 1 && !method.name.starts_with('<') {
+                if method.name.len() <= 1
+                    && !method.name.starts_with('<')
+                    && !kotlin_generated(&method.name, method.access_flags)
+                {
                    short_names_count += 1;
                 }
             }
             for field in &details.fields {
-                if field.name.len() <= 1 {
+                if field.name.len() <= 1 && !kotlin_generated(&field.name, field.access_flags) {
                     short_names_count += 1;
                 }
             }
@@ -204,7 +339,7 @@ impl CollapseFindOBFScanner {
             } else if short_names_count >= 10 && details.class_name.len() <= 2 {
                  findings.push((
                     FindingType::ObfuscationRandomName,
-                    format!("Class and members use obfuscated naming pattern"),
+                    "Class and members use obfuscated naming pattern".to_string(),
                 ));
             }
 
@@ -226,7 +361,7 @@ impl CollapseFindOBFScanner {
                 }
 
                 let suspicious_count = name.chars()
-                    .filter(|&c| !c.is_ascii() && !(c >= '\u{0400}' && c <= '\u{04FF}'))
+                    .filter(|&c| !c.is_ascii() && !('\u{0400}'..='\u{04FF}').contains(&c))
                     .count();
                 
                 let total_chars = name.chars().count();
@@ -267,7 +402,82 @@ impl CollapseFindOBFScanner {
         }
     }
 
-    fn is_random_name(&self, simple_name: &str) -> bool {
+    /// Kotlin classes whose `kotlin/Metadata` was stripped, no longer
+    /// decodes, or names functions the class no longer has.
+    fn check_kotlin_metadata(
+        &self,
+        details: &ClassDetails,
+        kotlin: Option<&Result<KotlinMetadata, String>>,
+        findings: &mut Vec<(FindingType, String)>,
+    ) {
+        let metadata = match kotlin {
+            None => {
+                if details.referenced_classes().any(|c| c == KOTLIN_INTRINSICS) {
+                    findings.push((
+                        FindingType::KotlinMetadata,
+                        format!(
+                            "Kotlin-compiled class {} has no kotlin/Metadata (stripped)",
+                            details.class_name
+                        ),
+                    ));
+                }
+                return;
+            }
+            Some(Err(e)) => {
+                findings.push((
+                    FindingType::KotlinMetadata,
+                    format!("kotlin/Metadata of {} does not decode: {}", details.class_name, e),
+                ));
+                return;
+            }
+            Some(Ok(metadata)) => metadata,
+        };
+
+        let mut functions: Vec<&str> = metadata.functions.iter().map(String::as_str).collect();
+        functions.sort_unstable();
+        functions.dedup();
+        if functions.len() < 5 {
+            return;
+        }
+        let random = functions.iter().filter(|f| self.is_random_name(f)).count();
+        if random * 2 > functions.len() {
+            findings.push((
+                FindingType::KotlinMetadata,
+                format!(
+                    "kotlin/Metadata of {} declares {} of {} functions with scrambled names",
+                    details.class_name,
+                    random,
+                    functions.len()
+                ),
+            ));
+            return;
+        }
+        // Kotlin adds `$default`, `$module` and `-hash` suffixes to JVM names.
+        let present = functions
+            .iter()
+            .filter(|f| {
+                details.methods.iter().any(|m| {
+                    m.name == **f
+                        || m.name
+                            .strip_prefix(**f)
+                            .is_some_and(|rest| rest.starts_with(['$', '-']))
+                })
+            })
+            .count();
+        if present * 5 < functions.len() {
+            findings.push((
+                FindingType::KotlinMetadata,
+                format!(
+                    "kotlin/Metadata of {} declares {} functions but only {} exist under those names (members renamed)",
+                    details.class_name,
+                    functions.len(),
+                    present
+                ),
+            ));
+        }
+    }
+
+    pub(crate) fn is_random_name(&self, simple_name: &str) -> bool {
         let len = simple_name.len();
         if len == 0 { return false; }
 
@@ -350,6 +560,184 @@ impl CollapseFindOBFScanner {
         }
     }
 
+    fn check_control_flow(
+        &self,
+        details: &ClassDetails,
+        findings: &mut Vec<(FindingType, String)>,
+    ) {
+        const MAX_REPORTED_METHODS: usize = 5;
+
+        let flagged: Vec<(String, Vec<String>, usize)> = details
+            .methods
+            .iter()
+            .filter_map(|method| {
+                let metrics = analyze_method(method.code.as_ref()?);
+                let reasons = metrics.obfuscation_reasons();
+                if reasons.is_empty() {
+                    return None;
+                }
+                let name = qualified_method_name(
+                    &details.class_name,
+                    &method.name,
+                    &method.descriptor,
+                );
+                Some((name, reasons, metrics.cyclomatic_complexity))
+            })
+            .collect();
+
+        for (name, reasons, complexity) in flagged.iter().take(MAX_REPORTED_METHODS) {
+            findings.push((
+                FindingType::ObfuscationControlFlow,
+                format!(
+                    "{}: {} (cyclomatic complexity {})",
+                    truncate_string(name, 60),
+                    reasons.join(", "),
+                    complexity
+                ),
+            ));
+        }
+
+        if flagged.len() > MAX_REPORTED_METHODS {
+            findings.push((
+                FindingType::ObfuscationControlFlow,
+                format!(
+                    "{} {}",
+                    flagged.len() - MAX_REPORTED_METHODS,
+                    CONTROL_FLOW_OVERFLOW
+                ),
+            ));
+        }
+    }
+
+    fn check_annotation_rules(
+        &self,
+        details: &ClassDetails,
+        findings: &mut Vec<(FindingType, String)>,
+    ) {
+        if self.options.annotation_rules.is_empty() {
+            return;
+        }
+        for (site, annotation) in details.annotation_sites() {
+            for rule in &self.options.annotation_rules {
+                if rule.matches(annotation) {
+                    findings.push((
+                        FindingType::AnnotationMatch,
+                        format!("{} on {} matches rule '{}'", annotation, site, rule.source),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn check_dynamic_loading(
+        &self,
+        details: &ClassDetails,
+        findings: &mut Vec<(FindingType, String)>,
+    ) {
+        if extends_class_loader(details) {
+            findings.push((
+                FindingType::DynamicClassLoading,
+                format!("Class extends {}", details.superclass_name),
+            ));
+        }
+
+        let mut by_api: Vec<(LoadingKind, &str, Vec<DynamicLoadSite>)> = Vec::new();
+        for site in find_dynamic_loading(details) {
+            match by_api.iter_mut().find(|(_, api, _)| *api == site.api) {
+                Some((_, _, sites)) => sites.push(site),
+                None => by_api.push((site.kind, site.api, vec![site])),
+            }
+        }
+
+        for (kind, api, sites) in by_api {
+            let mut targets: Vec<String> = Vec::new();
+            for target in sites.iter().filter_map(|s| s.target.as_ref()) {
+                let quoted = format!("\"{}\"", truncate_string(target, 60));
+                if !targets.contains(&quoted) {
+                    targets.push(quoted);
+                }
+            }
+
+            let mut message = if targets.is_empty() {
+                api.to_string()
+            } else {
+                format!("{}({})", api, targets.join(", "))
+            };
+            message.push_str(&format!(" in {}", truncate_string(&sites[0].method, 60)));
+            if sites.len() > 1 {
+                message.push_str(&format!(" (+{} more call sites)", sites.len() - 1));
+            }
+
+            let finding_type = match kind {
+                LoadingKind::DefinesClasses => FindingType::DynamicClassLoading,
+                LoadingKind::Reflection => FindingType::Reflection,
+            };
+            findings.push((finding_type, message));
+        }
+    }
+
+    fn check_native_methods(
+        &self,
+        details: &ClassDetails,
+        archive: &ArchiveContext,
+        findings: &mut Vec<(FindingType, String)>,
+    ) {
+        for method in details.methods.iter().filter(|m| m.access_flags & ACC_NATIVE != 0) {
+            if archive.has_jni_binding(&details.class_name, &method.name) == Some(false) {
+                findings.push((
+                    FindingType::NativeCode,
+                    format!(
+                        "Native method {} has no JNI export in the bundled libraries",
+                        truncate_string(
+                            &qualified_method_name(
+                                &details.class_name,
+                                &method.name,
+                                &method.descriptor
+                            ),
+                            80
+                        )
+                    ),
+                ));
+            }
+        }
+    }
+
+    fn scan_recovered_strings(
+        &self,
+        recovered: &[RecoveredString],
+        findings: &mut Vec<(FindingType, String)>,
+    ) {
+        for rebuilt in recovered {
+            let mut local = Vec::new();
+            self.check_discord_webhook(&rebuilt.value, &mut local);
+            self.check_obfuscated_string(&rebuilt.value, &mut local);
+
+            findings.extend(local.into_iter().map(|(finding_type, message)| {
+                (
+                    finding_type,
+                    format!(
+                        "{} [{}]",
+                        message,
+                        truncate_string(&rebuilt.provenance(), 140)
+                    ),
+                )
+            }));
+        }
+    }
+
+    pub(crate) fn check_discord_webhook(&self, string: &str, findings: &mut Vec<(FindingType, String)>) {
+        if !string.contains("/api/webhooks/") {
+            return;
+        }
+
+        for webhook in DISCORD_WEBHOOK_REGEX.find_iter(string) {
+            findings.push((
+                FindingType::DiscordWebhook,
+                format!("Discord webhook: {}", truncate_string(webhook.as_str(), 120)),
+            ));
+        }
+    }
+
     fn check_obfuscated_string(&self, string: &str, findings: &mut Vec<(FindingType, String)>) {
         let total_chars = string.chars().count();
         if total_chars < 40 {
@@ -358,7 +746,7 @@ impl CollapseFindOBFScanner {
 
         let junk_count = string.chars().filter(|&c| {
             (c.is_ascii() && c.is_ascii_control() && c != '\n' && c != '\r' && c != '\t') ||
-            (!c.is_ascii() && !(c >= '\u{0400}' && c <= '\u{04FF}'))
+            (!c.is_ascii() && !('\u{0400}'..='\u{04FF}').contains(&c))
         }).count();
 
         if junk_count > 30 && (junk_count * 100 / total_chars > 85) {
@@ -388,7 +776,7 @@ impl CollapseFindOBFScanner {
         self.result_cache.get(&hash)
     }
 
-    fn calculate_danger_score(
+    pub(crate) fn calculate_danger_score(
         &self,
         findings: &[(FindingType, String)],
         _resource_info: Option<&ResourceInfo>,
@@ -402,7 +790,11 @@ impl CollapseFindOBFScanner {
             *type_counts.entry(finding_type.clone()).or_insert(0) += 1;
         }
 
-        if *type_counts.get(&FindingType::DiscordWebhook).unwrap_or(&0) > 0 {
+        if *type_counts.get(&FindingType::DiscordWebhook).unwrap_or(&0) > 0
+            || type_counts
+                .keys()
+                .any(|t| matches!(t, FindingType::KnownMalware { .. }))
+        {
             return 10;
         }
 
@@ -417,7 +809,7 @@ impl CollapseFindOBFScanner {
         (score_acc as i32).clamp(1, 10) as u8
     }
 
-    fn generate_danger_explanation(
+    pub(crate) fn generate_danger_explanation(
         &self,
         score: u8,
         findings: &[(FindingType, String)],
@@ -448,28 +840,210 @@ impl CollapseFindOBFScanner {
                 .push(value.clone());
         }
 
-        if let Some(webhooks) = by_type.get(&FindingType::DiscordWebhook) {
-            if !webhooks.is_empty() {
-                explanations.push(format!("CRITICAL: Found {} Discord webhook(s)!", webhooks.len()));
-            }
+        if let Some(webhooks) = by_type.get(&FindingType::DiscordWebhook)
+            && !webhooks.is_empty()
+        {
+            explanations.push(format!("CRITICAL: Found {} Discord webhook(s)!", webhooks.len()));
         }
 
-        if let Some(unicode) = by_type.get(&FindingType::ObfuscationUnicode) {
-            if !unicode.is_empty() {
-                explanations.push(format!("Detected {} unicode obfuscated names.", unicode.len()));
-            }
+        let mut malware: Vec<String> = by_type
+            .keys()
+            .filter_map(|t| match t {
+                FindingType::KnownMalware { family, variant } => {
+                    Some(format!("{} {}", family, variant))
+                }
+                _ => None,
+            })
+            .collect();
+        if !malware.is_empty() {
+            malware.sort();
+            explanations.push(format!(
+                "CRITICAL: Matches known malware signature(s): {}.",
+                malware.join(", ")
+            ));
         }
 
-        if let Some(random) = by_type.get(&FindingType::ObfuscationRandomName) {
-            if !random.is_empty() {
-                explanations.push(format!("Detected {} random/obfuscated names.", random.len()));
-            }
+        let clamav = by_type
+            .iter()
+            .filter(|(t, _)| matches!(t, FindingType::ClamAvMatch { .. }))
+            .map(|(_, hits)| hits.len())
+            .sum::<usize>();
+        if clamav > 0 {
+            explanations.push(format!("Matched {} ClamAV signature(s).", clamav));
         }
 
-        if let Some(obf_strings) = by_type.get(&FindingType::ObfuscationString) {
-            if !obf_strings.is_empty() {
-                explanations.push(format!("Detected {} obfuscated strings.", obf_strings.len()));
-            }
+        let yara = by_type
+            .keys()
+            .filter(|t| matches!(t, FindingType::YaraMatch { .. }))
+            .count();
+        if yara > 0 {
+            explanations.push(format!("Matched {} YARA rule(s).", yara));
+        }
+
+        if let Some(unicode) = by_type.get(&FindingType::ObfuscationUnicode)
+            && !unicode.is_empty()
+        {
+            explanations.push(format!("Detected {} unicode obfuscated names.", unicode.len()));
+        }
+
+        if let Some(random) = by_type.get(&FindingType::ObfuscationRandomName)
+            && !random.is_empty()
+        {
+            explanations.push(format!("Detected {} random/obfuscated names.", random.len()));
+        }
+
+        if let Some(flow) = by_type.get(&FindingType::ObfuscationControlFlow)
+            && !flow.is_empty()
+        {
+            let methods = flow
+                .iter()
+                .filter(|message| !message.ends_with(CONTROL_FLOW_OVERFLOW))
+                .count();
+            explanations.push(format!(
+                "Detected control-flow obfuscation in {} method(s).",
+                methods
+            ));
+        }
+
+        if let Some(loading) = by_type.get(&FindingType::DynamicClassLoading)
+            && !loading.is_empty()
+        {
+            explanations.push(format!(
+                "Defines or loads classes at runtime ({} use(s)).",
+                loading.len()
+            ));
+        }
+
+        if let Some(reflection) = by_type.get(&FindingType::Reflection)
+            && !reflection.is_empty()
+        {
+            explanations.push(format!("Uses reflection ({} API(s)).", reflection.len()));
+        }
+
+        if let Some(native) = by_type.get(&FindingType::NativeCode)
+            && !native.is_empty()
+        {
+            explanations.push(format!("Native code concerns: {}.", native.len()));
+        }
+
+        if let Some(encrypted) = by_type.get(&FindingType::EncryptedClass)
+            && !encrypted.is_empty()
+        {
+            explanations.push(format!(
+                "{} class(es) stored encrypted or compressed.",
+                encrypted.len()
+            ));
+        }
+
+        if let Some(limits) = by_type.get(&FindingType::ResourceLimit)
+            && !limits.is_empty()
+        {
+            explanations.push(format!(
+                "Scan is partial: {} resource limit(s) hit.",
+                limits.len()
+            ));
+        }
+
+        if let Some(agent) = by_type.get(&FindingType::JavaAgent)
+            && !agent.is_empty()
+        {
+            explanations.push(format!(
+                "Manifest declares a Java agent or instrumentation rights ({}).",
+                agent.len()
+            ));
+        }
+
+        if let Some(manifest) = by_type.get(&FindingType::ManifestAnomaly)
+            && !manifest.is_empty()
+        {
+            explanations.push(format!("Manifest raises {} concern(s).", manifest.len()));
+        }
+
+        if let Some(mismatches) = by_type.get(&FindingType::SignatureMismatch)
+            && !mismatches.is_empty()
+        {
+            explanations.push(format!(
+                "Signed JAR content does not match its signature ({} finding(s)); it was likely tampered with after signing.",
+                mismatches.len()
+            ));
+        }
+
+        if let Some(mixins) = by_type.get(&FindingType::SensitiveMixin)
+            && !mixins.is_empty()
+        {
+            explanations.push(format!(
+                "Mixins patch {} session, auth, networking or file I/O target(s).",
+                mixins.len()
+            ));
+        }
+
+        if let Some(injected) = by_type.get(&FindingType::InjectedClass)
+            && !injected.is_empty()
+        {
+            explanations.push(format!(
+                "{} class(es) differ from the rest of the JAR in several ways, as injected or patched classes do.",
+                injected.len()
+            ));
+        }
+
+        if let Some(debug_info) = by_type.get(&FindingType::DebugInfo)
+            && !debug_info.is_empty()
+        {
+            explanations.push(format!(
+                "Debug info is stripped, forged or inconsistent with the rest of the JAR ({} finding(s)).",
+                debug_info.len()
+            ));
+        }
+
+        if let Some(kotlin) = by_type.get(&FindingType::KotlinMetadata)
+            && !kotlin.is_empty()
+        {
+            explanations.push(format!(
+                "Kotlin metadata is stripped, scrambled or out of sync with the bytecode in {} place(s).",
+                kotlin.len()
+            ));
+        }
+
+        if let Some(rules) = by_type.get(&FindingType::AnnotationMatch)
+            && !rules.is_empty()
+        {
+            explanations.push(format!(
+                "{} annotation(s) match user-supplied annotation rules.",
+                rules.len()
+            ));
+        }
+
+        if let Some(mod_metadata) = by_type.get(&FindingType::ModMetadata)
+            && !mod_metadata.is_empty()
+        {
+            explanations.push(format!(
+                "Mod metadata and its entrypoints raise {} concern(s).",
+                mod_metadata.len()
+            ));
+        }
+
+        if let Some(anomalies) = by_type.get(&FindingType::ArchiveAnomaly)
+            && !anomalies.is_empty()
+        {
+            explanations.push(format!(
+                "Archive structure has {} anomaly finding(s).",
+                anomalies.len()
+            ));
+        }
+
+        if let Some(disguised) = by_type.get(&FindingType::DisguisedFile)
+            && !disguised.is_empty()
+        {
+            explanations.push(format!(
+                "{} entry(ies) hidden under a misleading name.",
+                disguised.len()
+            ));
+        }
+
+        if let Some(obf_strings) = by_type.get(&FindingType::ObfuscationString)
+            && !obf_strings.is_empty()
+        {
+            explanations.push(format!("Detected {} obfuscated strings.", obf_strings.len()));
         }
 
         explanations
@@ -486,7 +1060,7 @@ impl CollapseFindOBFScanner {
         if !cached_findings.is_empty() || self.options.verbose {
             let danger_score = self.calculate_danger_score(cached_findings, resource_info.as_ref());
             
-            if !self.options.verbose && danger_score < 4 {
+            if !self.options.verbose && danger_score < MIN_REPORTED_DANGER_SCORE {
                 return Ok(None);
             }
 
@@ -503,48 +1077,99 @@ impl CollapseFindOBFScanner {
                 resource_info,
                 danger_score,
                 danger_explanation,
+                partial_scan: false,
             }))
         } else {
             Ok(None)
         }
     }
 
+    /// Entries named like classes that do not start with `CAFE`. Undoes
+    /// XOR and compression layers where possible, trying keys from loaders
+    /// in the same archive, and scans the result like any other class.
+    /// Those keys come from the archive, so `cache_key` is scoped to it.
     fn handle_non_standard_class(
         &self,
-        _data: &[u8],
-        data_hash: u64,
+        data: &[u8],
+        sha256: &str,
+        cache_key: u64,
         original_path_str: &str,
         resource_info: Option<ResourceInfo>,
-        findings: &mut Vec<(FindingType, String)>,
+        archive_context: Option<&ArchiveContext>,
     ) -> Result<Option<ScanResult>, ScanError> {
-        {
-            let mut found_flag = self.found_custom_jvm_indicator.lock().unwrap();
-            *found_flag = true;
-        }
+        let keys = archive_context.map_or(&[][..], |archive| archive.loader_keys());
+        let loaders = archive_context.map_or(&[][..], |archive| archive.defining_loaders());
 
-        self.cache_findings_new(data_hash, findings);
+        let digest_findings = self.known_malware_entry_findings(CLASS_DATA_SUBJECT, sha256);
 
-        if !findings.is_empty() || self.options.verbose {
-            let danger_score = self.calculate_danger_score(findings, resource_info.as_ref());
-            
-            if !self.options.verbose && danger_score < 4 {
-                return Ok(None);
+        let Some(recovered) = recover_class(data, keys) else {
+            self.unrecovered_class_entries
+                .lock()
+                .unwrap()
+                .push(original_path_str.to_string());
+
+            let mut findings = digest_findings;
+            if !loaders.is_empty() {
+                findings.push((
+                    FindingType::EncryptedClass,
+                    format!(
+                        "Undecodable class data, likely loaded by {}",
+                        truncate_string(&loaders.join(", "), 120)
+                    ),
+                ));
             }
+            self.cache_findings_new(cache_key, &findings);
+            return Ok(self.create_resource_result(findings, original_path_str, resource_info));
+        };
 
-            let danger_explanation =
-                self.generate_danger_explanation(danger_score, findings, resource_info.as_ref());
+        let analysis = self.analyze_class_bytes(
+            &recovered.data,
+            &sha256_hex(&recovered.data),
+            original_path_str,
+            archive_context,
+        );
+        let (mut findings, class_details, _) = match analysis {
+            Ok(analysis) => analysis,
+            Err(error) => {
+                return self.unparsed_class_result(
+                    error,
+                    digest_findings,
+                    original_path_str,
+                    resource_info,
+                );
+            }
+        };
+        findings.extend(digest_findings);
+        let mut transform = format!("Class recovered by undoing {}", recovered.describe());
+        let key_from_loader = recovered
+            .transforms
+            .iter()
+            .any(|t| matches!(t, ClassTransform::Xor { source: Some(_), .. }));
+        if !key_from_loader && !loaders.is_empty() {
+            transform.push_str(&format!(
+                "; candidate loader(s): {}",
+                truncate_string(&loaders.join(", "), 120)
+            ));
+        }
+        findings.insert(0, (FindingType::EncryptedClass, transform));
 
-            Ok(Some(ScanResult {
-                file_path: original_path_str.to_string(),
-                matches: Arc::new(findings.clone()),
-                class_details: None,
-                resource_info,
-                danger_score,
-                danger_explanation,
-            }))
-        } else {
-            Ok(None)
+        self.cache_findings_new(cache_key, &findings);
+        self.create_scan_result(findings, class_details, original_path_str, resource_info)
+    }
+
+    /// A class that fails to parse keeps the digest matches taken before
+    /// parsing; without any, the parse error stands.
+    fn unparsed_class_result(
+        &self,
+        error: ScanError,
+        digest_findings: Vec<(FindingType, String)>,
+        original_path_str: &str,
+        resource_info: Option<ResourceInfo>,
+    ) -> Result<Option<ScanResult>, ScanError> {
+        if digest_findings.is_empty() {
+            return Err(error);
         }
+        Ok(self.create_resource_result(digest_findings, original_path_str, resource_info))
     }
 
     fn cache_findings_new(&self, hash: u64, findings: &[(FindingType, String)]) {
@@ -553,6 +1178,37 @@ impl CollapseFindOBFScanner {
         let _ = self.result_cache.get_with(hash, || arc.clone());
     }
 
+    /// Builds a result for a non-class entry, applying the same score
+    /// threshold as class results.
+    pub(crate) fn create_resource_result(
+        &self,
+        findings: Vec<(FindingType, String)>,
+        original_path_str: &str,
+        resource_info: Option<ResourceInfo>,
+    ) -> Option<ScanResult> {
+        if findings.is_empty() && !self.options.verbose {
+            return None;
+        }
+
+        let danger_score = self.calculate_danger_score(&findings, resource_info.as_ref());
+        if !self.options.verbose && danger_score < MIN_REPORTED_DANGER_SCORE {
+            return None;
+        }
+
+        let danger_explanation =
+            self.generate_danger_explanation(danger_score, &findings, resource_info.as_ref());
+
+        Some(ScanResult {
+            file_path: original_path_str.to_string(),
+            matches: Arc::new(findings),
+            class_details: None,
+            resource_info,
+            danger_score,
+            danger_explanation,
+            partial_scan: false,
+        })
+    }
+
     fn create_scan_result(
         &self,
         findings: Vec<(FindingType, String)>,
@@ -560,26 +1216,186 @@ impl CollapseFindOBFScanner {
         original_path_str: &str,
         resource_info: Option<ResourceInfo>,
     ) -> Result<Option<ScanResult>, ScanError> {
-        if !findings.is_empty() || self.options.verbose {
-            let danger_score = self.calculate_danger_score(&findings, resource_info.as_ref());
-            
-            if !self.options.verbose && danger_score < 4 {
-                return Ok(None);
-            }
-
-            let danger_explanation =
-                self.generate_danger_explanation(danger_score, &findings, resource_info.as_ref());
+        let mut danger_score = self.calculate_danger_score(&findings, resource_info.as_ref());
+        let reported = self.options.verbose
+            || (!findings.is_empty() && danger_score >= MIN_REPORTED_DANGER_SCORE);
+        if !reported && !self.options.inspect {
+            return Ok(None);
+        }
 
-            Ok(Some(ScanResult {
-                file_path: original_path_str.to_string(),
-                matches: Arc::new(findings),
-                class_details: Some(class_details),
-                resource_info,
-                danger_score,
-                danger_explanation,
-            }))
+        // `--inspect` keeps unreported classes for their structure alone.
+        let findings = if reported {
+            findings
         } else {
-            Ok(None)
+            danger_score = self.calculate_danger_score(&[], resource_info.as_ref());
+            Vec::new()
+        };
+
+        let danger_explanation =
+            self.generate_danger_explanation(danger_score, &findings, resource_info.as_ref());
+
+        Ok(Some(ScanResult {
+            file_path: original_path_str.to_string(),
+            matches: Arc::new(findings),
+            class_details: Some(class_details),
+            resource_info,
+            danger_score,
+            danger_explanation,
+            partial_scan: false,
+        }))
+    }
+}
+
+/// Cache key for class findings that depend on the archive around them.
+fn archive_scoped_hash(data_hash: u64, archive_context: Option<&ArchiveContext>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (data_hash, archive_context.map(ArchiveContext::cache_key)).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Compiler-generated Kotlin class names: `Outer$inner$1` segments are
+/// declared names, lambda indices or `WhenMappings`-style helpers.
+fn is_kotlin_class_name(part: &str, kotlin: &KotlinMetadata) -> bool {
+    part.contains('$')
+        && part.split('$').filter(|s| !s.is_empty()).all(|segment| {
+            segment.chars().all(|c| c.is_ascii_digit())
+                || kotlin.declares(segment)
+                || matches!(segment, "Companion" | "WhenMappings" | "DefaultImpls" | "sam")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::malware_signatures::SignatureDatabase;
+    use crate::test_support::{junk, ClassBuilder};
+    use crate::types::ScannerOptions;
+
+    fn scanner_knowing(data: &[u8]) -> CollapseFindOBFScanner {
+        let pack = format!(
+            r#"{{"version": "test", "signatures": [{{"family": "Test", "variant": "A", "sha256": ["{}"]}}]}}"#,
+            sha256_hex(data)
+        );
+        let mut scanner = CollapseFindOBFScanner::new(ScannerOptions::default()).unwrap();
+        scanner.malware_signatures = Arc::new(SignatureDatabase::parse(&pack).unwrap());
+        scanner
+    }
+
+    fn is_known_malware(result: Option<ScanResult>) -> bool {
+        result.is_some_and(|result| {
+            result
+                .matches
+                .iter()
+                .any(|(finding_type, _)| matches!(finding_type, FindingType::KnownMalware { .. }))
+        })
+    }
+
+    #[test]
+    fn truncated_class_matches_its_digest() {
+        let class = ClassBuilder::new("p/Stub").build();
+        let truncated = &class[..class.len() - 4];
+        let scanner = scanner_knowing(truncated);
+
+        let result = scanner.scan_class_data(truncated, "p/Stub.class", None, None);
+        assert!(is_known_malware(result.unwrap()));
+    }
+
+    #[test]
+    fn undecodable_class_matches_its_digest() {
+        let data = junk(512);
+        let scanner = scanner_knowing(&data);
+
+        let result = scanner.scan_class_data(&data, "p/Stub.class", None, None);
+        assert!(is_known_malware(result.unwrap()));
+    }
+    #[test]
+    fn self_contained_classes_share_findings_across_archives() {
+        let class = ClassBuilder::new("p/Plain").build();
+        let scanner = CollapseFindOBFScanner::new(ScannerOptions::default()).unwrap();
+        let mut archive_hashes = Vec::new();
+        for neighbour in ["p/A", "p/B"] {
+            let entries = vec![
+                ("p/Plain.class".to_string(), Arc::new(class.clone())),
+                (
+                    format!("{neighbour}.class"),
+                    Arc::new(ClassBuilder::new(neighbour).build()),
+                ),
+            ];
+            let archive = ArchiveContext::build(&entries, false);
+            scanner
+                .scan_class_data(&class, "p/Plain.class", None, Some(&archive))
+                .unwrap();
+            let data_hash = calculate_detection_hash(&class);
+            archive_hashes.push(archive_scoped_hash(data_hash, Some(&archive)));
         }
+
+        assert_ne!(archive_hashes[0], archive_hashes[1]);
+        assert!(scanner.result_cache.contains_key(&calculate_detection_hash(&class)));
+        assert!(!archive_hashes.iter().any(|hash| scanner.result_cache.contains_key(hash)));
+    }
+    #[test]
+    fn recovers_classes_that_keep_half_the_magic() {
+        let class = ClassBuilder::new("p/Hidden").build();
+        let key = [0x00, 0x5A];
+        let data: Vec<u8> = class.iter().zip(key.iter().cycle()).map(|(b, k)| b ^ k).collect();
+        assert_eq!(data[0], 0xCA);
+
+        let scanner = CollapseFindOBFScanner::new(ScannerOptions::default()).unwrap();
+        let result = scanner.scan_class_data(&data, "p/Hidden.class", None, None);
+        let result = result.unwrap().unwrap();
+        assert!(result
+            .matches
+            .iter()
+            .any(|(finding_type, _)| *finding_type == FindingType::EncryptedClass));
+    }
+    #[test]
+    fn cached_yara_findings_leave_out_the_entry_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let rules = dir.path().join("rules.yar");
+        std::fs::write(&rules, r#"rule stub { strings: $a = "p/Stub" condition: $a }"#).unwrap();
+        let options = ScannerOptions {
+            yara_rules: vec![rules],
+            ..ScannerOptions::default()
+        };
+        let scanner = CollapseFindOBFScanner::new(options).unwrap();
+        let class = ClassBuilder::new("p/Stub").build();
+
+        scanner.scan_class_data(&class, "first/Copy.class", None, None).unwrap();
+        let cached = scanner
+            .get_cached_findings(calculate_detection_hash(&class))
+            .unwrap();
+        assert!(cached
+            .iter()
+            .any(|(finding_type, _)| matches!(finding_type, FindingType::YaraMatch { .. })));
+        assert!(!cached.iter().any(|(_, message)| message.contains("first/")));
+    }
+    #[test]
+    fn cached_digest_findings_leave_out_the_entry_path() {
+        let data = junk(512);
+        let scanner = scanner_knowing(&data);
+
+        scanner.scan_class_data(&data, "first/Copy.class", None, None).unwrap();
+        let cache_key = archive_scoped_hash(calculate_detection_hash(&data), None);
+        let cached = scanner.get_cached_findings(cache_key).unwrap();
+        assert!(cached
+            .iter()
+            .any(|(finding_type, _)| matches!(finding_type, FindingType::KnownMalware { .. })));
+        assert!(!cached.iter().any(|(_, message)| message.contains("first/")));
+
+        let result = scanner.scan_class_data(&data, "second/Copy.class", None, None);
+        let result = result.unwrap().unwrap();
+        assert_eq!(result.file_path, "second/Copy.class");
+        assert!(!result.matches.iter().any(|(_, message)| message.contains("first/")));
+    }
+    #[test]
+    fn mod_metadata_alone_is_not_reported() {
+        let scanner = CollapseFindOBFScanner::new(ScannerOptions::default()).unwrap();
+        let metadata = (FindingType::ModMetadata, "No @Mod class found for example".to_string());
+        let findings = vec![metadata.clone(); 5];
+        assert!(scanner.calculate_danger_score(&findings, None) < MIN_REPORTED_DANGER_SCORE);
+
+        let disguised = (FindingType::DisguisedFile, "Class file stored as 'a.png'".to_string());
+        let findings = [metadata, disguised];
+        assert!(scanner.calculate_danger_score(&findings, None) > MIN_REPORTED_DANGER_SCORE);
     }
 }
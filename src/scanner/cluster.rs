@@ -0,0 +1,243 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::errors::ScanError;
+use crate::fingerprint::JarFingerprint;
+use crate::parser::parse_class_structure;
+use crate::scanner::jar::{has_class_file_name, has_class_magic};
+use crate::scanner::scan::CollapseFindOBFScanner;
+
+/// Classes with the same normalised structure in more than one member.
+#[derive(Debug, Serialize)]
+pub struct SharedClass {
+    pub structure: String,
+    pub class_names: BTreeSet<String>,
+    pub archives: usize,
+    /// False when the copies carry different string constants, such as a
+    /// loader reused with another C2 address.
+    pub identical_strings: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveCluster {
+    pub members: Vec<JarFingerprint>,
+    /// Mean pairwise similarity of the members.
+    pub cohesion: f64,
+    pub shared_classes: Vec<SharedClass>,
+    /// Every IOC seen in the cluster, with the number of members containing it.
+    pub iocs: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClusterReport {
+    pub threshold: f64,
+    pub archives: usize,
+    pub clusters: Vec<ArchiveCluster>,
+    pub unclustered: Vec<String>,
+}
+
+impl CollapseFindOBFScanner {
+    pub(crate) fn fingerprint_archive(&self, path: &Path) -> Result<JarFingerprint, ScanError> {
+        let classes: Vec<_> = self
+            .read_archive_file(path)?
+            .into_iter()
+            .filter(|(name, data)| has_class_file_name(name) || has_class_magic(data))
+            .filter_map(|(name, data)| parse_class_structure(&data, &name, false).ok())
+            .collect();
+        Ok(JarFingerprint::new(path.display().to_string(), &classes))
+    }
+
+    /// Groups archives whose similarity reaches `threshold`, linking a
+    /// sample to a cluster through any one member.
+    pub(crate) fn cluster_archives(
+        &self,
+        inputs: &[PathBuf],
+        threshold: f64,
+    ) -> Result<ClusterReport, ScanError> {
+        let mut paths = Vec::new();
+        for input in inputs {
+            if input.is_dir() {
+                paths.extend(
+                    walkdir::WalkDir::new(input)
+                        .into_iter()
+                        .filter_map(|e| e.ok())
+                        .filter(|e| e.file_type().is_file())
+                        .map(|e| e.into_path())
+                        .filter(|p| p.extension().is_some_and(|ext| ext == "jar" || ext == "zip")),
+                );
+            } else {
+                paths.push(input.clone());
+            }
+        }
+        paths.sort();
+
+        let fingerprints: Vec<JarFingerprint> = paths
+            .par_iter()
+            .filter_map(|path| match self.fingerprint_archive(path) {
+                Ok(fingerprint) => Some(fingerprint),
+                Err(e) => {
+                    eprintln!(
+                        "{} Could not fingerprint {}: {}",
+                        yellow_text!("⚠️ "),
+                        path.display(),
+                        e
+                    );
+                    None
+                }
+            })
+            .collect();
+
+        let mut parent: Vec<usize> = (0..fingerprints.len()).collect();
+        for i in 0..fingerprints.len() {
+            for j in i + 1..fingerprints.len() {
+                if fingerprints[i].similarity(&fingerprints[j]) >= threshold {
+                    let (a, b) = (find_root(&mut parent, i), find_root(&mut parent, j));
+                    parent[b] = a;
+                }
+            }
+        }
+        let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for i in 0..fingerprints.len() {
+            let root = find_root(&mut parent, i);
+            groups.entry(root).or_default().push(i);
+        }
+
+        let archives = fingerprints.len();
+        let mut slots: Vec<Option<JarFingerprint>> = fingerprints.into_iter().map(Some).collect();
+        let mut clusters = Vec::new();
+        let mut unclustered = Vec::new();
+        for indices in groups.into_values() {
+            let members: Vec<JarFingerprint> =
+                indices.iter().filter_map(|&i| slots[i].take()).collect();
+            if members.len() == 1 {
+                unclustered.extend(members.into_iter().map(|m| m.path));
+            } else {
+                clusters.push(build_cluster(members));
+            }
+        }
+        clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.members.len()));
+
+        Ok(ClusterReport {
+            threshold,
+            archives,
+            clusters,
+            unclustered,
+        })
+    }
+}
+
+fn find_root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Class names, member indices and string digests seen for one structure.
+type StructureCopies = (BTreeSet<String>, BTreeSet<usize>, BTreeSet<u64>);
+
+fn build_cluster(members: Vec<JarFingerprint>) -> ArchiveCluster {
+    let mut pairs = 0;
+    let mut total = 0.0;
+    for i in 0..members.len() {
+        for j in i + 1..members.len() {
+            total += members[i].similarity(&members[j]);
+            pairs += 1;
+        }
+    }
+
+    let mut by_structure: BTreeMap<u64, StructureCopies> = BTreeMap::new();
+    for (index, member) in members.iter().enumerate() {
+        for class in &member.classes {
+            let (names, archives, strings) = by_structure.entry(class.structure).or_default();
+            names.insert(class.class_name.clone());
+            archives.insert(index);
+            strings.insert(class.strings);
+        }
+    }
+    let mut shared_classes: Vec<SharedClass> = by_structure
+        .into_iter()
+        .filter(|(_, (_, archives, _))| archives.len() > 1)
+        .map(|(structure, (class_names, archives, strings))| SharedClass {
+            structure: format!("{:016x}", structure),
+            class_names,
+            archives: archives.len(),
+            identical_strings: strings.len() == 1,
+        })
+        .collect();
+    shared_classes.sort_by_key(|shared| std::cmp::Reverse(shared.archives));
+
+    let mut iocs: BTreeMap<String, usize> = BTreeMap::new();
+    for member in &members {
+        for ioc in &member.iocs {
+            *iocs.entry(ioc.clone()).or_default() += 1;
+        }
+    }
+
+    ArchiveCluster {
+        cohesion: if pairs == 0 { 1.0 } else { total / pairs as f64 },
+        members,
+        shared_classes,
+        iocs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scanner::scan::CollapseFindOBFScanner;
+    use crate::test_support::{jar, ClassBuilder};
+    use crate::types::ScannerOptions;
+
+    fn sample(strings: &[&str]) -> Vec<u8> {
+        let classes: Vec<(String, Vec<u8>)> = strings
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let name = format!("p/C{}", index);
+                let mut class = ClassBuilder::new(&name);
+                class.string(value);
+                (format!("{}.class", name), class.build())
+            })
+            .collect();
+        let entries: Vec<(&str, &[u8])> = classes
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.as_slice()))
+            .collect();
+        jar(&entries)
+    }
+
+    #[test]
+    fn groups_samples_sharing_their_strings() {
+        let dir = tempfile::tempdir().unwrap();
+        let family = sample(&["http://c2.example/stage", "token", "payload"]);
+        for (name, data) in [
+            ("a.jar", family.clone()),
+            ("b.jar", family),
+            ("other.jar", sample(&["hello", "world", "config"])),
+        ] {
+            std::fs::write(dir.path().join(name), data).unwrap();
+        }
+
+        let scanner = CollapseFindOBFScanner::new(ScannerOptions::default()).unwrap();
+        let report = scanner
+            .cluster_archives(&[dir.path().to_path_buf()], 0.6)
+            .unwrap();
+
+        assert_eq!(report.archives, 3);
+        assert_eq!(report.clusters.len(), 1);
+        let cluster = &report.clusters[0];
+        let members: Vec<&str> = cluster
+            .members
+            .iter()
+            .map(|member| member.path.rsplit('/').next().unwrap())
+            .collect();
+        assert_eq!(members, ["a.jar", "b.jar"]);
+        assert_eq!(cluster.iocs.get("http://c2.example/stage"), Some(&2));
+        assert_eq!(report.unclustered.len(), 1);
+        assert!(report.unclustered[0].ends_with("other.jar"));
+    }
+}
@@ -0,0 +1,238 @@
+use crate::scanner::archive::ArchiveContext;
+use crate::scanner::scan::CollapseFindOBFScanner;
+use crate::types::{ClassDetails, FindingType};
+use crate::utils::truncate_string;
+
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "java", "kt", "kts", "groovy", "gvy", "scala", "clj", "cljc", "jav",
+];
+/// Below this many classes with code, a JAR has no meaningful majority.
+const MIN_CLASSES_FOR_COVERAGE: usize = 5;
+/// Share of classes that must agree before the rest count as outliers.
+const MAJORITY_PERCENT: usize = 90;
+const MAX_OUTLIERS_REPORTED: usize = 10;
+
+const ACC_PUBLIC: u16 = 0x0001;
+
+/// Whether a class keeps `SourceFile` and line numbers, in every combination.
+pub(crate) const DEBUG_PROFILES: [(bool, bool); 4] =
+    [(false, false), (false, true), (true, false), (true, true)];
+
+impl CollapseFindOBFScanner {
+    /// `SourceFile` values that are not a source file name, or that name a
+    /// different file than a public top-level Java class must live in.
+    pub(crate) fn check_source_file(
+        &self,
+        details: &ClassDetails,
+        findings: &mut Vec<(FindingType, String)>,
+    ) {
+        let Some(source_file) = &details.debug_info.source_file else {
+            return;
+        };
+        let Some((stem, extension)) = source_file.rsplit_once('.').filter(|(stem, extension)| {
+            !stem.is_empty() && SOURCE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+        }) else {
+            findings.push((
+                FindingType::DebugInfo,
+                format!(
+                    "SourceFile '{}' of {} is not a source file name",
+                    truncate_string(source_file, 40),
+                    details.class_name
+                ),
+            ));
+            return;
+        };
+
+        let simple_name = details.class_name.rsplit('/').next().unwrap_or_default();
+        if extension == "java"
+            && details.access_flags & ACC_PUBLIC != 0
+            && !simple_name.contains('$')
+            && stem != simple_name
+        {
+            findings.push((
+                FindingType::DebugInfo,
+                format!(
+                    "SourceFile '{}' does not match public class {}",
+                    source_file, details.class_name
+                ),
+            ));
+        }
+    }
+
+    /// JAR-wide debug-info coverage, reported when most of it was stripped,
+    /// plus classes whose debug info differs from what nearly every other
+    /// class in the JAR has.
+    pub(crate) fn debug_info_findings(
+        &self,
+        archive_context: &ArchiveContext,
+    ) -> Vec<(FindingType, String)> {
+        let mut classes: Vec<&ClassDetails> = archive_context
+            .classes()
+            .filter(|details| details.debug_info.methods_with_code > 0)
+            .collect();
+        if classes.len() < MIN_CLASSES_FOR_COVERAGE {
+            return Vec::new();
+        }
+        classes.sort_by(|a, b| a.class_name.cmp(&b.class_name));
+
+        let total = classes.len();
+        let with_lines = classes
+            .iter()
+            .filter(|d| d.debug_info.has_line_numbers())
+            .count();
+        let with_locals = classes
+            .iter()
+            .filter(|d| d.debug_info.methods_with_local_variables > 0)
+            .count();
+        let with_source = classes
+            .iter()
+            .filter(|d| d.debug_info.source_file.is_some())
+            .count();
+
+        let mut findings = Vec::new();
+        if with_lines * 2 < total {
+            findings.push((
+                FindingType::DebugInfo,
+                format!(
+                    "Debug info stripped: line numbers in {} of {} classes, local variables in {}, SourceFile in {}",
+                    with_lines, total, with_locals, with_source
+                ),
+            ));
+        }
+
+        let mut counts = [[0usize; 2]; 2];
+        for details in &classes {
+            let (source, lines) = debug_profile(details);
+            counts[source as usize][lines as usize] += 1;
+        }
+        let Some(majority) = DEBUG_PROFILES
+            .into_iter()
+            .find(|(source, lines)| {
+                counts[*source as usize][*lines as usize] * 100 >= total * MAJORITY_PERCENT
+            })
+        else {
+            return findings;
+        };
+
+        let outliers: Vec<&&ClassDetails> = classes
+            .iter()
+            .filter(|details| profile_deviates(debug_profile(details), majority))
+            .collect();
+        for details in outliers.iter().take(MAX_OUTLIERS_REPORTED) {
+            findings.push((
+                FindingType::DebugInfo,
+                format!(
+                    "{} has {} while {} of {} classes have {}",
+                    details.class_name,
+                    describe_profile(debug_profile(details)),
+                    counts[majority.0 as usize][majority.1 as usize],
+                    total,
+                    describe_profile(majority)
+                ),
+            ));
+        }
+        if outliers.len() > MAX_OUTLIERS_REPORTED {
+            findings.push((
+                FindingType::DebugInfo,
+                format!(
+                    "{} more classes differ from the JAR's debug info",
+                    outliers.len() - MAX_OUTLIERS_REPORTED
+                ),
+            ));
+        }
+        findings
+    }
+}
+
+/// Whether a class keeps `SourceFile` and line numbers.
+pub(crate) fn debug_profile(details: &ClassDetails) -> (bool, bool) {
+    (
+        details.debug_info.source_file.is_some(),
+        details.debug_info.has_line_numbers(),
+    )
+}
+
+pub(crate) fn describe_profile(profile: (bool, bool)) -> &'static str {
+    match profile {
+        (true, true) => "SourceFile and line numbers",
+        (true, false) => "SourceFile but no line numbers",
+        (false, true) => "line numbers but no SourceFile",
+        (false, false) => "no debug info",
+    }
+}
+
+/// Generated classes often keep SourceFile but drop line numbers, so only a
+/// different SourceFile, or line numbers the norm lacks, sets a class apart.
+pub(crate) fn profile_deviates(profile: (bool, bool), norm: (bool, bool)) -> bool {
+    profile.0 != norm.0 || (profile.1 && !norm.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::test_support::ClassBuilder;
+    use crate::types::ScannerOptions;
+
+    fn class(name: &str, source_file: Option<&str>, debug_tables: &[&str]) -> Vec<u8> {
+        let mut class = ClassBuilder::new(name);
+        class.method(0x0009, "run", "()V", &[0xb1], debug_tables);
+        if let Some(source_file) = source_file {
+            class.source_file(source_file);
+        }
+        class.build()
+    }
+
+    fn messages(findings: &[(FindingType, String)]) -> Vec<&str> {
+        findings
+            .iter()
+            .map(|(_, message)| message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn flags_the_class_that_lost_its_debug_info() {
+        let mut entries: Vec<(String, Arc<Vec<u8>>)> = (0..9)
+            .map(|i| {
+                let name = format!("p/C{}", i);
+                let data = class(&name, Some(&format!("C{}.java", i)), &["LineNumberTable"]);
+                (format!("{}.class", name), Arc::new(data))
+            })
+            .collect();
+        entries.push((
+            "p/Odd.class".to_string(),
+            Arc::new(class("p/Odd", None, &[])),
+        ));
+        let archive = ArchiveContext::build(&entries, false);
+
+        let scanner = CollapseFindOBFScanner::new(ScannerOptions::default()).unwrap();
+        assert_eq!(
+            messages(&scanner.debug_info_findings(&archive)),
+            ["p/Odd has no debug info while 9 of 10 classes have SourceFile and line numbers"]
+        );
+    }
+
+    #[test]
+    fn checks_source_file_names() {
+        let scanner = CollapseFindOBFScanner::new(ScannerOptions::default()).unwrap();
+        let check = |source_file: &str| {
+            let data = class("p/Foo", Some(source_file), &[]);
+            let details =
+                crate::parser::parse_class_structure(&data, "p/Foo.class", false).unwrap();
+            let mut findings = Vec::new();
+            scanner.check_source_file(&details, &mut findings);
+            findings
+        };
+        assert!(check("Foo.java").is_empty());
+        assert!(check("Foo.kt").is_empty());
+        assert_eq!(
+            messages(&check("Bar.java")),
+            ["SourceFile 'Bar.java' does not match public class p/Foo"]
+        );
+        assert_eq!(
+            messages(&check("\u{0}")),
+            ["SourceFile '\u{0}' of p/Foo is not a source file name"]
+        );
+    }
+}
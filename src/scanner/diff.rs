@@ -0,0 +1,320 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::errors::ScanError;
+use crate::parser::parse_class_structure;
+use crate::scanner::jar::has_class_file_name;
+use crate::scanner::scan::CollapseFindOBFScanner;
+use crate::bytecode::qualified_method_name;
+use crate::types::{ClassDetails, FindingType};
+use crate::utils::sha256_hex;
+
+/// Class entries keyed by path, with their digest and contents.
+type ClassEntries = BTreeMap<String, (String, Arc<Vec<u8>>)>;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClassChange {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// How one class differs between two versions of an archive. Added classes
+/// list everything they declare and reference as new.
+#[derive(Debug, Serialize)]
+pub struct ClassDiff {
+    pub path: String,
+    pub change: ClassChange,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hierarchy_changes: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added_methods: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed_methods: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added_fields: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed_fields: Vec<String>,
+    /// Members kept under the same name and descriptor but with new access flags.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub access_changes: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added_strings: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed_strings: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added_method_refs: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NewFinding {
+    pub file_path: String,
+    pub finding_type: FindingType,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveDiff {
+    pub old: String,
+    pub new: String,
+    pub unchanged_classes: usize,
+    pub classes: Vec<ClassDiff>,
+    /// Findings reported for the new version that the old one did not have.
+    pub new_findings: Vec<NewFinding>,
+}
+
+impl CollapseFindOBFScanner {
+    pub(crate) fn diff_archives(&self, old: &Path, new: &Path) -> Result<ArchiveDiff, ScanError> {
+        let old_classes = self.read_class_entries(old)?;
+        let new_classes = self.read_class_entries(new)?;
+
+        let mut classes = Vec::new();
+        let mut unchanged_classes = 0;
+        for (path, (new_digest, new_data)) in &new_classes {
+            match old_classes.get(path) {
+                Some((old_digest, _)) if old_digest == new_digest => unchanged_classes += 1,
+                Some((_, old_data)) => {
+                    let old_details = parse_class_structure(old_data, path, false).ok();
+                    let new_details = parse_class_structure(new_data, path, false).ok();
+                    classes.push(diff_class(
+                        path,
+                        ClassChange::Modified,
+                        old_details.as_ref(),
+                        new_details.as_ref(),
+                    ));
+                }
+                None => {
+                    let new_details = parse_class_structure(new_data, path, false).ok();
+                    classes.push(diff_class(path, ClassChange::Added, None, new_details.as_ref()));
+                }
+            }
+        }
+        for path in old_classes.keys().filter(|path| !new_classes.contains_key(*path)) {
+            classes.push(diff_class(path, ClassChange::Removed, None, None));
+        }
+        classes.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let old_label = old.display().to_string();
+        let old_findings: HashSet<(String, FindingType, String)> = self
+            .scan_jar_file(old)?
+            .into_iter()
+            .flat_map(|result| {
+                result
+                    .matches
+                    .iter()
+                    .map(|(finding_type, message)| {
+                        finding_key(&old_label, &result.file_path, finding_type, message)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let new_label = new.display().to_string();
+        let mut new_findings = Vec::new();
+        for result in self.scan_jar_file(new)? {
+            for (finding_type, message) in result.matches.iter() {
+                let key = finding_key(&new_label, &result.file_path, finding_type, message);
+                if !old_findings.contains(&key) {
+                    new_findings.push(NewFinding {
+                        file_path: result.file_path.clone(),
+                        finding_type: finding_type.clone(),
+                        message: message.clone(),
+                    });
+                }
+            }
+        }
+        new_findings.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+
+        Ok(ArchiveDiff {
+            old: old.display().to_string(),
+            new: new.display().to_string(),
+            unchanged_classes,
+            classes,
+            new_findings,
+        })
+    }
+
+    /// Class entries of an archive keyed by path, with their digests.
+    fn read_class_entries(&self, path: &Path) -> Result<ClassEntries, ScanError> {
+        Ok(self
+            .read_archive_file(path)?
+            .into_iter()
+            .filter(|(name, _)| has_class_file_name(name))
+            .map(|(name, data)| (name, (sha256_hex(&data), data)))
+            .collect())
+    }
+}
+
+/// A finding keyed on where it is inside the archive, with the archive's
+/// own path left out, so copies of an archive under different names match.
+fn finding_key(
+    label: &str,
+    file_path: &str,
+    finding_type: &FindingType,
+    message: &str,
+) -> (String, FindingType, String) {
+    let path = match file_path.strip_prefix(label) {
+        Some(rest) => rest.trim_start_matches("!/"),
+        None => file_path,
+    };
+    (path.to_string(), finding_type.clone(), message.replace(label, ""))
+}
+
+fn diff_class<'a>(
+    path: &str,
+    change: ClassChange,
+    old: Option<&'a ClassDetails>,
+    new: Option<&'a ClassDetails>,
+) -> ClassDiff {
+    let mut diff = ClassDiff {
+        path: path.to_string(),
+        change,
+        hierarchy_changes: Vec::new(),
+        added_methods: Vec::new(),
+        removed_methods: Vec::new(),
+        added_fields: Vec::new(),
+        removed_fields: Vec::new(),
+        access_changes: Vec::new(),
+        added_strings: Vec::new(),
+        removed_strings: Vec::new(),
+        added_method_refs: Vec::new(),
+    };
+    if change == ClassChange::Removed {
+        return diff;
+    }
+    let Some(new) = new else {
+        return diff;
+    };
+
+    if let Some(old) = old {
+        if old.superclass_name != new.superclass_name {
+            diff.hierarchy_changes.push(format!(
+                "extends {} -> {}",
+                old.superclass_name, new.superclass_name
+            ));
+        }
+        for interface in new.interfaces.iter().filter(|i| !old.interfaces.contains(i)) {
+            diff.hierarchy_changes.push(format!("+ implements {}", interface));
+        }
+        for interface in old.interfaces.iter().filter(|i| !new.interfaces.contains(i)) {
+            diff.hierarchy_changes.push(format!("- implements {}", interface));
+        }
+    }
+
+    let method_members = |d: &'a ClassDetails| {
+        d.methods.iter().map(|m| (&m.name, &m.descriptor, m.access_flags))
+    };
+    let old_methods = members(old.map(method_members), "");
+    let new_methods = members(Some(method_members(new)), "");
+    compare_members(
+        "method",
+        &old_methods,
+        &new_methods,
+        &mut diff.added_methods,
+        &mut diff.removed_methods,
+        &mut diff.access_changes,
+    );
+    let field_members = |d: &'a ClassDetails| {
+        d.fields.iter().map(|f| (&f.name, &f.descriptor, f.access_flags))
+    };
+    let old_fields = members(old.map(field_members), ":");
+    let new_fields = members(Some(field_members(new)), ":");
+    compare_members(
+        "field",
+        &old_fields,
+        &new_fields,
+        &mut diff.added_fields,
+        &mut diff.removed_fields,
+        &mut diff.access_changes,
+    );
+
+    let old_strings = old.map(string_constants).unwrap_or_default();
+    let new_strings = string_constants(new);
+    diff.added_strings = new_strings.difference(&old_strings).cloned().collect();
+    diff.removed_strings = old_strings.difference(&new_strings).cloned().collect();
+
+    let old_refs = old.map(method_refs).unwrap_or_default();
+    diff.added_method_refs = method_refs(new).difference(&old_refs).cloned().collect();
+    diff
+}
+
+/// Members keyed as `name(args)ret` for methods and `name:type` for fields.
+fn members<'a>(
+    members: Option<impl Iterator<Item = (&'a String, &'a String, u16)>>,
+    separator: &str,
+) -> BTreeMap<String, u16> {
+    members
+        .into_iter()
+        .flatten()
+        .map(|(name, descriptor, access)| (format!("{}{}{}", name, separator, descriptor), access))
+        .collect()
+}
+
+fn compare_members(
+    kind: &str,
+    old: &BTreeMap<String, u16>,
+    new: &BTreeMap<String, u16>,
+    added: &mut Vec<String>,
+    removed: &mut Vec<String>,
+    access_changes: &mut Vec<String>,
+) {
+    for (member, access) in new {
+        match old.get(member) {
+            None => added.push(member.clone()),
+            Some(old_access) if old_access != access => access_changes.push(format!(
+                "{} {}: 0x{:04x} -> 0x{:04x}",
+                kind, member, old_access, access
+            )),
+            Some(_) => {}
+        }
+    }
+    removed.extend(old.keys().filter(|member| !new.contains_key(*member)).cloned());
+}
+
+fn string_constants(details: &ClassDetails) -> BTreeSet<String> {
+    details.string_constants().map(str::to_string).collect()
+}
+
+fn method_refs(details: &ClassDetails) -> BTreeSet<String> {
+    details
+        .method_refs()
+        .map(|member| qualified_method_name(member.owner, member.name, member.descriptor))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scanner::scan::CollapseFindOBFScanner;
+    use crate::test_support::{jar, ClassBuilder};
+    use crate::types::ScannerOptions;
+
+    #[test]
+    fn copies_of_an_archive_have_no_diff() {
+        let metadata = br#"{"schemaVersion": 1, "id": "example", "version": "1.0",
+            "entrypoints": {"main": ["com.example.Main"], "client": ["com.example.Missing"]}}"#;
+        let manifest = b"Manifest-Version: 1.0\r\nPremain-Class: com.example.Main\r\n\r\n";
+        let archive = jar(&[
+            ("META-INF/MANIFEST.MF", manifest),
+            ("fabric.mod.json", metadata),
+            ("com/example/Main.class", &ClassBuilder::new("com/example/Main").build()),
+            ("com/example/Hidden.txt", &ClassBuilder::new("com/example/Hidden").build()),
+        ]);
+        let dir = tempfile::tempdir().unwrap();
+        let (old, new) = (dir.path().join("mod-1.jar"), dir.path().join("mod-2.jar"));
+        std::fs::write(&old, &archive).unwrap();
+        std::fs::write(&new, &archive).unwrap();
+
+        let scanner = CollapseFindOBFScanner::new(ScannerOptions::default()).unwrap();
+        let findings = scanner.scan_jar_file(&new).unwrap();
+        let label = new.display().to_string();
+        assert!(findings.iter().any(|r| r.file_path == label && !r.matches.is_empty()));
+
+        let diff = scanner.diff_archives(&old, &new).unwrap();
+        assert_eq!(diff.unchanged_classes, 1);
+        assert!(diff.classes.is_empty());
+        assert!(diff.new_findings.is_empty(), "{:?}", diff.new_findings);
+    }
+}
@@ -1,25 +1,145 @@
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::Read;
+use std::io::{Cursor, Read, Seek};
 use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
-#[cfg(feature = "cli")]
-use colored::Colorize;
+use flate2::read::DeflateDecoder;
 use indicatif::{ProgressBar, ProgressStyle};
-use zip::ZipArchive;
+use memmap2::Mmap;
+use zip::{CompressionMethod, ZipArchive};
 
 use crate::config::SYSTEM_CONFIG;
 use crate::errors::ScanError;
+use crate::native::sniff_native_format;
+use crate::scanner::archive::ArchiveContext;
+use crate::scanner::mods::archive_entry_names;
 use crate::scanner::scan::CollapseFindOBFScanner;
-use crate::types::{ResourceInfo, ScanResult};
+use crate::types::{FindingType, ResourceInfo, ScanResult};
+use crate::utils::sha256_hex;
+use crate::zip_structure::analyze_zip_structure;
+
+/// Entry names with their decompressed contents.
+#[cfg(all(feature = "cli", not(feature = "gui")))]
+pub(crate) type ArchiveEntries = Vec<(String, Arc<Vec<u8>>)>;
+
+/// Archives up to this size are read into memory; larger ones are mapped.
+const MAX_BUFFERED_ARCHIVE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// The bytes of an archive on disk, read or mapped depending on its size.
+enum ArchiveData {
+    Buffered(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl std::ops::Deref for ArchiveData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            ArchiveData::Buffered(data) => data,
+            ArchiveData::Mapped(mapped) => mapped,
+        }
+    }
+}
+
+fn load_archive(path: &Path) -> Result<ArchiveData, ScanError> {
+    let mut file = File::open(path)?;
+    if file.metadata()?.len() <= MAX_BUFFERED_ARCHIVE_SIZE {
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        return Ok(ArchiveData::Buffered(data));
+    }
+    // SAFETY: the mapping is only read. Contents rewritten in place only
+    // yield garbage findings, which the parsers tolerate, but if another
+    // process truncates the file while it is mapped, touching the lost pages
+    // raises SIGBUS and kills the scan. Only archives too large to copy take
+    // that risk.
+    let mapped = unsafe { Mmap::map(&file)? };
+    Ok(ArchiveData::Mapped(mapped))
+}
+
+/// Largest single entry read into memory.
+fn max_entry_size() -> usize {
+    SYSTEM_CONFIG.buffer_size.min(16 * 1024 * 1024)
+}
+
+/// Resource use shared by an archive and every archive nested in it.
+#[derive(Default)]
+pub(super) struct ArchiveBudget {
+    uncompressed_bytes: u64,
+    entries: usize,
+    limit_hits: Vec<String>,
+}
+
+impl ArchiveBudget {
+    /// Reads an entry the scan itself did not keep, charged like any other.
+    /// `Err` with the reason when it does not fit the limits that are left.
+    pub(super) fn read_entry<R: Read + Seek>(
+        &mut self,
+        archive: &mut ZipArchive<R>,
+        name: &str,
+    ) -> Result<Vec<u8>, String> {
+        let limits = &SYSTEM_CONFIG.limits;
+        let unreadable = |e: &dyn std::fmt::Display| format!("could not be read ({})", e);
+        let mut entry = archive.by_name(name).map_err(|e| unreadable(&e))?;
+        let cap = (max_entry_size() as u64)
+            .min(
+                entry
+                    .compressed_size()
+                    .saturating_mul(limits.max_compression_ratio)
+                    .max(MIN_RATIO_CHECKED_SIZE),
+            )
+            .min(limits.max_total_uncompressed.saturating_sub(self.uncompressed_bytes));
+
+        let mut data = Vec::new();
+        let read = entry.by_ref().take(cap + 1).read_to_end(&mut data);
+        self.uncompressed_bytes += data.len() as u64;
+        read.map_err(|e| unreadable(&e))?;
+        if data.len() as u64 > cap {
+            return Err("exceeds the scan limits".to_string());
+        }
+        Ok(data)
+    }
+
+    pub(super) fn record_limit_hit(&mut self, hit: String) {
+        self.limit_hits.push(hit);
+    }
+}
 
 impl CollapseFindOBFScanner {
     pub(crate) fn scan_jar_file(&self, jar_path: &Path) -> Result<Vec<ScanResult>, ScanError> {
         let start_time = Instant::now();
-        let file = File::open(jar_path)?;
-        let mut archive = ZipArchive::new(file)?;
+        let mapped = load_archive(jar_path)?;
+        let label = jar_path.display().to_string();
+        if self.verify_known_good(&label, &mapped) {
+            return Ok(Vec::new());
+        }
+        let structure_findings = self.archive_structure_findings(&mapped);
+        let mut archive = match ZipArchive::new(Cursor::new(&mapped[..])) {
+            Ok(archive) => archive,
+            Err(e) => {
+                // The anomalies that broke the reader are still worth reporting.
+                if !structure_findings.is_empty() {
+                    eprintln!(
+                        "{} Could not read {} as a ZIP archive: {}",
+                        yellow_text!("⚠️ "),
+                        jar_path.display(),
+                        e
+                    );
+                    return Ok(self
+                        .archive_level_result(structure_findings, &[], &label)
+                        .into_iter()
+                        .collect());
+                }
+                return Err(e.into());
+            }
+        };
+        let mut archive_findings = structure_findings;
+        archive_findings.extend(self.clamav_findings(&label, &mapped));
+        archive_findings.extend(self.manifest_findings(&mut archive));
         let total_files = archive.len();
         let mut skipped_count = 0;
         let mut results = Vec::new();
@@ -28,13 +148,6 @@ impl CollapseFindOBFScanner {
             println!("{} Scanning JAR file: {}", blue_text!("🔎"), jar_path.display());
         }
 
-        let buffer_size = SYSTEM_CONFIG.buffer_size.min(16 * 1024 * 1024);
-        let _avg_entry_size = total_files.saturating_sub(1).max(1) * 1024;
-        let optimal_buffer = buffer_size;
-
-        let mut total_memory_used = 0u64;
-        let max_total_memory = 512 * 1024 * 1024;
-
         let pb_template = format!("{} [{{elapsed_precise}}] {{bar:40.cyan/blue}} {{pos:>7}}/{{len:7}} ({{percent}}%) Processing: {{msg}}", green_text!("🔍"));
         let progress_bar = Arc::new(Mutex::new(ProgressBar::new(total_files as u64)));
         progress_bar.lock().unwrap().set_style(
@@ -44,89 +157,249 @@ impl CollapseFindOBFScanner {
         );
 
         let processed_count = Arc::new(AtomicUsize::new(0));
-        if let Some(ref prog_arc) = self.options.progress {
-            if let Ok(mut gp) = prog_arc.lock() {
-                gp.total = total_files;
-                gp.current = 0;
-                gp.message = "Processing JAR entries...".to_string();
-            }
+        if let Some(ref prog_arc) = self.options.progress
+            && let Ok(mut gp) = prog_arc.lock()
+        {
+            gp.total = total_files;
+            gp.current = 0;
+            gp.message = "Processing JAR entries...".to_string();
         }
 
-        let results_arc: Arc<Mutex<Vec<ScanResult>>> = Arc::new(Mutex::new(Vec::new()));
+        let (mut mods, mod_findings) = self.read_mod_metadata(&mut archive);
+        archive_findings.extend(mod_findings);
+        let entry_names = archive_entry_names(&archive);
+
+        let mut budget = ArchiveBudget::default();
+        let entries =
+            self.read_archive_entries(&mut archive, &label, "", &mut skipped_count, &mut budget);
+        archive_findings.extend(self.signature_findings(&mut archive, &entries, "", &mut budget));
+        let archive_context = ArchiveContext::build(&entries, self.options.verbose);
+        results.extend(self.scan_archive_entries(
+            &entries,
+            &archive_context,
+            &progress_bar,
+            &processed_count,
+            0,
+            &mut budget,
+        ));
+        archive_findings.extend(self.mod_findings(
+            &mut mods,
+            &entry_names,
+            &archive_context,
+            &results,
+        ));
+        archive_findings.extend(self.mixin_findings(&mut archive, &mods, &archive_context));
+        archive_findings.extend(self.debug_info_findings(&archive_context));
+        archive_findings.extend(self.injected_class_findings(&mut archive, &archive_context));
+        self.record_mod_identities(&label, &mods);
+        results.extend(self.archive_level_result(archive_findings, &budget.limit_hits, &label));
 
-        rayon::scope(|scope| {
-            for i in 0..total_files {
-                let mut zip_file = match archive.by_index(i) {
-                    Ok(f) => f,
-                    Err(e) => {
-                        eprintln!(
-                            "{} Error accessing entry {} in {}: {}",
-                            yellow_text!("⚠️ "),
-                            i,
-                            jar_path.display(),
-                            e
-                        );
-                        continue;
-                    }
-                };
+        progress_bar.lock().unwrap().finish_with_message(format!(
+            "Finished processing {} files ({} skipped, {} analyzed)",
+            total_files,
+            skipped_count,
+            processed_count.load(Ordering::Relaxed)
+        ));
 
-                let original_entry_name = match zip_file.enclosed_name() {
-                    Some(p) => p.to_string_lossy().replace('\\', "/"),
-                    None => String::from_utf8_lossy(zip_file.name_raw()).replace('\\', "/"),
-                };
+        if let Some(ref prog_arc) = self.options.progress
+            && let Ok(mut gp) = prog_arc.lock()
+        {
+            gp.current = gp.total;
+            gp.message = format!(
+                "Finished processing {} files",
+                processed_count.load(Ordering::Relaxed)
+            );
+        }
 
-                if !self.should_scan(&original_entry_name) {
-                    skipped_count += 1;
-                    continue;
-                }
+        if self.options.verbose {
+            println!(
+                "{} JAR scan completed in {:.2}s",
+                green_text!("✅"),
+                start_time.elapsed().as_secs_f64()
+            );
+        }
 
-                let file_size = zip_file.size() as usize;
-                if file_size > optimal_buffer {
-                    skipped_count += 1;
-                    continue;
-                }
+        Ok(results)
+    }
 
-                if total_memory_used + file_size as u64 > max_total_memory {
-                    break;
-                }
+    /// Reads the entries of an archive on disk for tools that work on the
+    /// contents rather than the findings, under the same limits as a scan.
+    #[cfg(all(feature = "cli", not(feature = "gui")))]
+    pub(crate) fn read_archive_file(
+        &self,
+        path: &Path,
+    ) -> Result<ArchiveEntries, ScanError> {
+        let mapped = load_archive(path)?;
+        let mut archive = ZipArchive::new(Cursor::new(&mapped[..]))?;
+        let label = path.display().to_string();
+        let mut budget = ArchiveBudget::default();
+        let mut skipped = 0;
+        let entries = self.read_archive_entries(&mut archive, &label, "", &mut skipped, &mut budget);
+        for hit in budget.limit_hits {
+            eprintln!("{} {}", yellow_text!("⚠️ "), hit);
+        }
+        Ok(entries)
+    }
 
-                let mut buffer = Vec::with_capacity(file_size);
-                if let Err(e) = zip_file.read_to_end(&mut buffer) {
+    /// Reads every scannable entry of an archive into memory. `prefix` is
+    /// prepended to entry names so nested archives report `outer!/inner` paths.
+    /// Sizes are enforced on the bytes actually inflated; every limit hit is
+    /// recorded in `budget` instead of silently cutting the scan short.
+    fn read_archive_entries<R: Read + Seek>(
+        &self,
+        archive: &mut ZipArchive<R>,
+        archive_label: &str,
+        prefix: &str,
+        skipped_count: &mut usize,
+        budget: &mut ArchiveBudget,
+    ) -> Vec<(String, Arc<Vec<u8>>)> {
+        let limits = &SYSTEM_CONFIG.limits;
+        let optimal_buffer = max_entry_size();
+        let entry_count = archive.len();
+
+        let mut entries: Vec<(String, Arc<Vec<u8>>)> = Vec::new();
+        for i in 0..entry_count {
+            if budget.entries >= limits.max_archive_entries {
+                budget.limit_hits.push(format!(
+                    "Entry limit of {} reached in {}; {} entr(ies) not scanned",
+                    limits.max_archive_entries,
+                    archive_label,
+                    entry_count - i
+                ));
+                break;
+            }
+            let remaining_total = limits
+                .max_total_uncompressed
+                .saturating_sub(budget.uncompressed_bytes);
+            if remaining_total == 0 {
+                budget.limit_hits.push(format!(
+                    "Total uncompressed limit of {} MB reached in {}; {} entr(ies) not scanned",
+                    limits.max_total_uncompressed / (1024 * 1024),
+                    archive_label,
+                    entry_count - i
+                ));
+                break;
+            }
+
+            let mut zip_file = match archive.by_index(i) {
+                Ok(f) => f,
+                Err(e) => {
                     eprintln!(
-                        "{} Error reading content of {}: {}",
+                        "{} Error accessing entry {} in {}: {}",
                         yellow_text!("⚠️ "),
-                        original_entry_name,
+                        i,
+                        archive_label,
                         e
                     );
                     continue;
                 }
-                total_memory_used += buffer.len() as u64;
-                let arc_buf = Arc::new(buffer);
-                let name_clone = original_entry_name.clone();
-                let progress_bar_clone = progress_bar.clone();
-                let processed_count_clone = processed_count.clone();
+            };
+
+            let original_entry_name = match zip_file.enclosed_name() {
+                Some(p) => p.to_string_lossy().replace('\\', "/"),
+                None => String::from_utf8_lossy(zip_file.name_raw()).replace('\\', "/"),
+            };
+
+            if !self.should_scan(&original_entry_name) {
+                *skipped_count += 1;
+                continue;
+            }
+
+            budget.entries += 1;
+
+            // Declared sizes are only a hint; the caps apply to what inflates.
+            let ratio_cap = zip_file
+                .compressed_size()
+                .saturating_mul(limits.max_compression_ratio)
+                .max(MIN_RATIO_CHECKED_SIZE);
+            let cap = (optimal_buffer as u64).min(ratio_cap).min(remaining_total);
+
+            let mut buffer = Vec::with_capacity(zip_file.size().min(cap) as usize);
+            let read = zip_file.by_ref().take(cap + 1).read_to_end(&mut buffer);
+            let compressed_size = zip_file.compressed_size();
+            drop(zip_file);
+            if let Err(e) = read {
+                // A corrupted CRC does not stop the JVM from loading the entry,
+                // so it must not stop us from scanning it either.
+                match read_entry_ignoring_checksum(archive, i, cap as usize + 1) {
+                    Some(data) => buffer = data,
+                    None => {
+                        eprintln!(
+                            "{} Error reading content of {}: {}",
+                            yellow_text!("⚠️ "),
+                            original_entry_name,
+                            e
+                        );
+                        continue;
+                    }
+                }
+            }
+            budget.uncompressed_bytes += buffer.len() as u64;
+
+            if buffer.len() as u64 > cap {
+                if cap == remaining_total {
+                    budget.limit_hits.push(format!(
+                        "Total uncompressed limit of {} MB reached at {}{}; {} entr(ies) not scanned",
+                        limits.max_total_uncompressed / (1024 * 1024),
+                        prefix,
+                        original_entry_name,
+                        entry_count - i
+                    ));
+                    break;
+                }
+                budget.limit_hits.push(if cap == ratio_cap {
+                    format!(
+                        "{}{} inflates past {}:1 from {} compressed bytes; not scanned",
+                        prefix, original_entry_name, limits.max_compression_ratio, compressed_size
+                    )
+                } else {
+                    format!(
+                        "{}{} inflates past the {} MB entry limit; not scanned",
+                        prefix,
+                        original_entry_name,
+                        optimal_buffer / (1024 * 1024)
+                    )
+                });
+                continue;
+            }
+
+            entries.push((format!("{}{}", prefix, original_entry_name), Arc::new(buffer)));
+        }
+        entries
+    }
+
+    fn scan_archive_entries(
+        &self,
+        entries: &[(String, Arc<Vec<u8>>)],
+        archive_context: &ArchiveContext,
+        progress_bar: &Arc<Mutex<ProgressBar>>,
+        processed_count: &Arc<AtomicUsize>,
+        depth: usize,
+        budget: &mut ArchiveBudget,
+    ) -> Vec<ScanResult> {
+        let results_arc: Arc<Mutex<Vec<ScanResult>>> = Arc::new(Mutex::new(Vec::new()));
+
+        rayon::scope(|scope| {
+            for (name, buffer) in entries {
                 let results_clone = results_arc.clone();
 
                 scope.spawn(move |_| {
                     let res = self.process_jar_entry(
-                        &name_clone,
-                        arc_buf.as_ref(),
-                        &progress_bar_clone,
-                        &processed_count_clone,
+                        name,
+                        buffer.as_ref(),
+                        archive_context,
+                        progress_bar,
+                        processed_count,
                     );
 
                     match res {
-                        Ok((maybe_scan_result, _resource_info)) => {
-                            if let Some(sr) = maybe_scan_result {
-                                let mut guard = results_clone.lock().unwrap();
-                                guard.push(sr);
-                            }
-                        }
+                        Ok((Some(sr), _)) => results_clone.lock().unwrap().push(sr),
+                        Ok((None, _)) => {}
                         Err(e) => {
                             eprintln!(
                                 "{} Error processing JAR entry {}: {}",
                                 yellow_text!("⚠️ "),
-                                name_clone,
+                                name,
                                 e
                             );
                         }
@@ -135,71 +408,125 @@ impl CollapseFindOBFScanner {
             }
         });
 
-        let mut scan_results: Vec<Result<(Option<ScanResult>, ResourceInfo), ScanError>> =
-            Vec::new();
-        {
-            let guard = results_arc.lock().unwrap();
-            for sr in guard.iter() {
-                scan_results.push(Ok((
-                    Some(sr.clone()),
-                    ResourceInfo {
-                        path: "".to_string(),
-                        size: sr.resource_info.as_ref().map(|r| r.size).unwrap_or(0),
-                        is_class_file: sr.class_details.is_some(),
-                        is_dead_class_candidate: sr
-                            .resource_info
-                            .as_ref()
-                            .map(|r| r.is_dead_class_candidate)
-                            .unwrap_or(false),
-                    },
-                )));
-            }
-        }
+        let mut results = std::mem::take(&mut *results_arc.lock().unwrap());
 
-        for scan_result in scan_results {
-            match scan_result {
-                Ok((Some(scan_result), _)) => {
-                    results.push(scan_result);
-                }
-                Ok((None, _)) => {}
+        // Nested archives, jar-in-jar libraries and those hidden under other
+        // names alike, are opened and scanned like the outer JAR, up to the
+        // configured nesting depth.
+        for (name, buffer) in entries {
+            // Allowlisted archives were already recorded as entries.
+            if !has_zip_magic(buffer) || self.known_good_source(buffer).is_some() {
+                continue;
+            }
+            if depth >= SYSTEM_CONFIG.limits.max_nesting_depth {
+                budget.limit_hits.push(format!(
+                    "Nested archive {} exceeds the nesting depth limit of {}; not scanned",
+                    name, SYSTEM_CONFIG.limits.max_nesting_depth
+                ));
+                continue;
+            }
+            let mut archive = match ZipArchive::new(Cursor::new(buffer.as_slice())) {
+                Ok(archive) => archive,
                 Err(e) => {
-                    eprintln!("{} Error processing JAR entry: {}", yellow_text!("⚠️ "), e);
+                    if self.options.verbose {
+                        eprintln!(
+                            "{} Could not open nested archive {}: {}",
+                            yellow_text!("⚠️ "),
+                            name,
+                            e
+                        );
+                    }
+                    continue;
                 }
-            }
+            };
+            let mut archive_findings = self.archive_structure_findings(buffer);
+            archive_findings.extend(self.clamav_findings(name, buffer));
+            archive_findings.extend(self.manifest_findings(&mut archive));
+            let (mut mods, mod_findings) = self.read_mod_metadata(&mut archive);
+            archive_findings.extend(mod_findings);
+            let entry_names = archive_entry_names(&archive);
+
+            let mut skipped = 0;
+            let prefix = format!("{}!/", name);
+            let nested =
+                self.read_archive_entries(&mut archive, name, &prefix, &mut skipped, budget);
+            archive_findings.extend(self.signature_findings(&mut archive, &nested, &prefix, budget));
+            let nested_context = ArchiveContext::build(&nested, self.options.verbose);
+            let nested_results = self.scan_archive_entries(
+                &nested,
+                &nested_context,
+                progress_bar,
+                processed_count,
+                depth + 1,
+                budget,
+            );
+            archive_findings.extend(self.mod_findings(
+                &mut mods,
+                &entry_names,
+                &nested_context,
+                &nested_results,
+            ));
+            archive_findings.extend(self.mixin_findings(&mut archive, &mods, &nested_context));
+            archive_findings.extend(self.debug_info_findings(&nested_context));
+            archive_findings.extend(self.injected_class_findings(&mut archive, &nested_context));
+            self.record_mod_identities(name, &mods);
+            results.extend(nested_results);
+            results.extend(self.archive_level_result(archive_findings, &[], name));
         }
 
-        progress_bar.lock().unwrap().finish_with_message(format!(
-            "Finished processing {} files ({} skipped, {} analyzed)",
-            total_files,
-            skipped_count,
-            processed_count.load(Ordering::Relaxed)
-        ));
+        results
+    }
 
-        if let Some(ref prog_arc) = self.options.progress {
-            if let Ok(mut gp) = prog_arc.lock() {
-                gp.current = gp.total;
-                gp.message = format!(
-                    "Finished processing {} files",
-                    processed_count.load(Ordering::Relaxed)
-                );
+    /// Raw ZIP structure anomalies, at most a few of each kind.
+    fn archive_structure_findings(&self, data: &[u8]) -> Vec<(FindingType, String)> {
+        let anomalies = analyze_zip_structure(data);
+
+        let mut findings = Vec::new();
+        let mut reported: HashMap<&str, usize> = HashMap::new();
+        for anomaly in &anomalies {
+            let count = reported.entry(anomaly.kind()).or_default();
+            *count += 1;
+            if *count <= MAX_ANOMALIES_PER_KIND {
+                findings.push((FindingType::ArchiveAnomaly, anomaly.to_string()));
             }
         }
-
-        if self.options.verbose {
-            println!(
-                "{} JAR scan completed in {:.2}s",
-                green_text!("✅"),
-                start_time.elapsed().as_secs_f64()
-            );
+        let mut overflow: Vec<(&str, usize)> = reported
+            .into_iter()
+            .filter(|(_, count)| *count > MAX_ANOMALIES_PER_KIND)
+            .collect();
+        overflow.sort_unstable();
+        for (kind, count) in overflow {
+            findings.push((
+                FindingType::ArchiveAnomaly,
+                format!("{} more {}", count - MAX_ANOMALIES_PER_KIND, kind),
+            ));
         }
+        findings
+    }
 
-        Ok(results)
+    /// One result per archive for findings about the archive itself. Any
+    /// limit hit marks it as a partial scan.
+    fn archive_level_result(
+        &self,
+        mut findings: Vec<(FindingType, String)>,
+        limit_hits: &[String],
+        label: &str,
+    ) -> Option<ScanResult> {
+        findings.extend(
+            limit_hits
+                .iter()
+                .map(|hit| (FindingType::ResourceLimit, hit.clone())),
+        );
+        let mut result = self.create_resource_result(findings, label, None)?;
+        result.partial_scan = !limit_hits.is_empty();
+        Some(result)
     }
 
     pub fn process_jar_entry(
         &self,
         original_entry_name: &str,
         buffer: &[u8],
+        archive_context: &ArchiveContext,
         progress_bar: &Arc<Mutex<ProgressBar>>,
         processed_count: &Arc<AtomicUsize>,
     ) -> Result<(Option<ScanResult>, ResourceInfo), ScanError> {
@@ -208,23 +535,65 @@ impl CollapseFindOBFScanner {
         drop(pb_guard);
 
         let resource_info = self.analyze_resource(original_entry_name, buffer)?;
+        let known_good = self.verify_known_good(original_entry_name, buffer);
+        let is_class = resource_info.is_class_file || resource_info.is_dead_class_candidate;
+        // Class entries run the YARA rules during class analysis instead.
+        let mut additional_findings = Vec::new();
+        if !known_good {
+            additional_findings = self.clamav_findings(original_entry_name, buffer);
+            if !is_class {
+                additional_findings.extend(self.yara_entry_findings(original_entry_name, buffer));
+            }
+        }
 
-        let scan_result = if resource_info.is_class_file || resource_info.is_dead_class_candidate {
-            self.scan_class_data(buffer, original_entry_name, Some(resource_info.clone()))?
-        } else {
+        let scan_result = if known_good {
             None
+        } else if is_class {
+            self.scan_class_data(
+                buffer,
+                original_entry_name,
+                Some(resource_info.clone()),
+                Some(archive_context),
+            )?
+        } else if let Some(library) = archive_context.native_library(original_entry_name) {
+            self.scan_native_library(library, original_entry_name, Some(resource_info.clone()))
+        } else {
+            let mut findings =
+                self.known_malware_entry_findings(original_entry_name, &sha256_hex(buffer));
+            findings.append(&mut additional_findings);
+            if is_disguised_archive(original_entry_name, buffer) {
+                findings.push((
+                    FindingType::DisguisedFile,
+                    format!("ZIP archive stored as '{}'", original_entry_name),
+                ));
+            }
+            if findings.is_empty() {
+                None
+            } else {
+                self.create_resource_result(
+                    findings,
+                    original_entry_name,
+                    Some(resource_info.clone()),
+                )
+            }
         };
+        let scan_result = self.with_additional_findings(
+            scan_result,
+            additional_findings,
+            original_entry_name,
+            Some(resource_info.clone()),
+        );
 
         let count = processed_count.fetch_add(1, Ordering::Relaxed);
-        if let Some(ref prog_arc) = self.options.progress {
-            if let Ok(mut gp) = prog_arc.lock() {
-                if gp.cancelled {
-                    gp.message = "Scan cancelled".to_string();
-                    return Ok((None, resource_info));
-                }
-                gp.current = count + 1;
-                gp.message = original_entry_name.to_string();
+        if let Some(ref prog_arc) = self.options.progress
+            && let Ok(mut gp) = prog_arc.lock()
+        {
+            if gp.cancelled {
+                gp.message = "Scan cancelled".to_string();
+                return Ok((None, resource_info));
             }
+            gp.current = count + 1;
+            gp.message = original_entry_name.to_string();
         }
 
         if count.is_multiple_of(10) {
@@ -241,11 +610,10 @@ impl CollapseFindOBFScanner {
         original_path_str: &str,
         data: &[u8],
     ) -> Result<ResourceInfo, ScanError> {
-        let is_class_name_candidate =
-            original_path_str.ends_with(".class") || original_path_str.ends_with(".class/");
+        let is_class_name_candidate = has_class_file_name(original_path_str);
 
-        let is_standard_class_file =
-            is_class_name_candidate && data.len() >= 4 && &data[0..4] == b"\xCA\xFE\xBA\xBE";
+        // Content decides, so a class stored as `assets/icon.png` is still parsed.
+        let is_standard_class_file = has_class_magic(data);
 
         let is_dead_class_candidate =
             is_class_name_candidate && data.len() >= 2 && &data[0..2] != b"\xCA\xFE";
@@ -258,3 +626,111 @@ impl CollapseFindOBFScanner {
         })
     }
 }
+
+/// Entries that inflate to less than this are never judged by their ratio.
+const MIN_RATIO_CHECKED_SIZE: u64 = 1024 * 1024;
+const MAX_ANOMALIES_PER_KIND: usize = 5;
+
+const ARCHIVE_EXTENSIONS: &[&str] = &[
+    ".jar", ".zip", ".war", ".ear", ".apk", ".aar", ".litemod", ".mcpack", ".mrpack",
+];
+
+pub(crate) fn has_class_file_name(path: &str) -> bool {
+    path.ends_with(".class") || path.ends_with(".class/")
+}
+
+/// `CAFEBABE` is shared with fat Mach-O binaries, which are told apart by
+/// their small architecture count.
+pub(crate) fn has_class_magic(data: &[u8]) -> bool {
+    data.starts_with(b"\xCA\xFE\xBA\xBE") && sniff_native_format(data).is_none()
+}
+
+fn has_zip_magic(data: &[u8]) -> bool {
+    data.starts_with(b"PK\x03\x04") || data.starts_with(b"PK\x05\x06")
+}
+
+fn is_disguised_archive(path: &str, data: &[u8]) -> bool {
+    let lower = path.to_ascii_lowercase();
+    has_zip_magic(data) && !ARCHIVE_EXTENSIONS.iter().any(|ext| lower.ends_with(ext))
+}
+
+fn read_entry_ignoring_checksum<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    index: usize,
+    limit: usize,
+) -> Option<Vec<u8>> {
+    let mut raw = archive.by_index_raw(index).ok()?;
+    let method = raw.compression();
+    let mut compressed = Vec::new();
+    raw.by_ref()
+        .take(limit as u64)
+        .read_to_end(&mut compressed)
+        .ok()?;
+
+    match method {
+        CompressionMethod::Stored => Some(compressed),
+        CompressionMethod::Deflated => {
+            let mut data = Vec::new();
+            DeflateDecoder::new(compressed.as_slice())
+                .take(limit as u64)
+                .read_to_end(&mut data)
+                .ok()?;
+            Some(data)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::malware_signatures::SignatureDatabase;
+    use crate::test_support::{jar, ClassBuilder};
+    use crate::types::ScannerOptions;
+
+    fn scan_flagging(archive: &[u8], known_bad: &[u8]) -> Vec<ScanResult> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("outer.jar");
+        std::fs::write(&path, archive).unwrap();
+        let pack = format!(
+            r#"{{"version": "test", "signatures": [{{"family": "Test", "variant": "A", "sha256": ["{}"]}}]}}"#,
+            sha256_hex(known_bad)
+        );
+        let mut scanner = CollapseFindOBFScanner::new(ScannerOptions::default()).unwrap();
+        scanner.malware_signatures = Arc::new(SignatureDatabase::parse(&pack).unwrap());
+        scanner.scan_jar_file(&path).unwrap()
+    }
+
+    #[test]
+    fn scans_jar_in_jar_libraries() {
+        let class = ClassBuilder::new("p/Evil").build();
+        let inner = jar(&[("p/Evil.class", &class)]);
+        let outer = jar(&[("META-INF/jars/inner.jar", &inner)]);
+
+        let results = scan_flagging(&outer, &class);
+        assert!(results
+            .iter()
+            .any(|result| result.file_path == "META-INF/jars/inner.jar!/p/Evil.class"));
+    }
+
+    #[test]
+    fn stops_at_the_nesting_depth_limit() {
+        let class = ClassBuilder::new("p/Evil").build();
+        let mut archive = jar(&[("p/Evil.class", &class)]);
+        for _ in 0..=SYSTEM_CONFIG.limits.max_nesting_depth {
+            archive = jar(&[("lib.jar", &archive)]);
+        }
+
+        let results = scan_flagging(&archive, &class);
+        assert!(!results
+            .iter()
+            .any(|result| result.file_path.ends_with("p/Evil.class")));
+        assert!(results.iter().any(|result| {
+            result.partial_scan
+                && result
+                    .matches
+                    .iter()
+                    .any(|(_, message)| message.contains("nesting depth limit"))
+        }));
+    }
+}
@@ -0,0 +1,43 @@
+use crate::malware_signatures::SignatureMatch;
+use crate::scanner::scan::CollapseFindOBFScanner;
+use crate::types::{ClassDetails, FindingType};
+
+impl CollapseFindOBFScanner {
+    pub(crate) fn check_known_malware(
+        &self,
+        sha256: &str,
+        details: &ClassDetails,
+        findings: &mut Vec<(FindingType, String)>,
+    ) {
+        let matches = self.malware_signatures.match_class(details, sha256);
+        findings.extend(matches.iter().map(|m| known_malware_finding(&details.class_name, m)));
+    }
+
+    /// Resources, and classes that do not parse, can only match by digest.
+    pub(crate) fn known_malware_entry_findings(
+        &self,
+        subject: &str,
+        sha256: &str,
+    ) -> Vec<(FindingType, String)> {
+        self.malware_signatures
+            .match_digest(sha256)
+            .iter()
+            .map(|m| known_malware_finding(subject, m))
+            .collect()
+    }
+}
+
+fn known_malware_finding(subject: &str, signature_match: &SignatureMatch) -> (FindingType, String) {
+    let signature = signature_match.signature;
+    let mut message = format!("{} matches {}", subject, signature_match.evidence);
+    if let Some(description) = &signature.description {
+        message.push_str(&format!(" ({})", description));
+    }
+    (
+        FindingType::KnownMalware {
+            family: signature.family.clone(),
+            variant: signature.variant.clone(),
+        },
+        message,
+    )
+}
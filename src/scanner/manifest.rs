@@ -0,0 +1,70 @@
+use std::io::{Read, Seek};
+
+use zip::ZipArchive;
+
+use crate::manifest::{analyze_manifest, parse_manifest, ManifestIssueKind, MANIFEST_PATH};
+use crate::scanner::scan::CollapseFindOBFScanner;
+use crate::types::FindingType;
+
+/// Signed JARs list a digest per entry, so manifests can get large.
+const MAX_MANIFEST_SIZE: u64 = 16 * 1024 * 1024;
+
+impl CollapseFindOBFScanner {
+    /// JAR-level findings from `META-INF/MANIFEST.MF`, read straight from the
+    /// archive so include/exclude patterns cannot hide it.
+    pub(crate) fn manifest_findings<R: Read + Seek>(
+        &self,
+        archive: &mut ZipArchive<R>,
+    ) -> Vec<(FindingType, String)> {
+        let Some(text) = read_manifest(archive) else {
+            return Vec::new();
+        };
+        let manifest = parse_manifest(&text);
+        let issues = analyze_manifest(&manifest, |path| archive.index_for_name(path).is_some());
+
+        issues
+            .into_iter()
+            .filter_map(|issue| {
+                let finding_type = match issue.kind {
+                    ManifestIssueKind::Agent | ManifestIssueKind::Capability => {
+                        FindingType::JavaAgent
+                    }
+                    ManifestIssueKind::MainClass if !self.options.verbose => return None,
+                    _ => FindingType::ManifestAnomaly,
+                };
+                Some((finding_type, issue.message))
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn read_manifest<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Option<String> {
+    read_manifest_bytes(archive).map(|data| String::from_utf8_lossy(&data).into_owned())
+}
+
+/// The JDK falls back to a case-insensitive lookup, so we do too.
+pub(crate) fn read_manifest_bytes<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Option<Vec<u8>> {
+    let index = archive.index_for_name(MANIFEST_PATH).or_else(|| {
+        archive
+            .file_names()
+            .find(|name| name.eq_ignore_ascii_case(MANIFEST_PATH))
+            .and_then(|name| archive.index_for_name(name))
+    })?;
+    read_entry(archive, index, MAX_MANIFEST_SIZE)
+}
+
+/// Reads a whole entry, or `None` if it is unreadable or larger than `limit`.
+pub(crate) fn read_entry<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    index: usize,
+    limit: u64,
+) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    archive
+        .by_index(index)
+        .ok()?
+        .take(limit + 1)
+        .read_to_end(&mut data)
+        .ok()?;
+    (data.len() as u64 <= limit).then_some(data)
+}
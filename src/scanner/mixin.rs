@@ -0,0 +1,102 @@
+use std::io::{Read, Seek};
+
+use zip::ZipArchive;
+
+use crate::manifest::parse_manifest;
+use crate::mixin::{
+    mixin_targets, parse_mixin_config, sensitive_target_category, MANIFEST_MIXIN_CONFIGS,
+};
+use crate::mod_metadata::ModMetadata;
+use crate::scanner::archive::ArchiveContext;
+use crate::scanner::manifest::{read_entry, read_manifest};
+use crate::scanner::scan::CollapseFindOBFScanner;
+use crate::types::FindingType;
+
+const MAX_MIXIN_CONFIG_SIZE: u64 = 1024 * 1024;
+
+impl CollapseFindOBFScanner {
+    /// Resolves the mixins declared by mod metadata and the manifest to the
+    /// classes they patch, flagging patches to session, auth, networking
+    /// and file I/O classes.
+    pub(crate) fn mixin_findings<R: Read + Seek>(
+        &self,
+        archive: &mut ZipArchive<R>,
+        mods: &[ModMetadata],
+        archive_context: &ArchiveContext,
+    ) -> Vec<(FindingType, String)> {
+        let mut config_paths: Vec<String> = mods
+            .iter()
+            .flat_map(|m| m.mixin_configs.iter().cloned())
+            .collect();
+        if let Some(text) = read_manifest(archive)
+            && let Some(configs) = parse_manifest(&text).main.attribute(MANIFEST_MIXIN_CONFIGS)
+        {
+            config_paths.extend(
+                configs
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|path| !path.is_empty())
+                    .map(str::to_string),
+            );
+        }
+        config_paths.sort_unstable();
+        config_paths.dedup();
+
+        let mut findings = Vec::new();
+        for config_path in &config_paths {
+            let Some(index) = archive.index_for_name(config_path) else {
+                findings.push((
+                    FindingType::ModMetadata,
+                    format!("Mixin config {} is not in the JAR", config_path),
+                ));
+                continue;
+            };
+            let Some(data) = read_entry(archive, index, MAX_MIXIN_CONFIG_SIZE) else {
+                continue;
+            };
+            let config = match parse_mixin_config(&String::from_utf8_lossy(&data)) {
+                Ok(config) => config,
+                Err(e) => {
+                    findings.push((
+                        FindingType::ModMetadata,
+                        format!("Mixin config {} could not be parsed: {}", config_path, e),
+                    ));
+                    continue;
+                }
+            };
+
+            for class_name in config.mixins.iter().chain(&config.plugin) {
+                if archive
+                    .index_for_name(&format!("{}.class", class_name))
+                    .is_none()
+                {
+                    findings.push((
+                        FindingType::ModMetadata,
+                        format!(
+                            "Mixin class {} listed in {} is not in the JAR",
+                            class_name, config_path
+                        ),
+                    ));
+                }
+            }
+
+            for mixin in &config.mixins {
+                let Some(targets) = archive_context.class(mixin).and_then(mixin_targets) else {
+                    continue;
+                };
+                for target in targets {
+                    if let Some(category) = sensitive_target_category(&target) {
+                        findings.push((
+                            FindingType::SensitiveMixin,
+                            format!(
+                                "{} patches {} ({}) via {}",
+                                mixin, target, category, config_path
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+        findings
+    }
+}
@@ -1,4 +1,20 @@
+pub mod allowlist;
+pub mod archive;
+pub mod clamav;
+#[cfg(all(feature = "cli", not(feature = "gui")))]
+pub mod cluster;
 pub mod class;
+pub mod debug_info;
+#[cfg(all(feature = "cli", not(feature = "gui")))]
+pub mod diff;
 pub mod jar;
+pub mod malware;
+pub mod manifest;
+pub mod mixin;
+pub mod mods;
+pub mod native;
+pub mod outliers;
 pub mod path;
-pub mod scan;
\ No newline at end of file
+pub mod scan;
+pub mod signature;
+pub mod yara;
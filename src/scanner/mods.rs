@@ -0,0 +1,172 @@
+use std::collections::HashSet;
+use std::io::{Read, Seek};
+
+use zip::ZipArchive;
+
+use crate::mod_metadata::{parse_mod_metadata, Entrypoint, ModMetadata, METADATA_FILES};
+use crate::scanner::archive::ArchiveContext;
+use crate::scanner::manifest::read_entry;
+use crate::scanner::scan::CollapseFindOBFScanner;
+use crate::types::{FindingType, ScanResult};
+
+const MAX_METADATA_SIZE: u64 = 1024 * 1024;
+const MAX_REACHABLE_REPORTED: usize = 10;
+
+impl CollapseFindOBFScanner {
+    /// Mod loader and plugin metadata, read straight from the archive so
+    /// include/exclude patterns cannot hide it.
+    pub(crate) fn read_mod_metadata<R: Read + Seek>(
+        &self,
+        archive: &mut ZipArchive<R>,
+    ) -> (Vec<ModMetadata>, Vec<(FindingType, String)>) {
+        let mut mods = Vec::new();
+        let mut findings = Vec::new();
+        for (path, loader) in METADATA_FILES {
+            let Some(index) = archive.index_for_name(path) else {
+                continue;
+            };
+            let Some(data) = read_entry(archive, index, MAX_METADATA_SIZE) else {
+                continue;
+            };
+            match parse_mod_metadata(*loader, &String::from_utf8_lossy(&data)) {
+                Ok(parsed) => mods.extend(parsed),
+                Err(e) => findings.push((
+                    FindingType::ModMetadata,
+                    format!("{} could not be parsed: {}", path, e),
+                )),
+            }
+        }
+        (mods, findings)
+    }
+
+    /// Cross-checks declared entrypoints against the archive: missing or
+    /// obfuscated entrypoint classes, and classes reachable from an
+    /// entrypoint that have findings of their own.
+    pub(crate) fn mod_findings(
+        &self,
+        mods: &mut [ModMetadata],
+        entry_names: &HashSet<String>,
+        archive_context: &ArchiveContext,
+        results: &[ScanResult],
+    ) -> Vec<(FindingType, String)> {
+        let mut findings = Vec::new();
+
+        for metadata in mods.iter_mut() {
+            let annotations = metadata.loader.mod_annotations();
+            if !annotations.is_empty() {
+                let mut annotated: Vec<&str> = archive_context
+                    .classes()
+                    .filter(|details| {
+                        details
+                            .annotations
+                            .iter()
+                            .any(|a| annotations.contains(&a.type_descriptor.as_str()))
+                    })
+                    .map(|details| details.class_name.as_str())
+                    .collect();
+                annotated.sort_unstable();
+                if annotated.is_empty() && archive_context.classes().next().is_some() {
+                    findings.push((
+                        FindingType::ModMetadata,
+                        format!("No @Mod class found for {}", metadata.id),
+                    ));
+                }
+                metadata
+                    .entrypoints
+                    .extend(annotated.into_iter().map(|class_name| Entrypoint {
+                        kind: "@Mod".to_string(),
+                        class_name: class_name.to_string(),
+                    }));
+            }
+
+            for entrypoint in &metadata.entrypoints {
+                let class_name = &entrypoint.class_name;
+                if !entry_names.contains(&format!("{}.class", class_name)) {
+                    findings.push((
+                        FindingType::ModMetadata,
+                        format!(
+                            "{} entrypoint {} of {} is not in the JAR",
+                            entrypoint.kind, class_name, metadata.id
+                        ),
+                    ));
+                } else if self.is_obfuscated_class_name(class_name) {
+                    findings.push((
+                        FindingType::ModMetadata,
+                        format!(
+                            "{} entrypoint {} of {} has an obfuscated name",
+                            entrypoint.kind, class_name, metadata.id
+                        ),
+                    ));
+                }
+            }
+        }
+
+        let roots: Vec<String> = mods
+            .iter()
+            .flat_map(|m| m.entrypoints.iter().map(|e| e.class_name.clone()))
+            .collect();
+        let reachable = archive_context.reachable_from(&roots);
+        let mut flagged: Vec<String> = results
+            .iter()
+            .filter(|result| !result.matches.is_empty())
+            .filter_map(|result| {
+                let class_name = &result.class_details.as_ref()?.class_name;
+                let root = reachable.get(class_name)?;
+                let mut types: Vec<String> = result
+                    .matches
+                    .iter()
+                    .map(|(finding_type, _)| finding_type.to_string())
+                    .collect();
+                types.sort_unstable();
+                types.dedup();
+                Some(if class_name == root {
+                    format!("Entrypoint {} has findings: {}", class_name, types.join(", "))
+                } else {
+                    format!(
+                        "{} is reachable from entrypoint {} and has findings: {}",
+                        class_name,
+                        root,
+                        types.join(", ")
+                    )
+                })
+            })
+            .collect();
+        flagged.sort_unstable();
+        let extra = flagged.len().saturating_sub(MAX_REACHABLE_REPORTED);
+        findings.extend(
+            flagged
+                .into_iter()
+                .take(MAX_REACHABLE_REPORTED)
+                .map(|message| (FindingType::ModMetadata, message)),
+        );
+        if extra > 0 {
+            findings.push((
+                FindingType::ModMetadata,
+                format!("{} more entrypoint-reachable classes have findings", extra),
+            ));
+        }
+        findings
+    }
+
+    /// Random-looking class name, or a package of single-letter segments
+    /// as left behind by obfuscators.
+    pub(crate) fn is_obfuscated_class_name(&self, class_name: &str) -> bool {
+        let mut segments: Vec<&str> = class_name.split('/').collect();
+        let simple_name = segments.pop().unwrap_or_default();
+        self.is_random_name(simple_name)
+            || (segments.len() >= 2 && segments.iter().all(|s| s.len() == 1))
+    }
+
+    pub(crate) fn record_mod_identities(&self, label: &str, mods: &[ModMetadata]) {
+        if mods.is_empty() {
+            return;
+        }
+        let mut identities = self.mod_identities.lock().unwrap();
+        identities.extend(mods.iter().map(|m| (label.to_string(), m.clone())));
+    }
+}
+
+/// Every entry name in the archive, for entrypoint lookups.
+pub(crate) fn archive_entry_names<R: Read + Seek>(archive: &ZipArchive<R>) -> HashSet<String> {
+    archive.file_names().map(str::to_string).collect()
+}
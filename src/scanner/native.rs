@@ -0,0 +1,80 @@
+use crate::native::NativeLibrary;
+use crate::scanner::scan::CollapseFindOBFScanner;
+use crate::types::{FindingType, ResourceInfo, ScanResult};
+use crate::utils::truncate_string;
+
+impl CollapseFindOBFScanner {
+    pub(crate) fn scan_native_library(
+        &self,
+        library: &NativeLibrary,
+        original_path_str: &str,
+        resource_info: Option<ResourceInfo>,
+    ) -> Option<ScanResult> {
+        let mut findings = Vec::new();
+
+        let jni_symbols = library.jni_symbols();
+        let mut summary = format!(
+            "Bundled {} library for {} ({} imports, {} exports, {} JNI entry points)",
+            library.format,
+            library.architecture,
+            library.imports.len(),
+            library.exports.len(),
+            jni_symbols.len()
+        );
+        if !library.libraries.is_empty() {
+            summary.push_str(&format!(
+                ", links {}",
+                truncate_string(&library.libraries.join(", "), 80)
+            ));
+        }
+        findings.push((FindingType::NativeCode, summary));
+
+        if !has_native_library_name(original_path_str) {
+            findings.push((
+                FindingType::DisguisedFile,
+                format!("{} library stored as '{}'", library.format, original_path_str),
+            ));
+        }
+
+        let suspicious = library.suspicious_imports();
+        let mut categories: Vec<&str> = suspicious.iter().map(|(_, category)| *category).collect();
+        categories.sort_unstable();
+        categories.dedup();
+        for category in categories {
+            let names: Vec<&str> = suspicious
+                .iter()
+                .filter(|(_, c)| *c == category)
+                .map(|(name, _)| *name)
+                .collect();
+            findings.push((
+                FindingType::NativeCode,
+                format!("Imports {} APIs: {}", category, names.join(", ")),
+            ));
+        }
+
+        if !jni_symbols.is_empty() && self.options.verbose {
+            let classes: Vec<&str> = jni_symbols.iter().map(|s| s.class_name.as_str()).collect();
+            findings.push((
+                FindingType::NativeCode,
+                format!(
+                    "JNI bindings for: {}",
+                    truncate_string(&classes.join(", "), 120)
+                ),
+            ));
+        }
+
+        for string in &library.strings {
+            self.check_discord_webhook(string, &mut findings);
+        }
+
+        self.create_resource_result(findings, original_path_str, resource_info)
+    }
+}
+
+fn has_native_library_name(path: &str) -> bool {
+    let file_name = path.rsplit('/').next().unwrap_or(path).to_ascii_lowercase();
+    [".dll", ".dylib", ".jnilib", ".so"]
+        .iter()
+        .any(|ext| file_name.ends_with(ext))
+        || file_name.contains(".so.")
+}
@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+
+use zip::ZipArchive;
+
+use crate::scanner::archive::ArchiveContext;
+use crate::scanner::debug_info::{debug_profile, describe_profile, profile_deviates, DEBUG_PROFILES};
+use crate::scanner::scan::CollapseFindOBFScanner;
+use crate::types::{ClassDetails, FindingType};
+
+/// Below this many classes, a JAR has no meaningful norm.
+const MIN_CLASSES: usize = 10;
+/// Share of classes that must agree on a trait before it is a norm.
+const NORM_PERCENT: usize = 90;
+/// A single odd trait is common in legitimate JARs; injected classes
+/// usually differ in several.
+const MIN_DEVIATIONS: usize = 2;
+const MAX_OUTLIERS_REPORTED: usize = 10;
+
+/// Index of "debug info" in `FEATURES`.
+const DEBUG_INFO: usize = 2;
+
+const FEATURES: &[&str] = &[
+    "package",
+    "Java version",
+    "debug info",
+    "ZIP date",
+    "compression",
+    "naming",
+    "constant pool",
+];
+
+impl CollapseFindOBFScanner {
+    /// Classes that differ from the rest of the JAR in several ways at once,
+    /// as classes added to or patched into a legitimate mod do.
+    pub(crate) fn injected_class_findings<R: Read + Seek>(
+        &self,
+        archive: &mut ZipArchive<R>,
+        archive_context: &ArchiveContext,
+    ) -> Vec<(FindingType, String)> {
+        let mut classes: Vec<&ClassDetails> = archive_context.classes().collect();
+        if classes.len() < MIN_CLASSES {
+            return Vec::new();
+        }
+        classes.sort_by(|a, b| a.class_name.cmp(&b.class_name));
+
+        let mut zip_traits: HashMap<String, (String, String)> = HashMap::new();
+        for i in 0..archive.len() {
+            let Ok(entry) = archive.by_index_raw(i) else {
+                continue;
+            };
+            let Some(class_name) = entry.name().strip_suffix(".class") else {
+                continue;
+            };
+            let date = entry
+                .last_modified()
+                .map(|t| format!("{:04}-{:02}-{:02}", t.year(), t.month(), t.day()))
+                .unwrap_or_else(|| "no date".to_string());
+            zip_traits.insert(
+                class_name.to_string(),
+                (date, entry.compression().to_string()),
+            );
+        }
+
+        let traits: Vec<[Option<String>; 7]> = classes
+            .iter()
+            .map(|details| {
+                let zip = zip_traits.get(&details.class_name);
+                [
+                    Some(package_root(&details.class_name)),
+                    Some(java_version(details.major_version)),
+                    // Interfaces and annotations have no code to carry line numbers.
+                    (details.debug_info.methods_with_code > 0)
+                        .then(|| describe_profile(debug_profile(details)).to_string()),
+                    zip.map(|(date, _)| date.clone()),
+                    zip.map(|(_, compression)| compression.clone()),
+                    Some(
+                        if self.is_obfuscated_class_name(&details.class_name) {
+                            "obfuscated"
+                        } else {
+                            "readable"
+                        }
+                        .to_string(),
+                    ),
+                    Some(pool_layout(details)),
+                ]
+            })
+            .collect();
+
+        let norms: Vec<Option<(String, usize)>> = (0..FEATURES.len())
+            .map(|feature| {
+                let mut counts: HashMap<&str, usize> = HashMap::new();
+                let mut known = 0;
+                for value in traits.iter().filter_map(|t| t[feature].as_deref()) {
+                    *counts.entry(value).or_default() += 1;
+                    known += 1;
+                }
+                counts
+                    .into_iter()
+                    .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(a.0)))
+                    .filter(|(_, count)| known >= MIN_CLASSES && count * 100 >= known * NORM_PERCENT)
+                    .map(|(value, count)| (value.to_string(), count))
+            })
+            .collect();
+
+        let debug_norm = norms[DEBUG_INFO].as_ref().and_then(|(norm, _)| {
+            DEBUG_PROFILES
+                .into_iter()
+                .find(|profile| describe_profile(*profile) == norm)
+        });
+
+        let mut outliers = Vec::new();
+        for (details, class_traits) in classes.iter().zip(&traits) {
+            let reasons: Vec<String> = FEATURES
+                .iter()
+                .enumerate()
+                .filter_map(|(feature, label)| {
+                    let (norm, count) = norms[feature].as_ref()?;
+                    let value = class_traits[feature].as_ref()?;
+                    let deviates = match debug_norm {
+                        Some(debug_norm) if feature == DEBUG_INFO => {
+                            profile_deviates(debug_profile(details), debug_norm)
+                        }
+                        _ => value != norm,
+                    };
+                    deviates
+                        .then(|| format!("{}: {} vs {} in {} classes", label, value, norm, count))
+                })
+                .collect();
+            if reasons.len() >= MIN_DEVIATIONS {
+                outliers.push(format!(
+                    "{} stands out from the JAR: {}",
+                    details.class_name,
+                    reasons.join("; ")
+                ));
+            }
+        }
+
+        let mut findings: Vec<(FindingType, String)> = outliers
+            .iter()
+            .take(MAX_OUTLIERS_REPORTED)
+            .map(|message| (FindingType::InjectedClass, message.clone()))
+            .collect();
+        if outliers.len() > MAX_OUTLIERS_REPORTED {
+            findings.push((
+                FindingType::InjectedClass,
+                format!(
+                    "{} more classes stand out from the JAR",
+                    outliers.len() - MAX_OUTLIERS_REPORTED
+                ),
+            ));
+        }
+        findings
+    }
+}
+
+/// The first two package segments, where a mod's own code usually lives.
+fn package_root(class_name: &str) -> String {
+    let segments: Vec<&str> = class_name.split('/').collect();
+    let package = &segments[..segments.len() - 1];
+    if package.is_empty() {
+        "(default)".to_string()
+    } else {
+        package[..package.len().min(2)].join("/")
+    }
+}
+
+fn java_version(major_version: u16) -> String {
+    match major_version.checked_sub(44) {
+        Some(version) if version >= 1 => format!("Java {}", version),
+        _ => format!("class version {}", major_version),
+    }
+}
+
+/// Kinds of the first constant pool entries, which differ between javac,
+/// other compilers and bytecode libraries such as ASM.
+fn pool_layout(details: &ClassDetails) -> String {
+    details
+        .constant_pool
+        .iter()
+        .take(2)
+        .map(|entry| {
+            let kind = format!("{:?}", entry);
+            kind.split(['(', ' ']).next().unwrap_or_default().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::test_support::{ClassBuilder, jar, junk};
+    use crate::types::ScannerOptions;
+
+    fn class(name: &str, debug_info: bool) -> Vec<u8> {
+        let mut class = ClassBuilder::new(name);
+        if debug_info {
+            class.method(0x0009, "run", "()V", &[0xb1], &["LineNumberTable"]);
+            class.source_file("Source.java");
+        } else {
+            class.method(0x0009, "run", "()V", &[0xb1], &[]);
+        }
+        class.build()
+    }
+
+    fn outlier_findings(entries: &[(String, Vec<u8>)]) -> Vec<(FindingType, String)> {
+        let shared: Vec<(String, Arc<Vec<u8>>)> = entries
+            .iter()
+            .map(|(name, data)| (name.clone(), Arc::new(data.clone())))
+            .collect();
+        let archive_context = ArchiveContext::build(&shared, false);
+        let borrowed: Vec<(&str, &[u8])> = entries
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.as_slice()))
+            .collect();
+        let mut archive = ZipArchive::new(Cursor::new(jar(&borrowed))).unwrap();
+        let scanner = CollapseFindOBFScanner::new(ScannerOptions::default()).unwrap();
+        scanner.injected_class_findings(&mut archive, &archive_context)
+    }
+
+    fn mod_classes(count: usize) -> Vec<(String, Vec<u8>)> {
+        (0..count)
+            .map(|i| {
+                let name = format!("com/example/mod/Class{}", i);
+                (format!("{}.class", name), class(&name, true))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn flags_a_class_unlike_the_rest_of_the_jar() {
+        let mut entries = mod_classes(12);
+        assert!(outlier_findings(&entries).is_empty());
+
+        entries.push((
+            "net/other/Loader.class".to_string(),
+            class("net/other/Loader", false),
+        ));
+        let findings = outlier_findings(&entries);
+        assert_eq!(findings.len(), 1);
+        let (finding_type, message) = &findings[0];
+        assert_eq!(*finding_type, FindingType::InjectedClass);
+        assert!(message.starts_with("net/other/Loader stands out from the JAR: "));
+        assert!(message.contains("package: net/other vs com/example in 12 classes"));
+        assert!(message.contains("debug info: "));
+    }
+
+    #[test]
+    fn unparsable_classes_do_not_count_towards_a_norm() {
+        let mut entries = mod_classes(MIN_CLASSES - 2);
+        entries.push((
+            "net/other/Loader.class".to_string(),
+            class("net/other/Loader", false),
+        ));
+        for i in 0..MIN_CLASSES {
+            let mut data = b"\xCA\xFE\xBA\xBE".to_vec();
+            data.extend(junk(64 + i));
+            entries.push((format!("com/example/mod/Broken{}.class", i), data));
+        }
+        assert!(outlier_findings(&entries).is_empty());
+    }
+}
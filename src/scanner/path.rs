@@ -3,9 +3,6 @@ use std::fs::{self, File};
 use std::io::{self, BufRead, BufReader};
 use std::path::Path;
 
-#[cfg(feature = "cli")]
-use colored::Colorize;
-
 use crate::errors::ScanError;
 use crate::scanner::scan::CollapseFindOBFScanner;
 use crate::types::ScanResult;
@@ -80,6 +77,9 @@ impl CollapseFindOBFScanner {
                 );
             }
             let file_data = fs::read(path)?;
+            if self.verify_known_good(&path.display().to_string(), &file_data) {
+                return Ok(Vec::new());
+            }
             let resource_info = self.analyze_resource(&filename, &file_data)?;
             self.scan_class_file_data(&filename, file_data, Some(resource_info))
                 .map(|res| vec![res])
@@ -3,20 +3,31 @@ use moka::sync::Cache;
 use std::sync::{Arc, Mutex};
 use wildmatch::WildMatch;
 
-#[cfg(feature = "cli")]
-use colored::Colorize;
-
+use crate::allowlist::Allowlist;
+use crate::clamav::ClamAvDatabase;
 use crate::config::SYSTEM_CONFIG;
 use crate::errors::ScanError;
+use crate::malware_signatures::SignatureDatabase;
+use crate::mod_metadata::ModMetadata;
 use crate::types::ScannerOptions;
+use crate::yara::YaraRules;
 
 type ResultCache = Arc<Cache<u64, Arc<Vec<(crate::types::FindingType, String)>>>>;
 
 pub struct CollapseFindOBFScanner {
     pub options: ScannerOptions,
-    pub found_custom_jvm_indicator: Arc<Mutex<bool>>,
+    /// Class entries that could not be decoded into a class file.
+    pub unrecovered_class_entries: Arc<Mutex<Vec<String>>>,
+    /// Mods and plugins declared by scanned JARs, keyed by JAR path.
+    pub mod_identities: Arc<Mutex<Vec<(String, ModMetadata)>>>,
+    /// Archives and entries skipped as known-good, with their allowlist source.
+    pub known_good: Arc<Mutex<Vec<(String, String)>>>,
     pub exclude_patterns: Vec<WildMatch>,
     pub find_patterns: Vec<WildMatch>,
+    pub malware_signatures: Arc<SignatureDatabase>,
+    pub clamav: Arc<ClamAvDatabase>,
+    pub yara: Arc<YaraRules>,
+    pub allowlist: Arc<Allowlist>,
     pub result_cache: ResultCache,
 }
 
@@ -67,11 +78,66 @@ impl CollapseFindOBFScanner {
             SYSTEM_CONFIG.log_config();
         }
 
+        let (malware_signatures, pack_error) = SignatureDatabase::load();
+        if let Some(e) = pack_error {
+            eprintln!(
+                "{} Warning: Installed signature pack is invalid, using the built-in one: {}",
+                yellow_text!("⚠️"),
+                e
+            );
+        }
+        if options.verbose {
+            println!(
+                "{} Loaded malware signature pack {} ({} signatures)",
+                yellow_text!("☣️"),
+                malware_signatures.version,
+                malware_signatures.len()
+            );
+        }
+
+        let clamav = ClamAvDatabase::load(&options.clamav_databases)?;
+        if !clamav.is_empty() && options.verbose {
+            println!(
+                "{} Loaded {} ClamAV hash and {} body signatures",
+                yellow_text!("🦠"),
+                clamav.hash_signature_count(),
+                clamav.body_signature_count()
+            );
+        }
+        if clamav.skipped > 0 {
+            eprintln!(
+                "{} Warning: Skipped {} ClamAV signature(s) using unsupported features",
+                yellow_text!("⚠️"),
+                clamav.skipped
+            );
+        }
+
+        let yara = YaraRules::load(&options.yara_rules)?;
+        if !yara.is_empty() && options.verbose {
+            println!("{} Loaded {} YARA rules", yellow_text!("🔬"), yara.len());
+        }
+
+        let allowlist = Allowlist::load(&options.allowlists)?;
+        if !allowlist.is_empty() && options.verbose {
+            println!(
+                "{} Loaded {} known-good archive and {} entry digests",
+                yellow_text!("✅"),
+                allowlist.archives.len(),
+                allowlist.entries.len()
+            );
+        }
+
         Ok(CollapseFindOBFScanner {
             options,
-            found_custom_jvm_indicator: Arc::new(Mutex::new(false)),
+            unrecovered_class_entries: Arc::new(Mutex::new(Vec::new())),
+            mod_identities: Arc::new(Mutex::new(Vec::new())),
+            known_good: Arc::new(Mutex::new(Vec::new())),
             exclude_patterns,
             find_patterns,
+            malware_signatures: Arc::new(malware_signatures),
+            clamav: Arc::new(clamav),
+            yara: Arc::new(yara),
+            allowlist: Arc::new(allowlist),
             result_cache: Arc::new(
                 Cache::builder()
                     .max_capacity(SYSTEM_CONFIG.result_cache_size as u64)
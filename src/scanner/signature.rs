@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+use std::sync::Arc;
+
+use zip::ZipArchive;
+
+use crate::jar_signature::{
+    is_signature_file, signature_block_candidates, verify_jar_signatures, SignatureFile,
+};
+use crate::scanner::jar::ArchiveBudget;
+use crate::scanner::manifest::{read_entry, read_manifest_bytes};
+use crate::scanner::scan::CollapseFindOBFScanner;
+use crate::types::FindingType;
+
+const MAX_SIGNATURE_FILE_SIZE: u64 = 16 * 1024 * 1024;
+const MAX_LISTED_ENTRIES: usize = 10;
+
+impl CollapseFindOBFScanner {
+    /// JAR-level findings from `META-INF/*.SF` signatures: signer details,
+    /// plus anything changed, removed or added after signing. Digests are
+    /// taken over the `entries` already read; signed entries the scan left
+    /// out are read under `budget`.
+    pub(super) fn signature_findings<R: Read + Seek>(
+        &self,
+        archive: &mut ZipArchive<R>,
+        entries: &[(String, Arc<Vec<u8>>)],
+        prefix: &str,
+        budget: &mut ArchiveBudget,
+    ) -> Vec<(FindingType, String)> {
+        let entry_names: Vec<String> = (0..archive.len())
+            .filter_map(|i| archive.name_for_index(i).map(str::to_string))
+            .collect();
+        let sf_paths: Vec<&String> = entry_names.iter().filter(|n| is_signature_file(n)).collect();
+        if sf_paths.is_empty() {
+            return Vec::new();
+        }
+
+        let mut findings = Vec::new();
+        let Some(manifest) = read_manifest_bytes(archive) else {
+            findings.push((
+                FindingType::SignatureMismatch,
+                "JAR has signature files but no readable manifest".to_string(),
+            ));
+            return findings;
+        };
+
+        let signature_files: Vec<SignatureFile> = sf_paths
+            .into_iter()
+            .filter_map(|path| {
+                let data = read_entry(archive, archive.index_for_name(path)?, MAX_SIGNATURE_FILE_SIZE)?;
+                let block = signature_block_candidates(path).into_iter().find_map(|block_path| {
+                    let index = entry_names
+                        .iter()
+                        .position(|n| n.eq_ignore_ascii_case(&block_path))?;
+                    let block = read_entry(archive, index, MAX_SIGNATURE_FILE_SIZE)?;
+                    Some((entry_names[index].clone(), block))
+                });
+                Some(SignatureFile {
+                    path: path.clone(),
+                    data,
+                    block,
+                })
+            })
+            .collect();
+
+        let read: HashMap<&str, &[u8]> = entries
+            .iter()
+            .filter_map(|(name, data)| Some((name.strip_prefix(prefix)?, data.as_slice())))
+            .collect();
+        let mut unread: Vec<(String, String)> = Vec::new();
+        let report = verify_jar_signatures(&manifest, &signature_files, &entry_names, |name, algorithm| {
+            if let Some(data) = read.get(name) {
+                return Some(algorithm.digest(data));
+            }
+            match budget.read_entry(archive, name) {
+                Ok(data) => Some(algorithm.digest(&data)),
+                Err(reason) => {
+                    unread.push((name.to_string(), reason));
+                    None
+                }
+            }
+        });
+        for (name, reason) in unread {
+            budget.record_limit_hit(format!(
+                "Signed entry {}{} {}; its digest was not verified",
+                prefix, name, reason
+            ));
+        }
+
+        findings.extend(
+            report
+                .signers
+                .into_iter()
+                .map(|signer| (FindingType::SignedJar, signer)),
+        );
+        findings.extend(
+            report
+                .invalid
+                .into_iter()
+                .map(|problem| (FindingType::SignatureMismatch, problem)),
+        );
+        push_entry_list(
+            &mut findings,
+            &report.modified,
+            "was modified after signing",
+            "modified after signing",
+        );
+        push_entry_list(
+            &mut findings,
+            &report.missing,
+            "is signed but missing from the JAR",
+            "signed but missing from the JAR",
+        );
+        if !report.unsigned.is_empty() {
+            let mut listed: Vec<&str> = report
+                .unsigned
+                .iter()
+                .take(MAX_LISTED_ENTRIES)
+                .map(String::as_str)
+                .collect();
+            let more = report.unsigned.len() - listed.len();
+            let more_note = format!("{} more", more);
+            if more > 0 {
+                listed.push(&more_note);
+            }
+            findings.push((
+                FindingType::SignatureMismatch,
+                format!(
+                    "{} entr{} not covered by the signature: {}",
+                    report.unsigned.len(),
+                    if report.unsigned.len() == 1 { "y" } else { "ies" },
+                    listed.join(", ")
+                ),
+            ));
+        }
+        findings
+    }
+}
+
+fn push_entry_list(
+    findings: &mut Vec<(FindingType, String)>,
+    entries: &[String],
+    what: &str,
+    summary: &str,
+) {
+    for entry in entries.iter().take(MAX_LISTED_ENTRIES) {
+        findings.push((FindingType::SignatureMismatch, format!("'{}' {}", entry, what)));
+    }
+    if entries.len() > MAX_LISTED_ENTRIES {
+        findings.push((
+            FindingType::SignatureMismatch,
+            format!("{} more entries {}", entries.len() - MAX_LISTED_ENTRIES, summary),
+        ));
+    }
+}
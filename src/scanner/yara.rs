@@ -0,0 +1,110 @@
+use crate::scanner::scan::CollapseFindOBFScanner;
+use crate::types::{ClassDetails, FindingType};
+use crate::utils::truncate_string;
+use crate::yara::YaraMatch;
+
+/// Offsets listed per string before the rest are summarised.
+const MAX_OFFSETS_SHOWN: usize = 5;
+
+impl CollapseFindOBFScanner {
+    pub(crate) fn yara_entry_findings(
+        &self,
+        subject: &str,
+        data: &[u8],
+    ) -> Vec<(FindingType, String)> {
+        if self.yara.is_empty() {
+            return Vec::new();
+        }
+        self.yara
+            .scan(data)
+            .iter()
+            .map(|m| {
+                let strings: Vec<String> = m
+                    .strings
+                    .iter()
+                    .map(|(id, offsets)| {
+                        let mut shown: Vec<String> = offsets
+                            .iter()
+                            .take(MAX_OFFSETS_SHOWN)
+                            .map(|o| format!("{:#x}", o))
+                            .collect();
+                        if offsets.len() > MAX_OFFSETS_SHOWN {
+                            shown.push(format!("{} more", offsets.len() - MAX_OFFSETS_SHOWN));
+                        }
+                        format!("{} at {}", id, shown.join(", "))
+                    })
+                    .collect();
+                yara_finding(subject, m, &strings)
+            })
+            .collect()
+    }
+
+    /// Runs the rules over the class file and, for rules that miss it, over
+    /// the string pool with decrypted strings, which the raw bytes hide.
+    /// Class findings are cached by content, so they name the class rather
+    /// than the entry it was read from.
+    pub(crate) fn check_yara_rules(
+        &self,
+        data: &[u8],
+        details: &ClassDetails,
+        findings: &mut Vec<(FindingType, String)>,
+    ) {
+        if self.yara.is_empty() {
+            return;
+        }
+        let entry_findings =
+            self.yara_entry_findings(&format!("{} class file", details.class_name), data);
+
+        let mut pool: Vec<&str> = details
+            .strings
+            .iter()
+            .map(String::as_str)
+            .chain(details.recovered_strings.iter().map(|r| r.value.as_str()))
+            .collect();
+        // The constant pool strings come from a set; sorting keeps reports stable.
+        pool.sort_unstable();
+        let mut starts = Vec::with_capacity(pool.len());
+        let mut buffer = Vec::new();
+        for string in &pool {
+            starts.push(buffer.len());
+            buffer.extend_from_slice(string.as_bytes());
+            buffer.push(b'\n');
+        }
+
+        for m in self.yara.scan(&buffer) {
+            if entry_findings
+                .iter()
+                .any(|(t, _)| matches!(t, FindingType::YaraMatch { rule } if rule == m.rule))
+            {
+                continue;
+            }
+            let strings: Vec<String> = m
+                .strings
+                .iter()
+                .map(|(id, offsets)| {
+                    let index = starts.partition_point(|&start| start <= offsets[0]) - 1;
+                    format!("{} in \"{}\"", id, truncate_string(pool[index], 60))
+                })
+                .collect();
+            findings.push(yara_finding(
+                &format!("{} string pool", details.class_name),
+                &m,
+                &strings,
+            ));
+        }
+        findings.extend(entry_findings);
+    }
+}
+
+fn yara_finding(subject: &str, yara_match: &YaraMatch, strings: &[String]) -> (FindingType, String) {
+    let mut message = format!("{} matches {}", subject, yara_match.rule);
+    if !strings.is_empty() {
+        message.push_str(&format!(": {}", strings.join("; ")));
+    }
+    (
+        FindingType::YaraMatch {
+            rule: yara_match.rule.to_string(),
+        },
+        message,
+    )
+}
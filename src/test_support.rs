@@ -0,0 +1,158 @@
+//! Builders for the class files and archives unit tests scan.
+
+use std::io::{Cursor, Write};
+
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+/// Assembles a class file around a constant pool.
+pub struct ClassBuilder {
+    pool: Vec<Vec<u8>>,
+    utf8: Vec<(String, u16)>,
+    this_class: u16,
+    super_class: u16,
+    methods: Vec<Vec<u8>>,
+    attributes: Vec<Vec<u8>>,
+}
+
+impl ClassBuilder {
+    pub fn new(name: &str) -> Self {
+        let mut builder = Self {
+            pool: Vec::new(),
+            utf8: Vec::new(),
+            this_class: 0,
+            super_class: 0,
+            methods: Vec::new(),
+            attributes: Vec::new(),
+        };
+        builder.this_class = builder.class(name);
+        builder.super_class = builder.class("java/lang/Object");
+        builder
+    }
+
+    fn push(&mut self, entry: Vec<u8>) -> u16 {
+        self.pool.push(entry);
+        self.pool.len() as u16
+    }
+
+    pub fn utf8(&mut self, value: &str) -> u16 {
+        if let Some((_, index)) = self.utf8.iter().find(|(v, _)| v == value) {
+            return *index;
+        }
+        let mut entry = vec![1];
+        entry.extend((value.len() as u16).to_be_bytes());
+        entry.extend(value.as_bytes());
+        let index = self.push(entry);
+        self.utf8.push((value.to_string(), index));
+        index
+    }
+
+    pub fn class(&mut self, name: &str) -> u16 {
+        let name = self.utf8(name);
+        self.push([&[7][..], &name.to_be_bytes()].concat())
+    }
+
+    pub fn string(&mut self, value: &str) -> u16 {
+        let value = self.utf8(value);
+        self.push([&[8][..], &value.to_be_bytes()].concat())
+    }
+
+    pub fn method_ref(&mut self, owner: &str, name: &str, descriptor: &str) -> u16 {
+        let owner = self.class(owner);
+        let name = self.utf8(name);
+        let descriptor = self.utf8(descriptor);
+        let name_and_type =
+            self.push([&[12][..], &name.to_be_bytes(), &descriptor.to_be_bytes()].concat());
+        self.push([&[10][..], &owner.to_be_bytes(), &name_and_type.to_be_bytes()].concat())
+    }
+
+    fn attribute(&mut self, name: &str, body: &[u8]) -> Vec<u8> {
+        let mut attribute = self.utf8(name).to_be_bytes().to_vec();
+        attribute.extend((body.len() as u32).to_be_bytes());
+        attribute.extend(body);
+        attribute
+    }
+
+    /// Adds a method running `code`, with an empty table for each of
+    /// `debug_tables` in its `Code` attribute.
+    pub fn method(
+        &mut self,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+        code: &[u8],
+        debug_tables: &[&str],
+    ) -> &mut Self {
+        let mut body = vec![0, 16, 0, 16];
+        body.extend((code.len() as u32).to_be_bytes());
+        body.extend(code);
+        body.extend(0u16.to_be_bytes());
+        body.extend((debug_tables.len() as u16).to_be_bytes());
+        for table in debug_tables {
+            let table = self.attribute(table, &[0, 0]);
+            body.extend(table);
+        }
+
+        let mut method = access_flags.to_be_bytes().to_vec();
+        method.extend(self.utf8(name).to_be_bytes());
+        method.extend(self.utf8(descriptor).to_be_bytes());
+        method.extend(1u16.to_be_bytes());
+        method.extend(self.attribute("Code", &body));
+        self.methods.push(method);
+        self
+    }
+
+    pub fn source_file(&mut self, file: &str) -> &mut Self {
+        let file = self.utf8(file);
+        let attribute = self.attribute("SourceFile", &file.to_be_bytes());
+        self.attributes.push(attribute);
+        self
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let mut out = vec![0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 52];
+        out.extend((self.pool.len() as u16 + 1).to_be_bytes());
+        for entry in &self.pool {
+            out.extend(entry);
+        }
+        out.extend(0x0021u16.to_be_bytes());
+        out.extend(self.this_class.to_be_bytes());
+        out.extend(self.super_class.to_be_bytes());
+        out.extend(0u16.to_be_bytes());
+        out.extend(0u16.to_be_bytes());
+        out.extend((self.methods.len() as u16).to_be_bytes());
+        for method in &self.methods {
+            out.extend(method);
+        }
+        out.extend((self.attributes.len() as u16).to_be_bytes());
+        for attribute in &self.attributes {
+            out.extend(attribute);
+        }
+        out
+    }
+}
+
+/// Zips `entries` in order with deflate compression.
+pub fn jar(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in entries {
+        writer
+            .start_file(*name, SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(data).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+/// Deterministic filler that parses as nothing in particular.
+pub fn junk(length: usize) -> Vec<u8> {
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    (0..length)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 24) as u8
+        })
+        .collect()
+}
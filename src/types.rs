@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
+use crate::annotations::AnnotationRule;
+
 
 mod arc_matches_serde {
     use super::FindingType;
@@ -33,6 +35,9 @@ pub struct ScanResult {
     pub resource_info: Option<ResourceInfo>,
     pub danger_score: u8,
     pub danger_explanation: Vec<String>,
+    /// Set when a resource limit stopped part of the scan.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub partial_scan: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -69,6 +74,30 @@ pub enum FindingType {
     ObfuscationUnicode,
     ObfuscationRandomName,
     ObfuscationString,
+    ObfuscationControlFlow,
+    DynamicClassLoading,
+    Reflection,
+    NativeCode,
+    DisguisedFile,
+    EncryptedClass,
+    ArchiveAnomaly,
+    ResourceLimit,
+    JavaAgent,
+    ManifestAnomaly,
+    SignatureMismatch,
+    SignedJar,
+    ModMetadata,
+    SensitiveMixin,
+    AnnotationMatch,
+    KotlinMetadata,
+    DebugInfo,
+    InjectedClass,
+    /// A signature from the malware pack matched.
+    KnownMalware { family: String, variant: String },
+    /// A user-supplied ClamAV hash or body signature matched.
+    ClamAvMatch { signature: String },
+    /// A user-supplied YARA rule matched.
+    YaraMatch { rule: String },
 }
 
 impl std::fmt::Display for FindingType {
@@ -78,6 +107,29 @@ impl std::fmt::Display for FindingType {
             FindingType::ObfuscationUnicode => write!(f, "Obfuscation (Unicode)"),
             FindingType::ObfuscationRandomName => write!(f, "Obfuscation (Random Name)"),
             FindingType::ObfuscationString => write!(f, "Obfuscation (String)"),
+            FindingType::ObfuscationControlFlow => write!(f, "Obfuscation (Control Flow)"),
+            FindingType::DynamicClassLoading => write!(f, "Dynamic Class Loading"),
+            FindingType::Reflection => write!(f, "Reflection"),
+            FindingType::NativeCode => write!(f, "Native Code"),
+            FindingType::DisguisedFile => write!(f, "Disguised File"),
+            FindingType::EncryptedClass => write!(f, "Encrypted Class"),
+            FindingType::ArchiveAnomaly => write!(f, "Archive Anomaly"),
+            FindingType::ResourceLimit => write!(f, "Resource Limit"),
+            FindingType::JavaAgent => write!(f, "Java Agent"),
+            FindingType::ManifestAnomaly => write!(f, "Manifest"),
+            FindingType::SignatureMismatch => write!(f, "Signature Mismatch"),
+            FindingType::SignedJar => write!(f, "Signed JAR"),
+            FindingType::ModMetadata => write!(f, "Mod Metadata"),
+            FindingType::SensitiveMixin => write!(f, "Sensitive Mixin"),
+            FindingType::AnnotationMatch => write!(f, "Annotation Rule"),
+            FindingType::KotlinMetadata => write!(f, "Kotlin Metadata"),
+            FindingType::DebugInfo => write!(f, "Debug Info"),
+            FindingType::InjectedClass => write!(f, "Injected Class"),
+            FindingType::KnownMalware { family, variant } => {
+                write!(f, "Known Malware ({} {})", family, variant)
+            }
+            FindingType::ClamAvMatch { signature } => write!(f, "ClamAV ({})", signature),
+            FindingType::YaraMatch { rule } => write!(f, "YARA ({})", rule),
         }
     }
 }
@@ -90,6 +142,27 @@ impl FindingType {
             FindingType::ObfuscationUnicode => ("㊙️", "magenta"),
             FindingType::ObfuscationRandomName => ("🔀", "magenta"),
             FindingType::ObfuscationString => ("📝", "magenta"),
+            FindingType::ObfuscationControlFlow => ("🌀", "magenta"),
+            FindingType::DynamicClassLoading => ("🧬", "red"),
+            FindingType::Reflection => ("🪞", "yellow"),
+            FindingType::NativeCode => ("⚙️", "yellow"),
+            FindingType::DisguisedFile => ("🎭", "red"),
+            FindingType::EncryptedClass => ("🔐", "red"),
+            FindingType::ArchiveAnomaly => ("🧩", "yellow"),
+            FindingType::ResourceLimit => ("🧨", "red"),
+            FindingType::JavaAgent => ("🕵️", "red"),
+            FindingType::ManifestAnomaly => ("📜", "yellow"),
+            FindingType::SignatureMismatch => ("✍️", "red"),
+            FindingType::SignedJar => ("🔏", "blue"),
+            FindingType::ModMetadata => ("📦", "yellow"),
+            FindingType::SensitiveMixin => ("🪝", "red"),
+            FindingType::AnnotationMatch => ("🏷️", "cyan"),
+            FindingType::KotlinMetadata => ("🧾", "magenta"),
+            FindingType::DebugInfo => ("🐞", "magenta"),
+            FindingType::InjectedClass => ("💉", "red"),
+            FindingType::KnownMalware { .. } => ("☣️", "red"),
+            FindingType::ClamAvMatch { .. } => ("🦠", "red"),
+            FindingType::YaraMatch { .. } => ("🔬", "red"),
         }
     }
 }
@@ -101,6 +174,27 @@ impl FindingType {
             FindingType::ObfuscationUnicode => 1,
             FindingType::ObfuscationRandomName => 4,
             FindingType::ObfuscationString => 1,
+            FindingType::ObfuscationControlFlow => 3,
+            FindingType::DynamicClassLoading => 3,
+            FindingType::Reflection => 1,
+            FindingType::NativeCode => 2,
+            FindingType::DisguisedFile => 4,
+            FindingType::EncryptedClass => 4,
+            FindingType::ArchiveAnomaly => 2,
+            FindingType::ResourceLimit => 4,
+            FindingType::JavaAgent => 4,
+            FindingType::ManifestAnomaly => 2,
+            FindingType::SignatureMismatch => 5,
+            FindingType::SignedJar => 0,
+            FindingType::ModMetadata => 1,
+            FindingType::SensitiveMixin => 4,
+            FindingType::AnnotationMatch => 4,
+            FindingType::KotlinMetadata => 3,
+            FindingType::DebugInfo => 2,
+            FindingType::InjectedClass => 4,
+            FindingType::KnownMalware { .. } => 10,
+            FindingType::ClamAvMatch { .. } => 8,
+            FindingType::YaraMatch { .. } => 6,
         }
     }
 
@@ -110,6 +204,28 @@ impl FindingType {
             FindingType::ObfuscationUnicode => 4,
             FindingType::ObfuscationRandomName => 5,
             FindingType::ObfuscationString => 3,
+            FindingType::ObfuscationControlFlow => 6,
+            FindingType::DynamicClassLoading => 6,
+            FindingType::Reflection => 2,
+            FindingType::NativeCode => 6,
+            FindingType::DisguisedFile => 8,
+            FindingType::EncryptedClass => 8,
+            FindingType::ArchiveAnomaly => 8,
+            FindingType::ResourceLimit => 8,
+            FindingType::JavaAgent => 8,
+            FindingType::ManifestAnomaly => 6,
+            FindingType::SignatureMismatch => 10,
+            FindingType::SignedJar => 0,
+            // Stays under the report threshold unless other findings join it.
+            FindingType::ModMetadata => 3,
+            FindingType::SensitiveMixin => 8,
+            FindingType::AnnotationMatch => 8,
+            FindingType::KotlinMetadata => 6,
+            FindingType::DebugInfo => 4,
+            FindingType::InjectedClass => 8,
+            FindingType::KnownMalware { .. } => 10,
+            FindingType::ClamAvMatch { .. } => 10,
+            FindingType::YaraMatch { .. } => 10,
         }
     }
 }
@@ -117,12 +233,26 @@ impl FindingType {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClassDetails {
     pub class_name: String,
+    /// Class file major version; Java N compiles to N + 44.
+    #[serde(default)]
+    pub major_version: u16,
     pub superclass_name: String,
     pub interfaces: Vec<String>,
     pub methods: Vec<MethodInfo>,
     pub fields: Vec<FieldInfo>,
     pub strings: Vec<String>,
     pub access_flags: u16,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recovered_strings: Vec<RecoveredString>,
+    /// Class-level annotations, visible and invisible.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+    #[serde(default)]
+    pub debug_info: DebugInfo,
+    #[serde(skip)]
+    pub constant_pool: Vec<ConstantPoolEntry>,
+    #[serde(skip)]
+    pub bootstrap_methods: Vec<BootstrapMethod>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -130,6 +260,237 @@ pub struct MethodInfo {
     pub name: String,
     pub descriptor: String,
     pub access_flags: u16,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+    /// One list per parameter, from the `Runtime*ParameterAnnotations` attributes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parameter_annotations: Vec<Vec<Annotation>>,
+    #[serde(skip)]
+    pub code: Option<CodeAttribute>,
+}
+
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct CodeAttribute {
+    pub max_stack: u16,
+    pub max_locals: u16,
+    pub code: Vec<u8>,
+    pub exception_table: Vec<ExceptionTableEntry>,
+    pub has_line_numbers: bool,
+    pub has_local_variables: bool,
+}
+
+/// What is left of the debug attributes javac emits by default.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DebugInfo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_file: Option<String>,
+    pub methods_with_code: usize,
+    pub methods_with_line_numbers: usize,
+    pub methods_with_local_variables: usize,
+}
+
+impl DebugInfo {
+    pub fn has_line_numbers(&self) -> bool {
+        self.methods_with_line_numbers > 0
+    }
+
+    /// e.g. `SourceFile Foo.java, line numbers 4/5 methods, local variables 0/5`.
+    #[cfg(all(feature = "cli", not(feature = "gui")))]
+    pub fn summary(&self) -> String {
+        format!(
+            "SourceFile {}, line numbers {}/{} methods, local variables {}/{}",
+            self.source_file.as_deref().unwrap_or("missing"),
+            self.methods_with_line_numbers,
+            self.methods_with_code,
+            self.methods_with_local_variables,
+            self.methods_with_code
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct ExceptionTableEntry {
+    pub start_pc: u16,
+    pub end_pc: u16,
+    pub handler_pc: u16,
+    pub catch_type: u16,
+}
+
+#[derive(Debug, Clone)]
+pub struct BootstrapMethod {
+    pub method_ref: u16,
+    pub arguments: Vec<u16>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Annotation {
+    /// Field descriptor of the annotation type, e.g. `Lorg/spongepowered/asm/mixin/Mixin;`.
+    pub type_descriptor: String,
+    /// `false` for `RuntimeInvisibleAnnotations` (CLASS retention).
+    pub visible: bool,
+    pub elements: Vec<(String, ElementValue)>,
+}
+
+impl Annotation {
+    /// Internal name of the annotation type, e.g. `org/spongepowered/asm/mixin/Mixin`.
+    pub fn type_name(&self) -> &str {
+        self.type_descriptor
+            .strip_prefix('L')
+            .and_then(|d| d.strip_suffix(';'))
+            .unwrap_or(&self.type_descriptor)
+    }
+
+    pub fn element(&self, name: &str) -> Option<&ElementValue> {
+        self.elements
+            .iter()
+            .find(|(element_name, _)| element_name == name)
+            .map(|(_, value)| value)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ElementValue {
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Boolean(bool),
+    Char(char),
+    String(String),
+    Enum {
+        type_descriptor: String,
+        name: String,
+    },
+    /// A class literal, as a return descriptor such as `Lcom/example/Foo;` or `V`.
+    Class(String),
+    Annotation(Box<Annotation>),
+    Array(Vec<ElementValue>),
+}
+
+/// Source-like form, e.g. `@org.spongepowered.asm.mixin.Mixin(value={net.minecraft.Foo.class})`.
+impl std::fmt::Display for Annotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "@{}", self.type_name().replace('/', "."))?;
+        if self.elements.is_empty() {
+            return Ok(());
+        }
+        let elements: Vec<String> = self
+            .elements
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        write!(f, "({})", elements.join(", "))
+    }
+}
+
+impl std::fmt::Display for ElementValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ElementValue::Int(v) => write!(f, "{}", v),
+            ElementValue::Long(v) => write!(f, "{}L", v),
+            ElementValue::Float(v) => write!(f, "{}f", v),
+            ElementValue::Double(v) => write!(f, "{}", v),
+            ElementValue::Boolean(v) => write!(f, "{}", v),
+            ElementValue::Char(v) => write!(f, "{:?}", v),
+            ElementValue::String(v) => write!(f, "{:?}", v),
+            ElementValue::Enum { .. } => {
+                write!(f, "{}", self.text().unwrap_or_default().replace('/', "."))
+            }
+            ElementValue::Class(descriptor) => {
+                let name = match descriptor.as_str() {
+                    "B" => "byte".to_string(),
+                    "C" => "char".to_string(),
+                    "D" => "double".to_string(),
+                    "F" => "float".to_string(),
+                    "I" => "int".to_string(),
+                    "J" => "long".to_string(),
+                    "S" => "short".to_string(),
+                    "Z" => "boolean".to_string(),
+                    "V" => "void".to_string(),
+                    _ => self.text().unwrap_or_default().replace('/', "."),
+                };
+                write!(f, "{}.class", name)
+            }
+            ElementValue::Annotation(annotation) => write!(f, "{}", annotation),
+            ElementValue::Array(values) => {
+                let values: Vec<String> = values.iter().map(ToString::to_string).collect();
+                write!(f, "{{{}}}", values.join(", "))
+            }
+        }
+    }
+}
+
+impl ElementValue {
+    /// The bare value used for rule matching: strings unquoted, class
+    /// literals as internal names, enums as `Type.CONSTANT`. `None` for
+    /// arrays and nested annotations.
+    pub fn text(&self) -> Option<String> {
+        Some(match self {
+            ElementValue::String(v) => v.clone(),
+            ElementValue::Class(descriptor) => descriptor
+                .strip_prefix('L')
+                .and_then(|d| d.strip_suffix(';'))
+                .unwrap_or(descriptor)
+                .to_string(),
+            ElementValue::Enum {
+                type_descriptor,
+                name,
+            } => {
+                let type_name = type_descriptor
+                    .strip_prefix('L')
+                    .and_then(|d| d.strip_suffix(';'))
+                    .unwrap_or(type_descriptor);
+                format!("{}.{}", type_name, name)
+            }
+            ElementValue::Char(v) => v.to_string(),
+            ElementValue::Annotation(_) | ElementValue::Array(_) => return None,
+            other => other.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveredString {
+    pub value: String,
+    pub method: String,
+    pub source: StringSource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub helper: Option<String>,
+}
+
+impl RecoveredString {
+    pub fn provenance(&self) -> String {
+        match (&self.source, &self.helper) {
+            (StringSource::Emulated, Some(helper)) => {
+                format!("decrypted via {} in {}", helper, self.method)
+            }
+            (StringSource::Emulated, None) => format!("decrypted by emulating {}", self.method),
+            (source, _) => format!("rebuilt from {} in {}", source, self.method),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum StringSource {
+    StringBuilder,
+    IndyConcat,
+    CharArray,
+    ByteArray,
+    Emulated,
+}
+
+impl std::fmt::Display for StringSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StringSource::StringBuilder => write!(f, "StringBuilder chain"),
+            StringSource::IndyConcat => write!(f, "indy string concat"),
+            StringSource::CharArray => write!(f, "char array"),
+            StringSource::ByteArray => write!(f, "byte array"),
+            StringSource::Emulated => write!(f, "emulated decrypt call"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -137,6 +498,8 @@ pub struct FieldInfo {
     pub name: String,
     pub descriptor: String,
     pub access_flags: u16,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -169,6 +532,15 @@ pub struct ScannerOptions {
     pub ignore_keywords_file: Option<PathBuf>,
     pub exclude_patterns: Vec<String>,
     pub find_patterns: Vec<String>,
+    pub annotation_rules: Vec<AnnotationRule>,
+    /// ClamAV `.hdb`/`.hsb`/`.ndb` files, or directories holding them.
+    pub clamav_databases: Vec<PathBuf>,
+    /// YARA rule files, or directories holding them.
+    pub yara_rules: Vec<PathBuf>,
+    /// Known-good digest lists built with `build-allowlist`.
+    pub allowlists: Vec<PathBuf>,
+    /// Keep every parsed class, reported or not, for `--inspect`.
+    pub inspect: bool,
     pub progress: Option<Arc<Mutex<Progress>>>,
 }
 
@@ -180,6 +552,11 @@ impl Default for ScannerOptions {
             ignore_keywords_file: None,
             exclude_patterns: Vec::new(),
             find_patterns: Vec::new(),
+            annotation_rules: Vec::new(),
+            clamav_databases: Vec::new(),
+            yara_rules: Vec::new(),
+            allowlists: Vec::new(),
+            inspect: false,
             progress: None,
         }
     }
@@ -189,21 +566,21 @@ impl Default for ScannerOptions {
 #[allow(dead_code)]
 pub enum ConstantPoolEntry {
     Utf8(String),
-    Integer,
-    Float,
-    Long,
-    Double,
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
     Class(u16),
     String(u16),
     Fieldref(u16, u16),
     Methodref(u16, u16),
     InterfaceMethodref(u16, u16),
     NameAndType(u16, u16),
-    MethodHandle,
-    MethodType,
-    Dynamic,
-    InvokeDynamic,
-    Module,
-    Package,
+    MethodHandle(u8, u16),
+    MethodType(u16),
+    Dynamic(u16, u16),
+    InvokeDynamic(u16, u16),
+    Module(u16),
+    Package(u16),
     Placeholder,
 }
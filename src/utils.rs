@@ -1,5 +1,31 @@
 use url::Url;
 
+/// Decodes standard or URL-safe Base64, ignoring line breaks.
+pub fn decode_base64(input: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(input.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for &c in input {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            b'\r' | b'\n' => continue,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
 pub fn truncate_string(s: &str, max_len: usize) -> String {
     if s.chars().count() <= max_len {
         s.to_string()
@@ -17,10 +43,10 @@ pub fn extract_domain(url_str: &str) -> String {
             .map(|host| host.trim_start_matches("www.").to_lowercase())
     };
 
-    if let Ok(url) = Url::parse(url_str) {
-        if let Some(host) = get_host(url) {
-            return host;
-        }
+    if let Ok(url) = Url::parse(url_str)
+        && let Some(host) = get_host(url)
+    {
+        return host;
     }
 
     if (!url_str.contains("://") && url_str.contains('.')) || url_str.starts_with("//") {
@@ -31,10 +57,10 @@ pub fn extract_domain(url_str: &str) -> String {
         };
         let url_with_scheme = format!("{}{}", prefix, url_str);
 
-        if let Ok(url) = Url::parse(&url_with_scheme) {
-            if let Some(host) = get_host(url) {
-                return host;
-            }
+        if let Ok(url) = Url::parse(&url_with_scheme)
+            && let Some(host) = get_host(url)
+        {
+            return host;
         }
     }
 
@@ -47,3 +73,15 @@ pub fn get_simple_name(fqn: &str) -> &str {
 
     name_part.rsplit(['/', '.']).next().unwrap()
 }
+
+/// Lowercase hex SHA-256, the form digests take in signature packs.
+pub fn sha256_hex(data: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    hex::encode(Sha256::digest(data))
+}
+
+/// Lowercase hex MD5. Only ClamAV `.hdb` databases still need it.
+pub fn md5_hex(data: &[u8]) -> String {
+    use md5::{Digest, Md5};
+    hex::encode(Md5::digest(data))
+}
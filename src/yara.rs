@@ -0,0 +1,842 @@
+//! A practical subset of YARA for matching decompressed entries. Supported:
+//! text strings with `nocase`, `wide`, `ascii` and `fullword`; hex strings
+//! with `??`, nibble wildcards, jumps, alternatives and `~` negation;
+//! `/regex/is` strings; conditions with `and`/`or`/`not`, parentheses,
+//! `$a`, `$a at N`, `$a in (A..B)`, `#a` counts, `filesize` and
+//! `any`/`all`/`none`/`N of them` or of a `($a, $b*)` set. Modules,
+//! `for` loops and external variables are not.
+
+use std::path::{Path, PathBuf};
+
+use regex::bytes::{Regex, RegexBuilder};
+
+use crate::errors::ScanError;
+
+const RULE_EXTENSIONS: &[&str] = &["yar", "yara"];
+/// Offsets kept per string; counts saturate here too.
+const MAX_MATCHES_PER_STRING: usize = 1000;
+/// Matches tried per string, bounding the search when `fullword` rejects
+/// most of them.
+const MAX_CANDIDATES_PER_STRING: usize = 10 * MAX_MATCHES_PER_STRING;
+const REGEX_SIZE_LIMIT: usize = 8 * 1024 * 1024;
+
+#[derive(Debug)]
+struct YaraString {
+    id: String,
+    pattern: Regex,
+    fullword: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    fn holds(self, left: u64, right: u64) -> bool {
+        match self {
+            Comparison::Eq => left == right,
+            Comparison::Ne => left != right,
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Gt => left > right,
+            Comparison::Ge => left >= right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Quantifier {
+    Any,
+    All,
+    None,
+    AtLeast(usize),
+}
+
+#[derive(Debug)]
+enum Condition {
+    Bool(bool),
+    Not(Box<Condition>),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Present(usize),
+    At(usize, u64),
+    In(usize, u64, u64),
+    Count(usize, Comparison, u64),
+    FileSize(Comparison, u64),
+    Of(Quantifier, Vec<usize>),
+}
+
+impl Condition {
+    fn holds(&self, offsets: &[Vec<usize>], file_size: u64) -> bool {
+        match self {
+            Condition::Bool(value) => *value,
+            Condition::Not(inner) => !inner.holds(offsets, file_size),
+            Condition::And(parts) => parts.iter().all(|c| c.holds(offsets, file_size)),
+            Condition::Or(parts) => parts.iter().any(|c| c.holds(offsets, file_size)),
+            Condition::Present(index) => !offsets[*index].is_empty(),
+            Condition::At(index, offset) => offsets[*index].contains(&(*offset as usize)),
+            Condition::In(index, start, end) => offsets[*index]
+                .iter()
+                .any(|&o| (*start..=*end).contains(&(o as u64))),
+            Condition::Count(index, comparison, value) => {
+                comparison.holds(offsets[*index].len() as u64, *value)
+            }
+            Condition::FileSize(comparison, value) => comparison.holds(file_size, *value),
+            Condition::Of(quantifier, set) => {
+                let matched = set.iter().filter(|&&i| !offsets[i].is_empty()).count();
+                match quantifier {
+                    Quantifier::Any => matched > 0,
+                    Quantifier::All => matched == set.len(),
+                    Quantifier::None => matched == 0,
+                    Quantifier::AtLeast(n) => matched >= *n,
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Rule {
+    name: String,
+    /// `private` rules only exist to be referenced, which this subset
+    /// does not support, so they never report.
+    private: bool,
+    strings: Vec<YaraString>,
+    condition: Condition,
+}
+
+/// A rule that matched, with the offsets of each string that did.
+pub struct YaraMatch<'a> {
+    pub rule: &'a str,
+    pub strings: Vec<(&'a str, Vec<usize>)>,
+}
+
+#[derive(Debug, Default)]
+pub struct YaraRules {
+    rules: Vec<Rule>,
+}
+
+impl YaraRules {
+    /// Loads each file, or every `.yar`/`.yara` in each directory.
+    pub fn load(paths: &[PathBuf]) -> Result<Self, ScanError> {
+        let mut rules = YaraRules::default();
+        for path in paths {
+            if path.is_dir() {
+                let mut files: Vec<PathBuf> = std::fs::read_dir(path)?
+                    .filter_map(|entry| entry.ok().map(|e| e.path()))
+                    .filter(|file| {
+                        file.extension()
+                            .and_then(|e| e.to_str())
+                            .is_some_and(|e| RULE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+                    })
+                    .collect();
+                files.sort();
+                for file in files {
+                    rules.load_file(&file)?;
+                }
+            } else {
+                rules.load_file(path)?;
+            }
+        }
+        Ok(rules)
+    }
+
+    fn load_file(&mut self, path: &Path) -> Result<(), ScanError> {
+        let source = std::fs::read_to_string(path)?;
+        let mut parser = Parser::new(&source);
+        let parsed = parser.rules().map_err(|e| {
+            ScanError::YaraError(format!(
+                "{}:{}: {}",
+                path.display(),
+                parser.line(),
+                e
+            ))
+        })?;
+        self.rules.extend(parsed);
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn scan(&self, data: &[u8]) -> Vec<YaraMatch<'_>> {
+        let mut matches = Vec::new();
+        for rule in self.rules.iter().filter(|r| !r.private) {
+            let offsets: Vec<Vec<usize>> = rule
+                .strings
+                .iter()
+                .map(|string| string_offsets(string, data))
+                .collect();
+            if rule.condition.holds(&offsets, data.len() as u64) {
+                matches.push(YaraMatch {
+                    rule: &rule.name,
+                    strings: rule
+                        .strings
+                        .iter()
+                        .zip(offsets)
+                        .filter(|(_, offsets)| !offsets.is_empty())
+                        .map(|(string, offsets)| (string.id.as_str(), offsets))
+                        .collect(),
+                });
+            }
+        }
+        matches
+    }
+}
+
+/// Offsets of every match, overlapping ones included: `$a = "aa"` occurs
+/// twice in "aaa". `fullword` is applied to each candidate in turn.
+fn string_offsets(string: &YaraString, data: &[u8]) -> Vec<usize> {
+    let is_word = |b: &u8| b.is_ascii_alphanumeric() || *b == b'_';
+    let mut offsets = Vec::new();
+    let mut start = 0;
+    for _ in 0..MAX_CANDIDATES_PER_STRING {
+        if offsets.len() == MAX_MATCHES_PER_STRING || start > data.len() {
+            break;
+        }
+        let Some(m) = string.pattern.find_at(data, start) else {
+            break;
+        };
+        if !string.fullword
+            || (!data[..m.start()].last().is_some_and(is_word)
+                && !data.get(m.end()).is_some_and(is_word))
+        {
+            offsets.push(m.start());
+        }
+        start = m.start() + 1;
+    }
+    offsets
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Self {
+        Parser {
+            source,
+            position: 0,
+        }
+    }
+
+    fn line(&self) -> usize {
+        self.source[..self.position].matches('\n').count() + 1
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.source[self.position..]
+    }
+
+    fn skip_trivia(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.position += rest.len() - trimmed.len();
+            if trimmed.starts_with("//") {
+                self.position += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else if let Some(comment) = trimmed.strip_prefix("/*") {
+                self.position += 2 + comment.find("*/").map_or(comment.len(), |end| end + 2);
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_trivia();
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_trivia();
+        let rest = self.rest();
+        let Some(after) = rest.strip_prefix(token) else {
+            return false;
+        };
+        let is_word = token.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        let boundary = after
+            .chars()
+            .next()
+            .is_none_or(|c| !(c.is_ascii_alphanumeric() || c == '_'));
+        if !is_word || boundary {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}'", token))
+        }
+    }
+
+    fn take_while(&mut self, accept: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let end = rest.find(|c| !accept(c)).unwrap_or(rest.len());
+        self.position += end;
+        &rest[..end]
+    }
+
+    fn identifier(&mut self) -> Result<&'a str, String> {
+        self.skip_trivia();
+        let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err("expected an identifier".to_string());
+        }
+        Ok(name)
+    }
+
+    /// A decimal or `0x` number, with an optional `KB`/`MB` suffix.
+    fn number(&mut self) -> Result<u64, String> {
+        self.skip_trivia();
+        let text = self.take_while(|c| c.is_ascii_alphanumeric());
+        let (digits, multiplier) = if let Some(digits) = text.strip_suffix("KB") {
+            (digits, 1024)
+        } else if let Some(digits) = text.strip_suffix("MB") {
+            (digits, 1024 * 1024)
+        } else {
+            (text, 1)
+        };
+        let value = match digits.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => digits.parse(),
+        }
+        .map_err(|_| format!("expected a number, got '{}'", text))?;
+        Ok(value * multiplier)
+    }
+
+    fn rules(&mut self) -> Result<Vec<Rule>, String> {
+        let mut rules = Vec::new();
+        loop {
+            if self.peek().is_none() {
+                return Ok(rules);
+            }
+            if self.eat("import") || self.eat("include") {
+                self.quoted()?;
+                continue;
+            }
+            let private = self.eat("private");
+            if self.eat("global") {
+                return Err("global rules are not supported".to_string());
+            }
+            self.expect("rule")?;
+            rules.push(self.rule(private)?);
+        }
+    }
+
+    fn rule(&mut self, private: bool) -> Result<Rule, String> {
+        let name = self.identifier()?.to_string();
+        if self.eat(":") {
+            while self.peek() != Some('{') {
+                self.identifier()?;
+            }
+        }
+        self.expect("{")?;
+
+        if self.eat_section("meta") {
+            while !(self.at_section("strings") || self.at_section("condition")) {
+                self.identifier()?;
+                self.expect("=")?;
+                match self.peek() {
+                    Some('"') => {
+                        self.quoted()?;
+                    }
+                    Some('-') => {
+                        self.expect("-")?;
+                        self.number()?;
+                    }
+                    Some(c) if c.is_ascii_digit() => {
+                        self.number()?;
+                    }
+                    _ => {
+                        if !(self.eat("true") || self.eat("false")) {
+                            return Err("expected a meta value".to_string());
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut strings = Vec::new();
+        if self.eat_section("strings") {
+            while !self.eat_section("condition") {
+                strings.push(self.string_definition()?);
+            }
+        } else if !self.eat_section("condition") {
+            return Err("expected 'strings:' or 'condition:'".to_string());
+        }
+
+        let condition = self.or_condition(&strings)?;
+        self.expect("}")?;
+        Ok(Rule {
+            name,
+            private,
+            strings,
+            condition,
+        })
+    }
+
+    /// `strings:` or `condition:`, without consuming anything otherwise.
+    fn eat_section(&mut self, section: &str) -> bool {
+        let start = self.position;
+        if self.eat(section) && self.eat(":") {
+            return true;
+        }
+        self.position = start;
+        false
+    }
+
+    fn at_section(&mut self, section: &str) -> bool {
+        let start = self.position;
+        let found = self.eat_section(section);
+        self.position = start;
+        found
+    }
+
+    fn quoted(&mut self) -> Result<Vec<u8>, String> {
+        self.expect("\"")?;
+        let mut bytes = Vec::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.position += i + 1;
+                    return Ok(bytes);
+                }
+                '\\' => {
+                    let (_, escaped) = chars.next().ok_or("unterminated string")?;
+                    match escaped {
+                        'n' => bytes.push(b'\n'),
+                        't' => bytes.push(b'\t'),
+                        'r' => bytes.push(b'\r'),
+                        '"' | '\\' => bytes.push(escaped as u8),
+                        'x' => {
+                            let hex: String = (0..2).filter_map(|_| chars.next().map(|(_, c)| c)).collect();
+                            let byte = u8::from_str_radix(&hex, 16)
+                                .map_err(|_| format!("bad escape \\x{}", hex))?;
+                            bytes.push(byte);
+                        }
+                        other => return Err(format!("unknown escape \\{}", other)),
+                    }
+                }
+                '\n' => return Err("unterminated string".to_string()),
+                c => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            }
+        }
+        Err("unterminated string".to_string())
+    }
+
+    fn string_definition(&mut self) -> Result<YaraString, String> {
+        self.expect("$")?;
+        let id = format!("${}", self.identifier()?);
+        self.expect("=")?;
+
+        let (mut body, text) = match self.peek() {
+            Some('"') => {
+                let literal = self.quoted()?;
+                (literal_regex(&literal), Some(literal))
+            }
+            Some('{') => {
+                self.expect("{")?;
+                let hex = self.take_while(|c| c != '}');
+                self.expect("}")?;
+                (hex_regex(hex).ok_or_else(|| format!("bad hex string in {}", id))?, None)
+            }
+            Some('/') => {
+                self.position += 1;
+                let mut pattern = String::new();
+                let mut chars = self.rest().char_indices();
+                loop {
+                    let (i, c) = chars.next().ok_or("unterminated regex")?;
+                    match c {
+                        '/' => {
+                            self.position += i + 1;
+                            break;
+                        }
+                        '\\' => {
+                            let (_, next) = chars.next().ok_or("unterminated regex")?;
+                            if next != '/' {
+                                pattern.push('\\');
+                            }
+                            pattern.push(next);
+                        }
+                        c => pattern.push(c),
+                    }
+                }
+                let flags = self.take_while(|c| c == 'i' || c == 's');
+                let flags = if flags.is_empty() {
+                    String::new()
+                } else {
+                    format!("(?{})", flags)
+                };
+                (format!("{}(?:{})", flags, pattern), None)
+            }
+            _ => return Err(format!("expected a string value for {}", id)),
+        };
+
+        let (mut nocase, mut wide, mut ascii, mut fullword) = (false, false, false, false);
+        loop {
+            if self.eat("nocase") {
+                nocase = true;
+            } else if self.eat("wide") {
+                wide = true;
+            } else if self.eat("ascii") {
+                ascii = true;
+            } else if self.eat("fullword") {
+                fullword = true;
+            } else {
+                break;
+            }
+        }
+        if wide {
+            let literal = text.ok_or_else(|| format!("'wide' needs a text string in {}", id))?;
+            let wide_body: String = literal
+                .iter()
+                .map(|b| format!(r"\x{:02x}\x00", b))
+                .collect();
+            body = if ascii {
+                format!("{}|{}", body, wide_body)
+            } else {
+                wide_body
+            };
+        }
+        if nocase {
+            body = format!("(?i:{})", body);
+        }
+
+        let pattern = RegexBuilder::new(&format!("(?s:{})", body))
+            .unicode(false)
+            .size_limit(REGEX_SIZE_LIMIT)
+            .build()
+            .map_err(|e| format!("{}: {}", id, e))?;
+        Ok(YaraString {
+            id,
+            pattern,
+            fullword,
+        })
+    }
+
+    fn or_condition(&mut self, strings: &[YaraString]) -> Result<Condition, String> {
+        let mut parts = vec![self.and_condition(strings)?];
+        while self.eat("or") {
+            parts.push(self.and_condition(strings)?);
+        }
+        Ok(if parts.len() == 1 {
+            parts.remove(0)
+        } else {
+            Condition::Or(parts)
+        })
+    }
+
+    fn and_condition(&mut self, strings: &[YaraString]) -> Result<Condition, String> {
+        let mut parts = vec![self.unary_condition(strings)?];
+        while self.eat("and") {
+            parts.push(self.unary_condition(strings)?);
+        }
+        Ok(if parts.len() == 1 {
+            parts.remove(0)
+        } else {
+            Condition::And(parts)
+        })
+    }
+
+    fn unary_condition(&mut self, strings: &[YaraString]) -> Result<Condition, String> {
+        if self.eat("not") {
+            return Ok(Condition::Not(Box::new(self.unary_condition(strings)?)));
+        }
+        if self.eat("(") {
+            let inner = self.or_condition(strings)?;
+            self.expect(")")?;
+            return Ok(inner);
+        }
+        if self.eat("true") {
+            return Ok(Condition::Bool(true));
+        }
+        if self.eat("false") {
+            return Ok(Condition::Bool(false));
+        }
+        if self.eat("filesize") {
+            let comparison = self.comparison()?;
+            return Ok(Condition::FileSize(comparison, self.number()?));
+        }
+        if self.eat("#") {
+            let index = self.string_index(strings)?;
+            let comparison = self.comparison()?;
+            return Ok(Condition::Count(index, comparison, self.number()?));
+        }
+        if self.peek() == Some('$') {
+            self.expect("$")?;
+            let index = self.string_index(strings)?;
+            if self.eat("at") {
+                return Ok(Condition::At(index, self.number()?));
+            }
+            if self.eat("in") {
+                self.expect("(")?;
+                let start = self.number()?;
+                self.expect("..")?;
+                let end = self.number()?;
+                self.expect(")")?;
+                return Ok(Condition::In(index, start, end));
+            }
+            return Ok(Condition::Present(index));
+        }
+
+        let quantifier = if self.eat("any") {
+            Quantifier::Any
+        } else if self.eat("all") {
+            Quantifier::All
+        } else if self.eat("none") {
+            Quantifier::None
+        } else if self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            Quantifier::AtLeast(self.number()? as usize)
+        } else {
+            return Err("expected a condition".to_string());
+        };
+        self.expect("of")?;
+        let set = if self.eat("them") {
+            (0..strings.len()).collect()
+        } else {
+            self.expect("(")?;
+            let mut set = Vec::new();
+            loop {
+                self.expect("$")?;
+                let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_' || c == '*');
+                let prefix = format!("${}", name.trim_end_matches('*'));
+                let members: Vec<usize> = (0..strings.len())
+                    .filter(|&i| {
+                        if name.ends_with('*') {
+                            strings[i].id.starts_with(&prefix)
+                        } else {
+                            strings[i].id == prefix
+                        }
+                    })
+                    .collect();
+                if members.is_empty() {
+                    return Err(format!("${} matches no string", name));
+                }
+                for member in members {
+                    if !set.contains(&member) {
+                        set.push(member);
+                    }
+                }
+                if !self.eat(",") {
+                    break;
+                }
+            }
+            self.expect(")")?;
+            set
+        };
+        if let Quantifier::AtLeast(n) = quantifier
+            && n > set.len()
+        {
+            return Err(format!("{} of a set of {} strings can never match", n, set.len()));
+        }
+        Ok(Condition::Of(quantifier, set))
+    }
+
+    fn string_index(&mut self, strings: &[YaraString]) -> Result<usize, String> {
+        let id = format!("${}", self.take_while(|c| c.is_ascii_alphanumeric() || c == '_'));
+        strings
+            .iter()
+            .position(|s| s.id == id)
+            .ok_or_else(|| format!("undefined string {}", id))
+    }
+
+    fn comparison(&mut self) -> Result<Comparison, String> {
+        for (token, comparison) in [
+            ("==", Comparison::Eq),
+            ("!=", Comparison::Ne),
+            ("<=", Comparison::Le),
+            (">=", Comparison::Ge),
+            ("<", Comparison::Lt),
+            (">", Comparison::Gt),
+        ] {
+            if self.eat(token) {
+                return Ok(comparison);
+            }
+        }
+        Err("expected a comparison".to_string())
+    }
+}
+
+fn literal_regex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!(r"\x{:02x}", b)).collect()
+}
+
+/// `4D 5A ?? ?A [2-4] (01 02 | 03) ~00`, whitespace-insensitive.
+fn hex_regex(hex: &str) -> Option<String> {
+    let chars: Vec<char> = hex.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut regex = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '[' => {
+                let end = i + chars[i..].iter().position(|&c| c == ']')?;
+                let range: String = chars[i + 1..end].iter().collect();
+                let number = |text: &str| text.parse::<usize>().ok();
+                regex.push_str(&match range.split_once('-') {
+                    None => format!(".{{{}}}", number(&range)?),
+                    Some(("", "")) => ".*?".to_string(),
+                    Some((min, "")) => format!(".{{{},}}?", number(min)?),
+                    Some(("", max)) => format!(".{{0,{}}}?", number(max)?),
+                    Some((min, max)) => format!(".{{{},{}}}?", number(min)?, number(max)?),
+                });
+                i = end + 1;
+            }
+            '(' => {
+                let mut depth = 0;
+                let mut end = i;
+                for (j, &c) in chars.iter().enumerate().skip(i) {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                end = j;
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                if depth != 0 {
+                    return None;
+                }
+                let inner: String = chars[i + 1..end].iter().collect();
+                let alternatives: Vec<String> = split_alternatives(&inner)
+                    .iter()
+                    .map(|a| hex_regex(a))
+                    .collect::<Option<_>>()?;
+                regex.push_str(&format!("(?:{})", alternatives.join("|")));
+                i = end + 1;
+            }
+            '~' => {
+                let pair = chars.get(i + 1..i + 3)?;
+                if pair == ['?', '?'] {
+                    return None;
+                }
+                regex.push_str(&format!("[^{}]", byte_regex(pair[0], pair[1])?.trim_matches(['[', ']'])));
+                i += 3;
+            }
+            _ => {
+                let pair = chars.get(i..i + 2)?;
+                regex.push_str(&byte_regex(pair[0], pair[1])?);
+                i += 2;
+            }
+        }
+    }
+    (!regex.is_empty()).then_some(regex)
+}
+
+/// Splits on top-level `|` only, so nested alternatives stay whole.
+fn split_alternatives(text: &str) -> Vec<String> {
+    let mut alternatives = vec![String::new()];
+    let mut depth = 0;
+    for c in text.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            '|' if depth == 0 => {
+                alternatives.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        alternatives.last_mut().unwrap().push(c);
+    }
+    alternatives
+}
+
+fn byte_regex(high: char, low: char) -> Option<String> {
+    Some(match (high, low) {
+        ('?', '?') => ".".to_string(),
+        ('?', low) => {
+            let low = low.to_digit(16)?;
+            let class: String = (0..16).map(|h| format!(r"\x{:02x}", h << 4 | low)).collect();
+            format!("[{}]", class)
+        }
+        (high, '?') => {
+            let high = high.to_digit(16)?;
+            format!(r"[\x{:02x}-\x{:02x}]", high << 4, high << 4 | 0xf)
+        }
+        (high, low) => format!(r"\x{:02x}", high.to_digit(16)? << 4 | low.to_digit(16)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(source: &str) -> Result<YaraRules, String> {
+        Parser::new(source).rules().map(|rules| YaraRules { rules })
+    }
+
+    fn matched(rules: &YaraRules, data: &[u8]) -> Vec<String> {
+        rules
+            .scan(data)
+            .into_iter()
+            .map(|m| m.rule.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn non_ascii_rule_name_is_a_parse_error() {
+        assert!(rules("rule café { condition: true }").is_err());
+        assert!(rules("rule r { condition: true }é").is_err());
+    }
+
+    #[test]
+    fn counts_overlapping_matches() {
+        let rules = rules(r#"rule twice { strings: $a = "aa" condition: #a == 2 }"#).unwrap();
+        assert_eq!(matched(&rules, b"aaa"), ["twice"]);
+        assert!(matched(&rules, b"aa").is_empty());
+    }
+
+    #[test]
+    fn fullword_is_checked_per_candidate() {
+        let rules =
+            rules(r#"rule word { strings: $a = "key" fullword condition: $a at 8 and #a == 1 }"#)
+                .unwrap();
+        assert_eq!(matched(&rules, b"keyring key"), ["word"]);
+        assert!(matched(&rules, b"keyring keys").is_empty());
+    }
+
+    #[test]
+    fn matches_hex_strings_and_sets() {
+        let rules = rules(
+            r#"
+            rule loader : tag {
+                meta:
+                    author = "test"
+                strings:
+                    $magic = { CA FE BA BE }
+                    $url1 = "http://" nocase
+                    $url2 = "https://" wide
+                condition:
+                    $magic at 0 and any of ($url*) and filesize < 1KB
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(matched(&rules, b"\xCA\xFE\xBA\xBE HTTP://x"), ["loader"]);
+        assert!(matched(&rules, b"\xCA\xFE\xBA\xBE https://x").is_empty());
+        assert!(matched(&rules, b" \xCA\xFE\xBA\xBE HTTP://x").is_empty());
+    }
+}
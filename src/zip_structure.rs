@@ -0,0 +1,581 @@
+use std::collections::HashMap;
+use std::io::Read;
+
+use flate2::read::DeflateDecoder;
+use flate2::Crc;
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x0706_4b50;
+
+const LOCAL_HEADER_LENGTH: usize = 30;
+const CENTRAL_HEADER_LENGTH: usize = 46;
+const END_OF_CENTRAL_DIRECTORY_LENGTH: usize = 22;
+
+const FLAG_ENCRYPTED: u16 = 0x0001;
+const FLAG_DATA_DESCRIPTOR: u16 = 0x0008;
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+
+const MAX_COMMENT_LENGTH: usize = 1024;
+const MAX_EXTRA_FIELD_LENGTH: usize = 1024;
+/// CRCs are checked until this much data has been inflated.
+const MAX_CRC_CHECK_BYTES: u64 = 512 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZipAnomaly {
+    PrependedData { length: u64, kind: Option<&'static str> },
+    TrailingData { length: u64 },
+    MissingEndOfCentralDirectory,
+    ArchiveComment { length: usize },
+    EntryCountMismatch { declared: u64, found: usize },
+    DuplicateEntry { name: String, count: usize },
+    ClassDirectory { name: String },
+    UnsafePath { name: String },
+    BadLocalHeader { name: String },
+    HeaderMismatch { name: String, field: &'static str },
+    CrcMismatch { name: String, expected: u32, actual: u32 },
+    EncryptedEntry { name: String },
+    EntryComment { name: String, length: usize },
+    ExtraField { name: String, length: usize, malformed: bool },
+}
+
+impl ZipAnomaly {
+    /// Short label used to group anomalies of the same kind in reports.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ZipAnomaly::PrependedData { .. } => "prepended data",
+            ZipAnomaly::TrailingData { .. } => "trailing data",
+            ZipAnomaly::MissingEndOfCentralDirectory => "missing central directory",
+            ZipAnomaly::ArchiveComment { .. } => "archive comment",
+            ZipAnomaly::EntryCountMismatch { .. } => "entry count",
+            ZipAnomaly::DuplicateEntry { .. } => "duplicate entries",
+            ZipAnomaly::ClassDirectory { .. } => ".class directories",
+            ZipAnomaly::UnsafePath { .. } => "unsafe paths",
+            ZipAnomaly::BadLocalHeader { .. } => "bad local headers",
+            ZipAnomaly::HeaderMismatch { .. } => "header mismatches",
+            ZipAnomaly::CrcMismatch { .. } => "CRC mismatches",
+            ZipAnomaly::EncryptedEntry { .. } => "encrypted entries",
+            ZipAnomaly::EntryComment { .. } => "entry comments",
+            ZipAnomaly::ExtraField { .. } => "extra fields",
+        }
+    }
+}
+
+impl std::fmt::Display for ZipAnomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ZipAnomaly::PrependedData { length, kind } => {
+                write!(f, "{} byte(s) before the first local header", length)?;
+                if let Some(kind) = kind {
+                    write!(f, " ({} polyglot)", kind)?;
+                }
+                Ok(())
+            }
+            ZipAnomaly::TrailingData { length } => {
+                write!(f, "{} byte(s) after the end of central directory", length)
+            }
+            ZipAnomaly::MissingEndOfCentralDirectory => {
+                write!(f, "No end of central directory record found")
+            }
+            ZipAnomaly::ArchiveComment { length } => {
+                write!(f, "Oversized archive comment ({} bytes)", length)
+            }
+            ZipAnomaly::EntryCountMismatch { declared, found } => write!(
+                f,
+                "Central directory declares {} entries but holds {}",
+                declared, found
+            ),
+            ZipAnomaly::DuplicateEntry { name, count } => {
+                write!(f, "Entry '{}' appears {} times", name, count)
+            }
+            ZipAnomaly::ClassDirectory { name } => {
+                write!(f, "Class name used as a directory entry: '{}'", name)
+            }
+            ZipAnomaly::UnsafePath { name } => write!(f, "Unsafe entry path '{}'", name),
+            ZipAnomaly::BadLocalHeader { name } => {
+                write!(f, "Local header of '{}' is missing or out of bounds", name)
+            }
+            ZipAnomaly::HeaderMismatch { name, field } => write!(
+                f,
+                "Local and central headers of '{}' disagree on {}",
+                name, field
+            ),
+            ZipAnomaly::CrcMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "CRC mismatch in '{}' (declared {:08x}, actual {:08x})",
+                name, expected, actual
+            ),
+            ZipAnomaly::EncryptedEntry { name } => {
+                write!(f, "Entry '{}' is marked encrypted", name)
+            }
+            ZipAnomaly::EntryComment { name, length } => {
+                write!(f, "Oversized comment on '{}' ({} bytes)", name, length)
+            }
+            ZipAnomaly::ExtraField {
+                name,
+                length,
+                malformed,
+            } => {
+                if *malformed {
+                    write!(f, "Malformed extra field on '{}' ({} bytes)", name, length)
+                } else {
+                    write!(f, "Oversized extra field on '{}' ({} bytes)", name, length)
+                }
+            }
+        }
+    }
+}
+
+struct CentralEntry {
+    name: String,
+    raw_name: Vec<u8>,
+    flags: u16,
+    method: u16,
+    crc: u32,
+    compressed_size: u64,
+    uncompressed_size: u64,
+    local_offset: u64,
+    extra_length: usize,
+    extra_well_formed: bool,
+    comment_length: usize,
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn u64_at(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset.checked_add(8)?)?;
+    Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Reads the raw central directory and local headers of a ZIP archive and
+/// reports what a well-formed JAR would not contain.
+pub fn analyze_zip_structure(data: &[u8]) -> Vec<ZipAnomaly> {
+    let mut anomalies = Vec::new();
+
+    let Some(eocd) = find_end_of_central_directory(data) else {
+        anomalies.push(ZipAnomaly::MissingEndOfCentralDirectory);
+        return anomalies;
+    };
+
+    let comment_length = u16_at(data, eocd + 20).unwrap_or(0) as usize;
+    if comment_length > MAX_COMMENT_LENGTH {
+        anomalies.push(ZipAnomaly::ArchiveComment {
+            length: comment_length,
+        });
+    }
+    let archive_end = eocd + END_OF_CENTRAL_DIRECTORY_LENGTH + comment_length;
+    if archive_end < data.len() {
+        anomalies.push(ZipAnomaly::TrailingData {
+            length: (data.len() - archive_end) as u64,
+        });
+    }
+
+    let mut declared_entries = u16_at(data, eocd + 10).unwrap_or(0) as u64;
+    let mut directory_size = u32_at(data, eocd + 12).unwrap_or(0) as u64;
+    let mut directory_offset = u32_at(data, eocd + 16).unwrap_or(0) as u64;
+    let mut directory_end = eocd as u64;
+    if let Some(zip64) = find_zip64_end_of_central_directory(data, eocd) {
+        declared_entries = u64_at(data, zip64 + 32).unwrap_or(declared_entries);
+        directory_size = u64_at(data, zip64 + 40).unwrap_or(directory_size);
+        directory_offset = u64_at(data, zip64 + 48).unwrap_or(directory_offset);
+        directory_end = zip64 as u64;
+    }
+
+    // Like the JDK, locate the central directory from the end of the file and
+    // treat any gap between where it is and where it claims to be as a prefix.
+    let directory_start = directory_end.saturating_sub(directory_size);
+    let prefix = directory_start.saturating_sub(directory_offset);
+
+    let entries = read_central_directory(data, directory_start as usize, directory_end as usize);
+    if entries.len() as u64 != declared_entries {
+        anomalies.push(ZipAnomaly::EntryCountMismatch {
+            declared: declared_entries,
+            found: entries.len(),
+        });
+    }
+
+    let first_local = entries
+        .iter()
+        .map(|entry| entry.local_offset.saturating_add(prefix))
+        .min()
+        .unwrap_or(directory_start);
+    if first_local > 0 {
+        anomalies.push(ZipAnomaly::PrependedData {
+            length: first_local,
+            kind: sniff_prefix(data),
+        });
+    }
+
+    let mut name_counts: HashMap<&str, usize> = HashMap::new();
+    for entry in &entries {
+        *name_counts.entry(entry.name.as_str()).or_default() += 1;
+    }
+    let mut duplicates: Vec<(&str, usize)> = name_counts
+        .into_iter()
+        .filter(|(_, count)| *count > 1)
+        .collect();
+    duplicates.sort_unstable();
+    anomalies.extend(duplicates.into_iter().map(|(name, count)| ZipAnomaly::DuplicateEntry {
+        name: name.to_string(),
+        count,
+    }));
+
+    let mut crc_budget = MAX_CRC_CHECK_BYTES;
+    for entry in &entries {
+        check_entry(data, entry, prefix, &mut crc_budget, &mut anomalies);
+    }
+
+    anomalies
+}
+
+fn find_end_of_central_directory(data: &[u8]) -> Option<usize> {
+    let last = data.len().checked_sub(END_OF_CENTRAL_DIRECTORY_LENGTH)?;
+    let first = last.saturating_sub(u16::MAX as usize);
+    (first..=last).rev().find(|&pos| {
+        u32_at(data, pos) == Some(END_OF_CENTRAL_DIRECTORY_SIGNATURE)
+            && u16_at(data, pos + 20).is_some_and(|len| {
+                pos + END_OF_CENTRAL_DIRECTORY_LENGTH + len as usize <= data.len()
+            })
+    })
+}
+
+fn find_zip64_end_of_central_directory(data: &[u8], eocd: usize) -> Option<usize> {
+    let locator = eocd.checked_sub(20)?;
+    if u32_at(data, locator)? != ZIP64_LOCATOR_SIGNATURE {
+        return None;
+    }
+    let declared = u64_at(data, locator + 8)? as usize;
+    // The record sits right before its locator even when the file has a prefix.
+    let position = if u32_at(data, declared) == Some(ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE) {
+        declared
+    } else {
+        locator.checked_sub(56)?
+    };
+    (u32_at(data, position)? == ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE).then_some(position)
+}
+
+fn read_central_directory(data: &[u8], start: usize, end: usize) -> Vec<CentralEntry> {
+    let mut entries = Vec::new();
+    let mut pos = start;
+    while pos + CENTRAL_HEADER_LENGTH <= end.min(data.len())
+        && u32_at(data, pos) == Some(CENTRAL_HEADER_SIGNATURE)
+    {
+        let header = |offset: usize| u16_at(data, pos + offset).unwrap_or(0);
+        let name_length = header(28) as usize;
+        let extra_length = header(30) as usize;
+        let comment_length = header(32) as usize;
+        let name_start = pos + CENTRAL_HEADER_LENGTH;
+        let extra_start = name_start + name_length;
+        let Some(raw_name) = data.get(name_start..extra_start) else {
+            break;
+        };
+        let extra = data
+            .get(extra_start..extra_start + extra_length)
+            .unwrap_or_default();
+
+        let mut entry = CentralEntry {
+            name: String::from_utf8_lossy(raw_name).into_owned(),
+            raw_name: raw_name.to_vec(),
+            flags: header(8),
+            method: header(10),
+            crc: u32_at(data, pos + 16).unwrap_or(0),
+            compressed_size: u32_at(data, pos + 20).unwrap_or(0) as u64,
+            uncompressed_size: u32_at(data, pos + 24).unwrap_or(0) as u64,
+            local_offset: u32_at(data, pos + 42).unwrap_or(0) as u64,
+            extra_length,
+            extra_well_formed: extra_fields_well_formed(extra),
+            comment_length,
+        };
+        apply_zip64_extra(&mut entry, extra);
+
+        entries.push(entry);
+        pos = extra_start + extra_length + comment_length;
+    }
+    entries
+}
+
+/// Replaces saturated 32-bit fields with their values from the ZIP64 extra
+/// field, which lists only the saturated ones, in a fixed order.
+fn apply_zip64_extra(entry: &mut CentralEntry, extra: &[u8]) {
+    let mut pos = 0;
+    while let (Some(id), Some(size)) = (u16_at(extra, pos), u16_at(extra, pos + 2)) {
+        let body = extra.get(pos + 4..pos + 4 + size as usize).unwrap_or_default();
+        if id == 0x0001 {
+            let mut values = body.chunks_exact(8).map(|v| u64_at(v, 0).unwrap_or(0));
+            for field in [
+                &mut entry.uncompressed_size,
+                &mut entry.compressed_size,
+                &mut entry.local_offset,
+            ] {
+                if *field == u32::MAX as u64 {
+                    match values.next() {
+                        Some(value) => *field = value,
+                        None => break,
+                    }
+                }
+            }
+            return;
+        }
+        pos += 4 + size as usize;
+    }
+}
+
+/// Extra data must be a sequence of `(id, size, body)` records that ends
+/// exactly at its declared length.
+fn extra_fields_well_formed(extra: &[u8]) -> bool {
+    let mut pos = 0;
+    while pos < extra.len() {
+        let Some(size) = u16_at(extra, pos + 2) else {
+            return false;
+        };
+        pos += 4 + size as usize;
+    }
+    pos == extra.len()
+}
+
+fn check_entry(
+    data: &[u8],
+    entry: &CentralEntry,
+    prefix: u64,
+    crc_budget: &mut u64,
+    anomalies: &mut Vec<ZipAnomaly>,
+) {
+    let name = || entry.name.clone();
+
+    if entry.name.ends_with(".class/") {
+        anomalies.push(ZipAnomaly::ClassDirectory { name: name() });
+    }
+    if is_unsafe_path(&entry.raw_name) {
+        anomalies.push(ZipAnomaly::UnsafePath { name: name() });
+    }
+    if entry.flags & FLAG_ENCRYPTED != 0 {
+        anomalies.push(ZipAnomaly::EncryptedEntry { name: name() });
+    }
+    if entry.comment_length > MAX_COMMENT_LENGTH {
+        anomalies.push(ZipAnomaly::EntryComment {
+            name: name(),
+            length: entry.comment_length,
+        });
+    }
+    if !entry.extra_well_formed || entry.extra_length > MAX_EXTRA_FIELD_LENGTH {
+        anomalies.push(ZipAnomaly::ExtraField {
+            name: name(),
+            length: entry.extra_length,
+            malformed: !entry.extra_well_formed,
+        });
+    }
+
+    let Some(local) = usize::try_from(entry.local_offset.saturating_add(prefix))
+        .ok()
+        .filter(|&local| u32_at(data, local) == Some(LOCAL_HEADER_SIGNATURE))
+        .filter(|&local| local + LOCAL_HEADER_LENGTH <= data.len())
+    else {
+        anomalies.push(ZipAnomaly::BadLocalHeader { name: name() });
+        return;
+    };
+
+    let local_flags = u16_at(data, local + 6).unwrap_or(0);
+    let local_method = u16_at(data, local + 8).unwrap_or(0);
+    let local_name_length = u16_at(data, local + 26).unwrap_or(0) as usize;
+    let local_extra_length = u16_at(data, local + 28).unwrap_or(0) as usize;
+    let name_start = local + LOCAL_HEADER_LENGTH;
+    let local_name = data.get(name_start..name_start + local_name_length);
+
+    let mut mismatch = |field| {
+        anomalies.push(ZipAnomaly::HeaderMismatch { name: name(), field });
+    };
+    if local_name != Some(entry.raw_name.as_slice()) {
+        mismatch("name");
+    }
+    if local_method != entry.method {
+        mismatch("compression method");
+    }
+    if (local_flags ^ entry.flags) & FLAG_ENCRYPTED != 0 {
+        mismatch("encryption flag");
+    }
+    // With a data descriptor the local header legitimately leaves these zero.
+    if local_flags & FLAG_DATA_DESCRIPTOR == 0 {
+        let local_crc = u32_at(data, local + 14).unwrap_or(0);
+        let local_compressed = u32_at(data, local + 18).unwrap_or(0);
+        let local_uncompressed = u32_at(data, local + 22).unwrap_or(0);
+        if local_crc != entry.crc {
+            mismatch("CRC");
+        }
+        if local_compressed != u32::MAX
+            && (local_compressed as u64 != entry.compressed_size
+                || local_uncompressed as u64 != entry.uncompressed_size)
+        {
+            mismatch("sizes");
+        }
+    }
+
+    if entry.flags & FLAG_ENCRYPTED != 0 || entry.uncompressed_size > *crc_budget {
+        return;
+    }
+    let data_start = name_start + local_name_length + local_extra_length;
+    let Some(compressed) = usize::try_from(entry.compressed_size)
+        .ok()
+        .and_then(|size| data.get(data_start..data_start.checked_add(size)?))
+    else {
+        anomalies.push(ZipAnomaly::BadLocalHeader { name: name() });
+        return;
+    };
+    if let Some(actual) = entry_crc(compressed, entry.method, entry.uncompressed_size) {
+        *crc_budget -= entry.uncompressed_size;
+        if actual != entry.crc {
+            anomalies.push(ZipAnomaly::CrcMismatch {
+                name: name(),
+                expected: entry.crc,
+                actual,
+            });
+        }
+    }
+}
+
+fn entry_crc(compressed: &[u8], method: u16, uncompressed_size: u64) -> Option<u32> {
+    let mut crc = Crc::new();
+    match method {
+        METHOD_STORED => crc.update(compressed),
+        METHOD_DEFLATED => {
+            let mut decoder = DeflateDecoder::new(compressed).take(uncompressed_size);
+            let mut buffer = [0u8; 64 * 1024];
+            loop {
+                let read = decoder.read(&mut buffer).ok()?;
+                if read == 0 {
+                    break;
+                }
+                crc.update(&buffer[..read]);
+            }
+        }
+        _ => return None,
+    }
+    Some(crc.sum())
+}
+
+fn is_unsafe_path(raw_name: &[u8]) -> bool {
+    raw_name.starts_with(b"/")
+        || raw_name.contains(&b'\\')
+        || raw_name.iter().any(|&b| b < 0x20)
+        || raw_name.get(1) == Some(&b':')
+        || raw_name.split(|&b| b == b'/').any(|part| part == b"..")
+}
+
+/// Names the format of data glued in front of the archive, if recognisable.
+fn sniff_prefix(data: &[u8]) -> Option<&'static str> {
+    match data.get(..4)? {
+        [b'M', b'Z', ..] => Some("PE executable"),
+        [0x7f, b'E', b'L', b'F'] => Some("ELF executable"),
+        [b'#', b'!', ..] => Some("shell script"),
+        [b'%', b'P', b'D', b'F'] => Some("PDF"),
+        [0x89, b'P', b'N', b'G'] => Some("PNG"),
+        [b'G', b'I', b'F', b'8'] => Some("GIF"),
+        [0xff, 0xd8, 0xff, _] => Some("JPEG"),
+        [0xca, 0xfe, 0xba, 0xbe] => Some("class file"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::write::SimpleFileOptions;
+    use zip::{CompressionMethod, ZipWriter};
+
+    use super::*;
+    use crate::test_support::jar;
+
+    fn stored(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in entries {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn replace_all(data: &mut [u8], from: &[u8], to: &[u8]) {
+        let mut pos = 0;
+        while let Some(found) = data[pos..].windows(from.len()).position(|w| w == from) {
+            data[pos + found..pos + found + to.len()].copy_from_slice(to);
+            pos += found + to.len();
+        }
+    }
+
+    #[test]
+    fn well_formed_archive_has_no_anomalies() {
+        let archive = jar(&[("p/A.class", b"\xCA\xFE\xBA\xBE"), ("a.txt", b"hello")]);
+        assert_eq!(analyze_zip_structure(&archive), []);
+    }
+
+    #[test]
+    fn reports_prepended_executable() {
+        let mut data = b"MZ\x90\x00stub".to_vec();
+        data.extend(jar(&[("a.txt", b"hello")]));
+        assert_eq!(
+            analyze_zip_structure(&data),
+            [ZipAnomaly::PrependedData {
+                length: 8,
+                kind: Some("PE executable"),
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_duplicate_entries() {
+        let mut archive = jar(&[("a.txt", b"one"), ("b.txt", b"two")]);
+        replace_all(&mut archive, b"b.txt", b"a.txt");
+        assert_eq!(
+            analyze_zip_structure(&archive),
+            [ZipAnomaly::DuplicateEntry {
+                name: "a.txt".to_string(),
+                count: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_crc_mismatch() {
+        let mut archive = stored(&[("a.txt", b"hello world")]);
+        replace_all(&mut archive, b"hello world", b"hellO world");
+        let anomalies = analyze_zip_structure(&archive);
+        assert!(
+            matches!(anomalies.as_slice(), [ZipAnomaly::CrcMismatch { name, .. }] if name == "a.txt"),
+            "{:?}",
+            anomalies
+        );
+    }
+
+    #[test]
+    fn malformed_archives_do_not_panic() {
+        assert_eq!(
+            analyze_zip_structure(b"not a zip"),
+            [ZipAnomaly::MissingEndOfCentralDirectory]
+        );
+        let archive = stored(&[("a.txt", b"hello"), ("../b.txt", b"world")]);
+        assert!(
+            analyze_zip_structure(&archive).contains(&ZipAnomaly::UnsafePath {
+                name: "../b.txt".to_string(),
+            })
+        );
+        for length in 0..archive.len() {
+            analyze_zip_structure(&archive[..length]);
+        }
+    }
+}